@@ -1,6 +1,13 @@
 static CONFIG: &[(&str, &str)] = &[
-	// Can be either "LINUX" or "ZAP"
-	("FONT", "ZAP"),
+	// Can be either "MIRROR" (copy console output to COM1) or "OFF"
+	("SERIAL", "MIRROR"),
+	// Most verbose log level compiled in: "ERROR", "WARN", "INFO", "DEBUG" or
+	// "TRACE"
+	("LOG_LEVEL", "INFO"),
+	// Can be either "ON" (show a logo and progress bar while booting) or "OFF"
+	("SPLASH", "ON"),
+	// Keyboard layout used until something picks another: "US", "UK" or "DE"
+	("KEYMAP", "US"),
 ];
 
 fn main() {
@@ -1,609 +1,479 @@
 #![deny(missing_docs)]
 #![no_std]
 
-//! zap_font provides access to the 8x16 Zap font as a byte array.
-
-/// The font's size in pixels
-pub static FONT_DIMENSIONS: (u8, u8) = (8, 16);
-
-/// The byte array containing the font.
-pub static FONT: &[u8] = &[
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00000000, 0b00001000, 0b00001000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100, 0b00100100,
-	0b01111110, 0b00100100, 0b00100100, 0b01111110, 0b00100100, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00001000, 0b00001000, 0b00111110, 0b01001001, 0b01001000, 0b01001000,
-	0b00111110, 0b00001001, 0b00001001, 0b01001001, 0b00111110, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00110001,
-	0b01001010, 0b01001010, 0b00110100, 0b00001000, 0b00001000, 0b00010110,
-	0b00101001, 0b00101001, 0b01000110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00100100,
-	0b00100100, 0b00011000, 0b00101000, 0b01000101, 0b01000010, 0b01000110,
-	0b00111001, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00001000, 0b00001000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000100, 0b00001000,
-	0b00001000, 0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00010000,
-	0b00010000, 0b00001000, 0b00001000, 0b00000100, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00100000, 0b00010000, 0b00010000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00010000,
-	0b00010000, 0b00100000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00001000, 0b01001001, 0b00101010,
-	0b00011100, 0b00101010, 0b01001001, 0b00001000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00001000, 0b01111111, 0b00001000,
-	0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00001000, 0b00001000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b01111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000010, 0b00000010, 0b00000100,
-	0b00000100, 0b00001000, 0b00001000, 0b00010000, 0b00010000, 0b00100000,
-	0b00100000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01001010,
-	0b01010010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00011000, 0b00101000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b00000010, 0b00000100, 0b00001000, 0b00010000, 0b00100000, 0b01000000,
-	0b01111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b00000010, 0b00011100,
-	0b00000010, 0b00000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000100,
-	0b00001100, 0b00010100, 0b00100100, 0b01000100, 0b01000100, 0b01111110,
-	0b00000100, 0b00000100, 0b00000100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01111110, 0b01000000, 0b01000000,
-	0b01000000, 0b01111100, 0b00000010, 0b00000010, 0b00000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00011100, 0b00100000, 0b01000000, 0b01000000, 0b01111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01111110,
-	0b00000010, 0b00000010, 0b00000100, 0b00000100, 0b00001000, 0b00001000,
-	0b00010000, 0b00010000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111110, 0b00000010, 0b00000010, 0b00000100, 0b00111000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00001000, 0b00001000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00001000, 0b00001000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000100, 0b00001000, 0b00010000, 0b00100000,
-	0b01000000, 0b00100000, 0b00010000, 0b00001000, 0b00000100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b01111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100000, 0b00010000,
-	0b00001000, 0b00000100, 0b00000010, 0b00000100, 0b00001000, 0b00010000,
-	0b00100000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b00000010, 0b00000100,
-	0b00001000, 0b00001000, 0b00000000, 0b00001000, 0b00001000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00011100,
-	0b00100010, 0b01001010, 0b01010110, 0b01010010, 0b01010010, 0b01010010,
-	0b01001110, 0b00100000, 0b00011110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00100100,
-	0b01000010, 0b01000010, 0b01111110, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111100, 0b01000010, 0b01000010, 0b01000010, 0b01111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000000, 0b01000000, 0b01000000, 0b01000000,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01111000, 0b01000100, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000100,
-	0b01111000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111110, 0b01000000, 0b01000000, 0b01000000, 0b01111100,
-	0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b01111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01111110,
-	0b01000000, 0b01000000, 0b01000000, 0b01111100, 0b01000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000000, 0b01000000, 0b01001110, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01111110,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111110,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011111, 0b00000100, 0b00000100,
-	0b00000100, 0b00000100, 0b00000100, 0b00000100, 0b01000100, 0b01000100,
-	0b00111000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000100, 0b01001000, 0b01010000, 0b01100000,
-	0b01100000, 0b01010000, 0b01001000, 0b01000100, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000001, 0b01100011, 0b01100011,
-	0b01010101, 0b01010101, 0b01001001, 0b01001001, 0b01000001, 0b01000001,
-	0b01000001, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000010, 0b01100010, 0b01100010, 0b01010010, 0b01010010,
-	0b01001010, 0b01001010, 0b01000110, 0b01000110, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01111100, 0b01000000, 0b01000000, 0b01000000,
-	0b01000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01011010, 0b01100110, 0b00111100, 0b00000011,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01111100, 0b01001000, 0b01000100,
-	0b01000100, 0b01000010, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000000, 0b00110000, 0b00001100, 0b00000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111111, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000001, 0b01000001, 0b01000001,
-	0b00100010, 0b00100010, 0b00100010, 0b00010100, 0b00010100, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000001, 0b01000001, 0b01000001, 0b01001001, 0b01001001,
-	0b01010101, 0b01010101, 0b01100011, 0b01100011, 0b01000001, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01000010,
-	0b01000010, 0b00100100, 0b00100100, 0b00011000, 0b00011000, 0b00100100,
-	0b00100100, 0b01000010, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000001, 0b01000001, 0b00100010,
-	0b00100010, 0b00010100, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111110, 0b00000010, 0b00000010, 0b00000100, 0b00001000,
-	0b00010000, 0b00100000, 0b01000000, 0b01000000, 0b01111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00011100, 0b00010000,
-	0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00010000,
-	0b00010000, 0b00010000, 0b00010000, 0b00011100, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00100000, 0b00100000, 0b00010000,
-	0b00010000, 0b00001000, 0b00001000, 0b00000100, 0b00000100, 0b00000010,
-	0b00000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00111000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00111000,
-	0b00000000, 0b00000000, 0b00000000, 0b00001000, 0b00010100, 0b00100010,
-	0b01000001, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111111, 0b00000000, 0b00000000, 0b00000000, 0b00010000,
-	0b00010000, 0b00010000, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b00000010, 0b00111110, 0b01000010,
-	0b01000010, 0b01000110, 0b00111010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b01000000, 0b01000000, 0b01000000, 0b01011100,
-	0b01100010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01100010,
-	0b01011100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000000,
-	0b01000000, 0b01000000, 0b01000000, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000010, 0b00000010,
-	0b00000010, 0b00111010, 0b01000110, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000110, 0b00111010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01111110, 0b01000000, 0b01000000, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00001110, 0b00010000, 0b00010000, 0b00010000, 0b01111100, 0b00010000,
-	0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111010, 0b01000110, 0b01000010, 0b01000010, 0b01000010,
-	0b01000110, 0b00111010, 0b00000010, 0b00000010, 0b01000010, 0b00111100,
-	0b00000000, 0b00000000, 0b01000000, 0b01000000, 0b01000000, 0b01011100,
-	0b01100010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00001000, 0b00001000, 0b00000000, 0b00011000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000100, 0b00000100,
-	0b00000000, 0b00001100, 0b00000100, 0b00000100, 0b00000100, 0b00000100,
-	0b00000100, 0b00000100, 0b00000100, 0b00000100, 0b01000100, 0b00111000,
-	0b00000000, 0b00000000, 0b01000000, 0b01000000, 0b01000000, 0b01000100,
-	0b01001000, 0b01010000, 0b01100000, 0b01010000, 0b01001000, 0b01000100,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00011000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01110110, 0b01001001, 0b01001001, 0b01001001, 0b01001001,
-	0b01001001, 0b01001001, 0b01001001, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01011100,
-	0b01100010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01011100, 0b01100010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01100010, 0b01011100, 0b01000000, 0b01000000, 0b01000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111010,
-	0b01000110, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000110,
-	0b00111010, 0b00000010, 0b00000010, 0b00000010, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01011100, 0b01100010, 0b01000000,
-	0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000000, 0b00110000, 0b00001100,
-	0b00000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00010000, 0b00010000, 0b00010000, 0b00010000,
-	0b01111100, 0b00010000, 0b00010000, 0b00010000, 0b00010000, 0b00010000,
-	0b00001110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000001, 0b01000001, 0b01000001, 0b00100010, 0b00100010,
-	0b00010100, 0b00010100, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01000001,
-	0b01000001, 0b01001001, 0b01001001, 0b01001001, 0b01001001, 0b01001001,
-	0b00110110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000010, 0b01000010, 0b00100100,
-	0b00011000, 0b00011000, 0b00100100, 0b01000010, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000110, 0b00111010, 0b00000010, 0b00000010, 0b01000010, 0b00111100,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01111110,
-	0b00000010, 0b00000100, 0b00001000, 0b00010000, 0b00100000, 0b01000000,
-	0b01111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000110, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00110000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00000110,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00110000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00000110, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00110000, 0b00000000, 0b00000000, 0b00000000, 0b00110001,
-	0b01001001, 0b01000110, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00111100, 0b00111100, 0b00011000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00110000, 0b00001100, 0b00000000, 0b00011000, 0b00100100, 0b00100100,
-	0b01000010, 0b01000010, 0b01111110, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00001100, 0b00110000,
-	0b00000000, 0b00011000, 0b00100100, 0b00100100, 0b01000010, 0b01000010,
-	0b01111110, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000, 0b00011000,
-	0b00100100, 0b00100100, 0b01000010, 0b01000010, 0b01111110, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00110010, 0b01001100, 0b00000000, 0b00011000, 0b00100100, 0b00100100,
-	0b01000010, 0b01000010, 0b01111110, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100,
-	0b00000000, 0b00011000, 0b00100100, 0b00100100, 0b01000010, 0b01000010,
-	0b01111110, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00100100, 0b00011000,
-	0b00100100, 0b00100100, 0b01000010, 0b01000010, 0b01111110, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011111, 0b00101000, 0b01001000,
-	0b01001000, 0b01111110, 0b01001000, 0b01001000, 0b01001000, 0b01001000,
-	0b01001111, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01000010, 0b01000010, 0b00111100, 0b00001000,
-	0b00001000, 0b00110000, 0b00110000, 0b00001100, 0b00000000, 0b01111110,
-	0b01000000, 0b01000000, 0b01000000, 0b01111100, 0b01000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00001100, 0b00110000, 0b00000000, 0b01111110, 0b01000000, 0b01000000,
-	0b01000000, 0b01111100, 0b01000000, 0b01000000, 0b01000000, 0b01000000,
-	0b01111110, 0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100,
-	0b00000000, 0b01111110, 0b01000000, 0b01000000, 0b01000000, 0b01111100,
-	0b01000000, 0b01000000, 0b01000000, 0b01000000, 0b01111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00100100, 0b00100100, 0b00000000, 0b01111110,
-	0b01000000, 0b01000000, 0b01000000, 0b01111100, 0b01000000, 0b01000000,
-	0b01000000, 0b01000000, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00110000, 0b00001100, 0b00000000, 0b00111110, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00111110, 0b00000000, 0b00000000, 0b00000000, 0b00000110, 0b00011000,
-	0b00000000, 0b00111110, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000, 0b00111110,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00100010, 0b00100010, 0b00000000, 0b00111110, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01111000, 0b01000100, 0b01000010, 0b01000010, 0b11110010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000100, 0b01111000, 0b00000000,
-	0b00000000, 0b00000000, 0b00110010, 0b01001100, 0b00000000, 0b01000010,
-	0b01100010, 0b01100010, 0b01010010, 0b01010010, 0b01001010, 0b01001010,
-	0b01000110, 0b01000110, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00110000, 0b00001100, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00001100, 0b00110000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00110010, 0b01001100, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00100010, 0b00010100, 0b00001000, 0b00010100,
-	0b00100010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000010, 0b00111010, 0b01000100, 0b01000110,
-	0b01001010, 0b01001010, 0b01010010, 0b01010010, 0b01100010, 0b00100010,
-	0b01011100, 0b01000000, 0b00000000, 0b00000000, 0b00110000, 0b00001100,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00001100, 0b00110000, 0b00000000, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00011000, 0b00100100, 0b00000000, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000110, 0b00011000, 0b00000000, 0b01000001,
-	0b01000001, 0b00100010, 0b00100010, 0b00010100, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b01000000, 0b01000000, 0b01111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01111100, 0b01000000,
-	0b01000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111000, 0b01000100, 0b01001100, 0b01010000, 0b01010000,
-	0b01001100, 0b01000010, 0b01000010, 0b01010010, 0b01001100, 0b00000000,
-	0b00000000, 0b00000000, 0b10101010, 0b01010101, 0b10101010, 0b01010101,
-	0b10101010, 0b01010101, 0b10101010, 0b01010101, 0b10101010, 0b01010101,
-	0b10101010, 0b01010101, 0b10101010, 0b01010101, 0b10101010, 0b01010101,
-	0b00000000, 0b00000000, 0b00000000, 0b00001000, 0b00001000, 0b00000000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00111110, 0b01001001, 0b01001000,
-	0b01001000, 0b01001000, 0b01001000, 0b01001001, 0b00111110, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00011100,
-	0b00100000, 0b00100000, 0b00100000, 0b01111000, 0b00100000, 0b00100000,
-	0b00100000, 0b00100010, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001110, 0b00010001,
-	0b00100000, 0b01111110, 0b00100000, 0b01111100, 0b00100000, 0b00010001,
-	0b00001110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000001, 0b01000001, 0b00100010, 0b00010100, 0b00001000,
-	0b00111110, 0b00001000, 0b00111110, 0b00001000, 0b00001000, 0b00000000,
-	0b00000000, 0b00000000, 0b00100100, 0b00011000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000000, 0b00110000, 0b00001100, 0b00000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00011100, 0b00100010, 0b00100000, 0b00011000,
-	0b00100100, 0b00100010, 0b00100010, 0b00010010, 0b00001100, 0b00000010,
-	0b00100010, 0b00011100, 0b00000000, 0b00000000, 0b00000000, 0b00100100,
-	0b00011000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000000,
-	0b00110000, 0b00001100, 0b00000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b10011001, 0b10100101, 0b10100001, 0b10100001, 0b10100101,
-	0b10011001, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00111000, 0b00000100, 0b00111100, 0b01000100,
-	0b00111100, 0b00000000, 0b01111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00010010, 0b00100100,
-	0b01001000, 0b00100100, 0b00010010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b01111110, 0b00000010,
-	0b00000010, 0b00000010, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100010,
-	0b00011100, 0b00100010, 0b00100010, 0b00100010, 0b00011100, 0b00100010,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b10111001, 0b10100101, 0b10100101,
-	0b10111001, 0b10101001, 0b10100101, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00100100, 0b00011000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00001000, 0b00001000, 0b01111111, 0b00001000,
-	0b00001000, 0b00001000, 0b00000000, 0b01111111, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00111000, 0b01000100,
-	0b00000100, 0b00011000, 0b00100000, 0b01000000, 0b01111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00111000, 0b01000100, 0b00000100, 0b00111000,
-	0b00000100, 0b01000100, 0b00111000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00011000,
-	0b00000000, 0b01111110, 0b00000010, 0b00000010, 0b00000100, 0b00001000,
-	0b00010000, 0b00100000, 0b01000000, 0b01000000, 0b01111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01100110, 0b01011010, 0b01000000, 0b01000000, 0b01000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111110, 0b01111010, 0b01111010,
-	0b01111010, 0b01111010, 0b00111010, 0b00001010, 0b00001010, 0b00001010,
-	0b00001010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00011000, 0b00000000,
-	0b00000000, 0b01111110, 0b00000010, 0b00000100, 0b00001000, 0b00010000,
-	0b00100000, 0b01000000, 0b01111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00010000, 0b00110000, 0b01010000, 0b00010000,
-	0b00010000, 0b00010000, 0b01111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00111000, 0b01000100, 0b01000100, 0b01000100, 0b00111000, 0b00000000,
-	0b01111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b01001000, 0b00100100, 0b00010010, 0b00100100,
-	0b01001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00110111, 0b01001000, 0b01001000,
-	0b01001000, 0b01001110, 0b01001000, 0b01001000, 0b01001000, 0b01001000,
-	0b00110111, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00110110, 0b01001001, 0b01001001,
-	0b01001111, 0b01001000, 0b01001000, 0b01001001, 0b00110110, 0b00000000,
-	0b00000000, 0b00000000, 0b00100010, 0b00100010, 0b00000000, 0b01000001,
-	0b01000001, 0b00100010, 0b00100010, 0b00010100, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00010000, 0b00010000, 0b00000000,
-	0b00010000, 0b00010000, 0b00100000, 0b01000000, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b11111111, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00001111, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b11111000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001111, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b11111000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001111, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b11111000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b11111111, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b11111111, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b11111111, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b10001000, 0b00100010, 0b10001000, 0b00100010, 0b10001000, 0b00100010,
-	0b10001000, 0b00100010, 0b10001000, 0b00100010, 0b10001000, 0b00100010,
-	0b10001000, 0b00100010, 0b10001000, 0b00100010, 0b11111111, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b11111111,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b11111111, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b11111111, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b11111111, 0b00000000, 0b11111111,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00011111,
-	0b00010000, 0b00010111, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b11111100, 0b00000100, 0b11110100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010111, 0b00010000, 0b00011111, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b11110100,
-	0b00000100, 0b11111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010111, 0b00010000, 0b00010111,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b11110100, 0b00000100, 0b11110100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b11111111,
-	0b00000000, 0b11110111, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b11110111, 0b00000000, 0b11111111,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b00010100,
-	0b00010100, 0b11110111, 0b00000000, 0b11110111, 0b00010100, 0b00010100,
-	0b00010100, 0b00010100, 0b00010100, 0b00010100, 0b11111111, 0b11111111,
-	0b11111111, 0b11111111, 0b11111111, 0b11111111, 0b11111111, 0b11111111,
-	0b11111111, 0b11111111, 0b11111111, 0b11111111, 0b11111111, 0b11111111,
-	0b11111111, 0b11111111, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001000, 0b00011100, 0b00101010, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00101010, 0b00011100, 0b00001000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00010000, 0b00100000,
-	0b01111111, 0b00100000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00001000, 0b00000100, 0b11111110, 0b00000100,
-	0b00001000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00110000, 0b00001100, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b00000010, 0b00111110, 0b01000010, 0b01000010, 0b01000110,
-	0b00111010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001100,
-	0b00110000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b00000010,
-	0b00111110, 0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b00000010, 0b00111110, 0b01000010,
-	0b01000010, 0b01000110, 0b00111010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00110010, 0b01001100, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b00000010, 0b00111110, 0b01000010, 0b01000010, 0b01000110,
-	0b00111010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b00000010,
-	0b00111110, 0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000000,
-	0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00100100, 0b00011000,
-	0b00000000, 0b00111100, 0b01000010, 0b00000010, 0b00111110, 0b01000010,
-	0b01000010, 0b01000110, 0b00111010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00110110,
-	0b01001001, 0b00001001, 0b00111111, 0b01001000, 0b01001000, 0b01001001,
-	0b00110110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000000,
-	0b01000000, 0b01000000, 0b01000000, 0b01000010, 0b00111100, 0b00001000,
-	0b00001000, 0b00110000, 0b00000000, 0b00110000, 0b00001100, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01111110, 0b01000000,
-	0b01000000, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00001100, 0b00110000, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01111110, 0b01000000, 0b01000000, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00011000,
-	0b00100100, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01111110, 0b01000000, 0b01000000, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00100100, 0b00100100, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01111110, 0b01000000,
-	0b01000000, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00110000, 0b00001100, 0b00000000, 0b00000000, 0b00011000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001100,
-	0b00110000, 0b00000000, 0b00000000, 0b00011000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00111110, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000,
-	0b00000000, 0b00011000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00001000, 0b00001000, 0b00111110, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00100100, 0b00100100, 0b00000000, 0b00000000, 0b00011000,
-	0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000, 0b00001000,
-	0b00111110, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b01100110, 0b00011000, 0b01100100, 0b00000010, 0b00111010, 0b01000110,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00110010, 0b01001100, 0b00000000,
-	0b00000000, 0b01011100, 0b01100010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00110000, 0b00001100, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00001100,
-	0b00110000, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00011000, 0b00100100, 0b00000000,
-	0b00000000, 0b00111100, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b00111100, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00110010, 0b01001100, 0b00000000, 0b00000000, 0b00111100,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b00111100, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b00111100, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b00111100, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000000,
-	0b00010000, 0b00010000, 0b00000000, 0b00000000, 0b01111100, 0b00000000,
-	0b00000000, 0b00010000, 0b00010000, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00000010, 0b00111100,
-	0b01000110, 0b01001010, 0b01001010, 0b01010010, 0b01010010, 0b01100010,
-	0b00111100, 0b01000000, 0b00000000, 0b00000000, 0b00000000, 0b00110000,
-	0b00001100, 0b00000000, 0b00000000, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00001100, 0b00110000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000110, 0b00111010, 0b00000000, 0b00000000, 0b00000000,
-	0b00000000, 0b00011000, 0b00100100, 0b00000000, 0b00000000, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000110,
-	0b00111010, 0b00000000, 0b00000000, 0b00000000, 0b00000000, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000000,
-	0b00000000, 0b00000000, 0b00000000, 0b00001100, 0b00110000, 0b00000000,
-	0b00000000, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01000010,
-	0b01000110, 0b00111010, 0b00000010, 0b00000010, 0b01000010, 0b00111100,
-	0b00000000, 0b00000000, 0b01000000, 0b01000000, 0b01000000, 0b01011100,
-	0b01100010, 0b01000010, 0b01000010, 0b01000010, 0b01000010, 0b01100010,
-	0b01011100, 0b01000000, 0b01000000, 0b01000000, 0b00000000, 0b00100100,
-	0b00100100, 0b00000000, 0b00000000, 0b01000010, 0b01000010, 0b01000010,
-	0b01000010, 0b01000010, 0b01000110, 0b00111010, 0b00000010, 0b00000010,
-	0b01000010, 0b00111100,
-];
+//! zap_font parses PC Screen Font (PSF1 and PSF2) files without allocating,
+//! and bundles the 8x16 Zap font in that format.
+
+/// The Zap Light 8x16 font, as a PSF1 file with a unicode table
+pub static ZAP_LIGHT_16: &[u8] = include_bytes!("../res/zap-light16.psf");
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_HEADER_SIZE: usize = 4;
+const PSF1_MODE_512: u8 = 0x01;
+const PSF1_MODE_HAS_TABLE: u8 = 0x02;
+const PSF1_MODE_HAS_SEQUENCES: u8 = 0x04;
+const PSF1_SEPARATOR: u16 = 0xFFFF;
+const PSF1_SEQUENCE_START: u16 = 0xFFFE;
+
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HEADER_SIZE: usize = 32;
+const PSF2_HAS_TABLE: u32 = 0x01;
+const PSF2_SEPARATOR: u8 = 0xFF;
+const PSF2_SEQUENCE_START: u8 = 0xFE;
+
+/// Why a font couldn't be parsed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+	/// The data doesn't start with a PSF1 or PSF2 magic number
+	BadMagic,
+	/// The header describes more data than there is
+	Truncated,
+	/// The header has a field set to something this parser can't handle
+	Unsupported,
+}
+
+/// Which revision of the PSF format a font uses
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Version {
+	/// Always 8 pixels wide, with 256 or 512 glyphs
+	Psf1,
+	/// Any size and glyph count
+	Psf2,
+}
+
+/// A parsed font, borrowing the file it came from
+#[derive(Clone, Copy)]
+pub struct Font<'a> {
+	version: Version,
+	width: usize,
+	height: usize,
+	glyph_count: usize,
+	glyph_size: usize,
+	glyphs: &'a [u8],
+	table: Option<&'a [u8]>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+	u32::from_le_bytes([
+		data[offset],
+		data[offset + 1],
+		data[offset + 2],
+		data[offset + 3],
+	])
+}
+
+impl<'a> Font<'a> {
+	/// Parse a PSF1 or PSF2 file
+	pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+		if data.starts_with(&PSF1_MAGIC) {
+			Self::parse_psf1(data)
+		} else if data.starts_with(&PSF2_MAGIC) {
+			Self::parse_psf2(data)
+		} else {
+			Err(Error::BadMagic)
+		}
+	}
+
+	fn parse_psf1(data: &'a [u8]) -> Result<Self, Error> {
+		if data.len() < PSF1_HEADER_SIZE {
+			return Err(Error::Truncated);
+		}
+
+		let mode = data[2];
+		let height = data[3] as usize;
+		let glyph_count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+		let has_table =
+			mode & (PSF1_MODE_HAS_TABLE | PSF1_MODE_HAS_SEQUENCES) != 0;
+
+		Self::new(
+			Version::Psf1,
+			data,
+			PSF1_HEADER_SIZE,
+			8,
+			height,
+			glyph_count,
+			height,
+			has_table,
+		)
+	}
+
+	fn parse_psf2(data: &'a [u8]) -> Result<Self, Error> {
+		if data.len() < PSF2_HEADER_SIZE {
+			return Err(Error::Truncated);
+		}
+
+		let version = read_u32(data, 4);
+		let header_size = read_u32(data, 8) as usize;
+		let flags = read_u32(data, 12);
+		let glyph_count = read_u32(data, 16) as usize;
+		let glyph_size = read_u32(data, 20) as usize;
+		let height = read_u32(data, 24) as usize;
+		let width = read_u32(data, 28) as usize;
+
+		if version != 0 || header_size < PSF2_HEADER_SIZE {
+			return Err(Error::Unsupported);
+		}
+
+		Self::new(
+			Version::Psf2,
+			data,
+			header_size,
+			width,
+			height,
+			glyph_count,
+			glyph_size,
+			flags & PSF2_HAS_TABLE != 0,
+		)
+	}
+
+	#[allow(clippy::too_many_arguments)]
+	fn new(
+		version: Version,
+		data: &'a [u8],
+		header_size: usize,
+		width: usize,
+		height: usize,
+		glyph_count: usize,
+		glyph_size: usize,
+		has_table: bool,
+	) -> Result<Self, Error> {
+		let min_size = width.div_ceil(8) * height;
+		if width == 0 || height == 0 || glyph_size < min_size {
+			return Err(Error::Unsupported);
+		}
+
+		let glyphs_end = glyph_count
+			.checked_mul(glyph_size)
+			.and_then(|size| size.checked_add(header_size))
+			.ok_or(Error::Truncated)?;
+		if glyphs_end > data.len() {
+			return Err(Error::Truncated);
+		}
+
+		Ok(Self {
+			version,
+			width,
+			height,
+			glyph_count,
+			glyph_size,
+			glyphs: &data[header_size..glyphs_end],
+			table: if has_table {
+				Some(&data[glyphs_end..])
+			} else {
+				None
+			},
+		})
+	}
+
+	/// Which revision of the format the font was stored in
+	pub const fn version(&self) -> Version {
+		self.version
+	}
+
+	/// Width of every glyph in pixels
+	pub const fn width(&self) -> usize {
+		self.width
+	}
+
+	/// Height of every glyph in pixels
+	pub const fn height(&self) -> usize {
+		self.height
+	}
+
+	/// Number of glyphs in the font
+	pub const fn glyph_count(&self) -> usize {
+		self.glyph_count
+	}
+
+	/// Whether the font maps code points to glyphs itself. Without a table,
+	/// glyph indices are assumed to be code points.
+	pub const fn has_unicode_table(&self) -> bool {
+		self.table.is_some()
+	}
+
+	/// Bitmap of the glyph at `index`
+	pub fn glyph(&self, index: usize) -> Option<Glyph<'a>> {
+		if index >= self.glyph_count {
+			return None;
+		}
+
+		let start = index * self.glyph_size;
+		Some(Glyph {
+			data: &self.glyphs[start..start + self.glyph_size],
+			width: self.width,
+			height: self.height,
+		})
+	}
+
+	/// Index of the glyph that draws `c`, if there is one. This walks the
+	/// unicode table, so callers drawing lots of text should cache results.
+	pub fn lookup(&self, c: char) -> Option<usize> {
+		if self.table.is_none() {
+			return Some(c as usize).filter(|&index| index < self.glyph_count);
+		}
+
+		self.mappings()
+			.find(|&(mapped, _)| mapped == c)
+			.map(|(_, index)| index)
+	}
+
+	/// Every (character, glyph index) pair in the unicode table. Multi-char
+	/// sequences are skipped, since they can't be drawn one char at a time.
+	pub fn mappings(&self) -> Mappings<'a> {
+		Mappings {
+			version: self.version,
+			table: self.table.unwrap_or(&[]),
+			offset: 0,
+			glyph: 0,
+			in_sequence: false,
+		}
+	}
+}
+
+/// Iterator over a font's unicode table, from `Font::mappings`
+pub struct Mappings<'a> {
+	version: Version,
+	table: &'a [u8],
+	offset: usize,
+	glyph: usize,
+	in_sequence: bool,
+}
+
+enum Entry {
+	Char(char),
+	SequenceStart,
+	Separator,
+	Invalid,
+}
+
+impl Mappings<'_> {
+	fn next_entry(&mut self) -> Option<Entry> {
+		let rest = &self.table[self.offset..];
+
+		match self.version {
+			Version::Psf1 => {
+				if rest.len() < 2 {
+					return None;
+				}
+				self.offset += 2;
+
+				Some(match u16::from_le_bytes([rest[0], rest[1]]) {
+					PSF1_SEPARATOR => Entry::Separator,
+					PSF1_SEQUENCE_START => Entry::SequenceStart,
+					code_point => core::char::from_u32(code_point as u32)
+						.map_or(Entry::Invalid, Entry::Char),
+				})
+			}
+			Version::Psf2 => {
+				let first = *rest.first()?;
+				let len = match first {
+					PSF2_SEPARATOR => {
+						self.offset += 1;
+						return Some(Entry::Separator);
+					}
+					PSF2_SEQUENCE_START => {
+						self.offset += 1;
+						return Some(Entry::SequenceStart);
+					}
+					0x00..=0x7F => 1,
+					0xC0..=0xDF => 2,
+					0xE0..=0xEF => 3,
+					0xF0..=0xF7 => 4,
+					_ => {
+						self.offset += 1;
+						return Some(Entry::Invalid);
+					}
+				};
+
+				let len = len.min(rest.len());
+				self.offset += len;
+
+				Some(
+					core::str::from_utf8(&rest[..len])
+						.ok()
+						.and_then(|s| s.chars().next())
+						.map_or(Entry::Invalid, Entry::Char),
+				)
+			}
+		}
+	}
+}
+
+impl Iterator for Mappings<'_> {
+	type Item = (char, usize);
+
+	fn next(&mut self) -> Option<(char, usize)> {
+		loop {
+			match self.next_entry()? {
+				Entry::Separator => {
+					self.glyph += 1;
+					self.in_sequence = false;
+				}
+				Entry::SequenceStart => self.in_sequence = true,
+				Entry::Char(c) if !self.in_sequence => {
+					return Some((c, self.glyph))
+				}
+				Entry::Char(_) | Entry::Invalid => {}
+			}
+		}
+	}
+}
+
+/// The bitmap of a single glyph. Rows are padded to whole bytes, with the
+/// most significant bit of each byte on the left.
+#[derive(Clone, Copy)]
+pub struct Glyph<'a> {
+	data: &'a [u8],
+	width: usize,
+	height: usize,
+}
+
+impl<'a> Glyph<'a> {
+	/// Width in pixels
+	pub const fn width(&self) -> usize {
+		self.width
+	}
+
+	/// Height in pixels
+	pub const fn height(&self) -> usize {
+		self.height
+	}
+
+	/// Bytes making up one row of the bitmap
+	pub const fn bytes_per_row(&self) -> usize {
+		self.width.div_ceil(8)
+	}
+
+	/// The raw bitmap, `bytes_per_row` bytes per row
+	pub const fn data(&self) -> &'a [u8] {
+		self.data
+	}
+
+	/// Whether the pixel at (`x`, `y`) is part of the glyph; anything outside
+	/// of it isn't
+	pub fn is_set(&self, x: usize, y: usize) -> bool {
+		if x >= self.width || y >= self.height {
+			return false;
+		}
+
+		self.data[y * self.bytes_per_row() + x / 8] & (0x80 >> (x % 8)) != 0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// A PSF2 file with two 8x2 glyphs: the first a filled top row mapped
+	/// to 'a', the second a single pixel mapped to 'é', plus a sequence that
+	/// has to be skipped
+	#[rustfmt::skip]
+	const PSF2: [u8; 44] = [
+		// magic, version, header size, flags, glyph count, glyph size
+		0x72, 0xB5, 0x4A, 0x86, 0, 0, 0, 0, 32, 0, 0, 0, 1, 0, 0, 0,
+		2, 0, 0, 0, 2, 0, 0, 0,
+		// height, width
+		2, 0, 0, 0, 8, 0, 0, 0,
+		// glyphs
+		0xFF, 0x00, 0x00, 0x40,
+		// unicode table
+		b'a', 0xFF, 0xC3, 0xA9, 0xFE, b'x', b'y', 0xFF,
+	];
+
+	fn psf2_with(offset: usize, value: u8) -> [u8; 44] {
+		let mut data = PSF2;
+		data[offset] = value;
+		data
+	}
+
+	#[test]
+	fn bundled_font() {
+		let font = Font::parse(ZAP_LIGHT_16).unwrap();
+
+		assert_eq!(font.version(), Version::Psf1);
+		assert_eq!((font.width(), font.height()), (8, 16));
+		assert!(font.glyph_count() == 256 || font.glyph_count() == 512);
+		assert!(font.has_unicode_table());
+
+		let index = font.lookup('A').unwrap();
+		let glyph = font.glyph(index).unwrap();
+		assert_eq!(glyph.data().len(), 16);
+		assert!(glyph.data().iter().any(|&row| row != 0));
+		assert_eq!(font.lookup('\u{10FFFF}'), None);
+	}
+
+	#[test]
+	fn psf2_header() {
+		let font = Font::parse(&PSF2).unwrap();
+
+		assert_eq!(font.version(), Version::Psf2);
+		assert_eq!((font.width(), font.height()), (8, 2));
+		assert_eq!(font.glyph_count(), 2);
+		assert!(font.glyph(2).is_none());
+	}
+
+	#[test]
+	fn psf2_unicode_table() {
+		let font = Font::parse(&PSF2).unwrap();
+
+		assert_eq!(font.lookup('a'), Some(0));
+		assert_eq!(font.lookup('é'), Some(1));
+		// only reachable as part of a sequence
+		assert_eq!(font.lookup('x'), None);
+		assert_eq!(font.lookup('y'), None);
+
+		let mut mappings = font.mappings();
+		assert_eq!(mappings.next(), Some(('a', 0)));
+		assert_eq!(mappings.next(), Some(('é', 1)));
+		assert_eq!(mappings.next(), None);
+	}
+
+	#[test]
+	fn glyph_bits() {
+		let font = Font::parse(&PSF2).unwrap();
+		let full = font.glyph(0).unwrap();
+		let dot = font.glyph(1).unwrap();
+
+		assert_eq!(full.bytes_per_row(), 1);
+		assert!((0..8).all(|x| full.is_set(x, 0) && !full.is_set(x, 1)));
+		assert!(dot.is_set(1, 1));
+		assert!(!dot.is_set(0, 1));
+		assert!(!full.is_set(8, 0));
+		assert!(!full.is_set(0, 2));
+	}
+
+	#[test]
+	fn psf1_without_table() {
+		// 256 glyphs, 1 pixel high, no table
+		let mut data = [0; 4 + 256];
+		data[..4].copy_from_slice(&[0x36, 0x04, 0, 1]);
+		data[4 + b'A' as usize] = 0x80;
+		let font = Font::parse(&data).unwrap();
+
+		assert_eq!(font.version(), Version::Psf1);
+		assert_eq!(font.glyph_count(), 256);
+		assert!(!font.has_unicode_table());
+		// glyph indices are code points
+		assert_eq!(font.lookup('A'), Some(65));
+		assert!(font.glyph(65).unwrap().is_set(0, 0));
+		assert_eq!(font.lookup('\u{100}'), None);
+	}
+
+	#[test]
+	fn bad_fonts() {
+		assert_eq!(Font::parse(&[0; 32]).err(), Some(Error::BadMagic));
+		assert_eq!(Font::parse(&PSF2[..16]).err(), Some(Error::Truncated));
+		// not enough room for both glyphs
+		assert_eq!(Font::parse(&PSF2[..35]).err(), Some(Error::Truncated));
+		assert_eq!(Font::parse(&[0x36, 0x04, 0]).err(), Some(Error::Truncated));
+		// version 1
+		let data = psf2_with(4, 1);
+		assert_eq!(Font::parse(&data).err(), Some(Error::Unsupported));
+		// 0 pixels wide
+		let data = psf2_with(28, 0);
+		assert_eq!(Font::parse(&data).err(), Some(Error::Unsupported));
+		// glyphs too small for their size
+		let data = psf2_with(20, 1);
+		assert_eq!(Font::parse(&data).err(), Some(Error::Unsupported));
+	}
+}
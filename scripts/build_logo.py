@@ -0,0 +1,174 @@
+#!/usr/bin/env python3
+"""Renders the boot splash logo (res/logo.qoi): a rounded gradient square
+with a "B" from the console font. Run from the repo root."""
+
+import math
+import struct
+
+FONT_PATH = "lib/zap_font/res/zap-light16.psf"
+OUTPUT_PATH = "res/logo.qoi"
+
+WIDTH = 200
+HEIGHT = 200
+CORNER_RADIUS = 28
+# the gradient runs from the top left corner to the bottom right one
+GRADIENT_START = (140, 60, 220)
+GRADIENT_END = (40, 200, 230)
+
+LETTER = "B"
+# each pixel of the glyph becomes a LETTER_SCALE x LETTER_SCALE square
+LETTER_SCALE = 9
+LETTER_COLOR = (255, 255, 255, 255)
+
+TRANSPARENT = (0, 0, 0, 0)
+
+PSF1_HEADER_SIZE = 4
+PSF1_GLYPH_WIDTH = 8
+
+QOI_OP_INDEX = 0x00
+QOI_OP_DIFF = 0x40
+QOI_OP_LUMA = 0x80
+QOI_OP_RUN = 0xC0
+QOI_OP_RGB = 0xFE
+QOI_OP_RGBA = 0xFF
+QOI_MAX_RUN = 62
+QOI_END_MARKER = bytes([0] * 7 + [1])
+
+
+def load_glyph(path, char):
+    """Rows of a glyph in a PSF1 font, one byte each. The glyph is looked
+    up by its code point, which holds for ASCII in zap even though it has a
+    unicode table."""
+    with open(path, "rb") as f:
+        psf = f.read()
+
+    height = psf[3]
+    start = PSF1_HEADER_SIZE + ord(char) * height
+    return psf[start:start + height]
+
+
+def lerp(start, end, t):
+    return int(start * (1 - t) + end * t)
+
+
+def background_pixel(x, y):
+    """Color of the rounded square at (x, y), with the alpha antialiasing
+    the corners"""
+    # distance past the straight edges, towards the corner's center
+    dx = max(CORNER_RADIUS - x, 0, x - (WIDTH - 1 - CORNER_RADIUS))
+    dy = max(CORNER_RADIUS - y, 0, y - (HEIGHT - 1 - CORNER_RADIUS))
+    distance = math.hypot(dx, dy)
+
+    coverage = max(0.0, min(1.0, CORNER_RADIUS + 0.5 - distance))
+    if coverage <= 0:
+        return TRANSPARENT
+
+    t = (x + y) / (WIDTH + HEIGHT - 2)
+    r, g, b = (
+        lerp(start, end, t)
+        for start, end in zip(GRADIENT_START, GRADIENT_END)
+    )
+    return (r, g, b, int(255 * coverage))
+
+
+def draw_background():
+    return [
+        [background_pixel(x, y) for x in range(WIDTH)]
+        for y in range(HEIGHT)
+    ]
+
+
+def draw_glyph(image, glyph):
+    """Scale the glyph up and center it on the image"""
+    left = (WIDTH - PSF1_GLYPH_WIDTH * LETTER_SCALE) // 2
+    top = (HEIGHT - len(glyph) * LETTER_SCALE) // 2
+
+    for row, bits in enumerate(glyph):
+        for col in range(PSF1_GLYPH_WIDTH):
+            if not bits & (0x80 >> col):
+                continue
+
+            for y in range(LETTER_SCALE):
+                for x in range(LETTER_SCALE):
+                    image_y = top + row * LETTER_SCALE + y
+                    image_x = left + col * LETTER_SCALE + x
+                    image[image_y][image_x] = LETTER_COLOR
+
+
+def wrapping_diff(a, b):
+    """a - b, wrapped into -128..127 like QOI's byte arithmetic"""
+    return (a - b + 128) % 256 - 128
+
+
+def qoi_hash(pixel):
+    r, g, b, a = pixel
+    return (r * 3 + g * 5 + b * 7 + a * 11) % 64
+
+
+def encode_change(pixel, prev):
+    """Smallest chunk turning `prev` into `pixel`, when they have the same
+    alpha"""
+    dr = wrapping_diff(pixel[0], prev[0])
+    dg = wrapping_diff(pixel[1], prev[1])
+    db = wrapping_diff(pixel[2], prev[2])
+    dr_dg = dr - dg
+    db_dg = db - dg
+
+    if all(-2 <= d <= 1 for d in (dr, dg, db)):
+        return bytes([QOI_OP_DIFF | (dr + 2) << 4 | (dg + 2) << 2 | (db + 2)])
+    if -32 <= dg <= 31 and -8 <= dr_dg <= 7 and -8 <= db_dg <= 7:
+        return bytes([QOI_OP_LUMA | (dg + 32), (dr_dg + 8) << 4 | (db_dg + 8)])
+    return bytes([QOI_OP_RGB, *pixel[:3]])
+
+
+def encode_qoi(image):
+    """Encode rows of RGBA tuples as a QOI file"""
+    height = len(image)
+    width = len(image[0])
+    out = bytearray(b"qoif" + struct.pack(">IIBB", width, height, 4, 0))
+
+    seen = [TRANSPARENT] * 64
+    prev = (0, 0, 0, 255)
+    run = 0
+    pixels = [pixel for row in image for pixel in row]
+
+    for i, pixel in enumerate(pixels):
+        if pixel == prev:
+            run += 1
+            if run == QOI_MAX_RUN or i == len(pixels) - 1:
+                out.append(QOI_OP_RUN | (run - 1))
+                run = 0
+            continue
+
+        if run:
+            out.append(QOI_OP_RUN | (run - 1))
+            run = 0
+
+        index = qoi_hash(pixel)
+        if seen[index] == pixel:
+            out.append(QOI_OP_INDEX | index)
+        else:
+            seen[index] = pixel
+            if pixel[3] == prev[3]:
+                out += encode_change(pixel, prev)
+            else:
+                out += bytes([QOI_OP_RGBA, *pixel])
+
+        prev = pixel
+
+    out += QOI_END_MARKER
+    return bytes(out)
+
+
+def main():
+    image = draw_background()
+    draw_glyph(image, load_glyph(FONT_PATH, LETTER))
+
+    data = encode_qoi(image)
+    with open(OUTPUT_PATH, "wb") as f:
+        f.write(data)
+    print("wrote", len(data), "bytes")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,138 @@
+//! Just enough ACPI to find tables, starting from the RSDP the bootloader
+//! hands over. Tables are expected to be in the identity-mapped first 4 GiB,
+//! anything above that is ignored.
+
+use crate::{boot::STIVALE_STRUCT, mm::IDENTITY_MAPPED};
+use core::{mem::size_of, ptr, slice};
+
+const RSDP_SIGNATURE: &[u8; 8] = b"RSD PTR ";
+/// Size of the ACPI 1.0 part of the RSDP, which the first checksum covers
+const RSDP_V1_LEN: usize = 20;
+
+#[repr(C, packed)]
+struct Rsdp {
+	signature: [u8; 8],
+	checksum: u8,
+	oem_id: [u8; 6],
+	revision: u8,
+	rsdt_address: u32,
+	// ACPI 2.0 and up
+	length: u32,
+	xsdt_address: u64,
+	extended_checksum: u8,
+	reserved: [u8; 3],
+}
+
+/// Header every system description table starts with
+#[repr(C, packed)]
+pub struct SdtHeader {
+	pub signature: [u8; 4],
+	/// Of the whole table, header included
+	pub length: u32,
+	pub revision: u8,
+	pub checksum: u8,
+	pub oem_id: [u8; 6],
+	pub oem_table_id: [u8; 8],
+	pub oem_revision: u32,
+	pub creator_id: u32,
+	pub creator_revision: u32,
+}
+
+impl SdtHeader {
+	/// Everything after the header
+	pub fn data(&self) -> &[u8] {
+		let len = self.length as usize - size_of::<Self>();
+		let start =
+			(self as *const Self as *const u8).wrapping_add(size_of::<Self>());
+
+		// SAFETY: the length was checked to fit in the identity map when the
+		// table was found
+		unsafe { slice::from_raw_parts(start, len) }
+	}
+}
+
+/// Whether `len` bytes at `address` can be read through the identity map
+fn mapped(address: u64, len: usize) -> bool {
+	address != 0
+		&& address
+			.checked_add(len as u64)
+			.map_or(false, |end| end <= IDENTITY_MAPPED)
+}
+
+/// ACPI checksums make every byte of a structure add up to 0
+unsafe fn checksum_ok(address: u64, len: usize) -> bool {
+	slice::from_raw_parts(address as *const u8, len)
+		.iter()
+		.fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+		== 0
+}
+
+/// The table at `address`, if it looks like a valid one
+unsafe fn table_at(address: u64) -> Option<&'static SdtHeader> {
+	if !mapped(address, size_of::<SdtHeader>()) {
+		return None;
+	}
+
+	let header = &*(address as *const SdtHeader);
+	let len = header.length as usize;
+	if len < size_of::<SdtHeader>() || !mapped(address, len) {
+		return None;
+	}
+
+	if checksum_ok(address, len) {
+		Some(header)
+	} else {
+		None
+	}
+}
+
+/// The RSDT or XSDT, and how wide its entries are
+fn root_table() -> Option<(&'static SdtHeader, usize)> {
+	let address = STIVALE_STRUCT.inner().rsdp()?.rsdp();
+	if !mapped(address, size_of::<Rsdp>()) {
+		return None;
+	}
+
+	// SAFETY: the bootloader says there's an RSDP here, and it's mapped.
+	// Everything it points at is checked before it's trusted.
+	unsafe {
+		let rsdp = ptr::read_unaligned(address as *const Rsdp);
+		if &rsdp.signature != RSDP_SIGNATURE
+			|| !checksum_ok(address, RSDP_V1_LEN)
+		{
+			return None;
+		}
+
+		// the XSDT replaces the RSDT from ACPI 2.0 on, when it's reachable
+		if rsdp.revision >= 2 && checksum_ok(address, rsdp.length as usize) {
+			if let Some(xsdt) = table_at(rsdp.xsdt_address) {
+				return Some((xsdt, size_of::<u64>()));
+			}
+		}
+		table_at(rsdp.rsdt_address.into()).map(|rsdt| (rsdt, size_of::<u32>()))
+	}
+}
+
+/// Find the first table with the given signature, e.g. `b"MCFG"`
+pub fn find_table(signature: &[u8; 4]) -> Option<&'static SdtHeader> {
+	let (root, entry_size) = root_table()?;
+
+	root.data().chunks_exact(entry_size).find_map(|entry| {
+		let address = match *entry {
+			[a, b, c, d] => u32::from_le_bytes([a, b, c, d]).into(),
+			_ => {
+				let mut bytes = [0; 8];
+				bytes.copy_from_slice(entry);
+				u64::from_le_bytes(bytes)
+			}
+		};
+
+		// SAFETY: the root table says there's a table here
+		let table = unsafe { table_at(address)? };
+		if &table.signature == signature {
+			Some(table)
+		} else {
+			None
+		}
+	})
+}
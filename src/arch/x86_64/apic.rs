@@ -0,0 +1,115 @@
+//! Local APIC of the boot CPU. Legacy IRQs still come from the 8259 PICs,
+//! passed through the local APIC in virtual wire mode; the local APIC itself
+//! is only needed for message signaled interrupts, which are sent straight
+//! to it and have to be acknowledged with `eoi` rather than `pic::eoi`.
+
+use super::{
+	cpu,
+	idt::{self, InterruptStackFrame},
+};
+use crate::mm::IDENTITY_MAPPED;
+use core::{
+	ptr,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+const IA32_APIC_BASE: u32 = 0x1B;
+const GLOBAL_ENABLE: u64 = 1 << 11;
+const BASE_MASK: u64 = 0xF_FFFF_F000;
+
+/// CPUID leaf 1 EDX bit saying there's a local APIC at all
+const CPUID_APIC: u32 = 1 << 9;
+
+// registers, as offsets from the base
+const ID: usize = 0x20;
+const TASK_PRIORITY: usize = 0x80;
+const END_OF_INTERRUPT: usize = 0xB0;
+const SPURIOUS: usize = 0xF0;
+const LVT_LINT0: usize = 0x350;
+const LVT_LINT1: usize = 0x360;
+
+const SOFTWARE_ENABLE: u32 = 1 << 8;
+// local vector table delivery modes
+const DELIVER_NMI: u32 = 0b100 << 8;
+const DELIVER_EXTINT: u32 = 0b111 << 8;
+
+/// Vector the local APIC uses for interrupts that went away before they
+/// could be delivered. The low 4 bits have to be set on older CPUs.
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+/// Where the registers are, or 0 before `init`
+static BASE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug)]
+pub enum ApicError {
+	/// The CPU doesn't have one
+	Missing,
+	/// The registers are somewhere outside the identity map
+	Unmapped(u64),
+}
+
+unsafe fn read(base: usize, register: usize) -> u32 {
+	ptr::read_volatile((base + register) as *const u32)
+}
+
+unsafe fn write(base: usize, register: usize, value: u32) {
+	ptr::write_volatile((base + register) as *mut u32, value)
+}
+
+extern "x86-interrupt" fn spurious_interrupt(_frame: InterruptStackFrame) {
+	// spurious interrupts aren't in service, so there's nothing to EOI
+}
+
+/// Whether `init` succeeded
+pub fn enabled() -> bool {
+	BASE.load(Ordering::Relaxed) != 0
+}
+
+/// ID of the boot CPU's local APIC, which is what MSIs are addressed to
+pub fn id() -> Option<u8> {
+	match BASE.load(Ordering::Relaxed) {
+		0 => None,
+		base => Some((unsafe { read(base, ID) } >> 24) as u8),
+	}
+}
+
+/// Tell the local APIC the interrupt being handled is done. Only for
+/// interrupts that came through the local APIC, like MSIs.
+pub fn eoi() {
+	let base = BASE.load(Ordering::Relaxed);
+	if base != 0 {
+		unsafe { write(base, END_OF_INTERRUPT, 0) }
+	}
+}
+
+/// Turn on the local APIC, keeping PIC interrupts coming through LINT0
+pub fn init() -> Result<(), ApicError> {
+	if cpu::cpuid(1, 0).edx & CPUID_APIC == 0 {
+		return Err(ApicError::Missing);
+	}
+
+	// SAFETY: the CPU has a local APIC, so it has this MSR
+	let msr = unsafe { cpu::read_msr(IA32_APIC_BASE) };
+	let address = msr & BASE_MASK;
+	if address + 0x1000 > IDENTITY_MAPPED {
+		return Err(ApicError::Unmapped(address));
+	}
+	let base = address as usize;
+
+	idt::set_handler(SPURIOUS_VECTOR, spurious_interrupt);
+
+	// SAFETY: the registers are identity mapped, and this only changes how
+	// interrupts get here, not which ones are raised
+	cpu::without_interrupts(|| unsafe {
+		cpu::write_msr(IA32_APIC_BASE, msr | GLOBAL_ENABLE);
+
+		// virtual wire mode: the PIC's output is LINT0, NMIs are LINT1
+		write(base, LVT_LINT0, DELIVER_EXTINT);
+		write(base, LVT_LINT1, DELIVER_NMI);
+		write(base, TASK_PRIORITY, 0);
+		write(base, SPURIOUS, SOFTWARE_ENABLE | u32::from(SPURIOUS_VECTOR));
+	});
+
+	BASE.store(base, Ordering::Relaxed);
+	Ok(())
+}
@@ -1,3 +1,94 @@
+use crate::drivers::ps2;
+use core::arch::x86_64::{CpuidResult, __cpuid_count};
+
 pub fn wait_for_interrupt() {
-	unsafe { asm!("hlt", options(noreturn)) }
+	unsafe { asm!("hlt", options(nomem, nostack)) }
+}
+
+pub fn enable_interrupts() {
+	unsafe { asm!("sti", options(nomem, nostack)) }
+}
+
+pub fn disable_interrupts() {
+	unsafe { asm!("cli", options(nomem, nostack)) }
+}
+
+/// Enable interrupts and halt until the next one arrives. `sti` only takes
+/// effect after the following instruction, so no interrupt can sneak in between
+/// the two and leave us sleeping with a wakeup already handled.
+pub fn enable_interrupts_and_wait() {
+	unsafe { asm!("sti; hlt", options(nomem, nostack)) }
+}
+
+pub fn interrupts_enabled() -> bool {
+	let rflags: u64;
+	unsafe { asm!("pushfq; pop {}", out(reg) rflags, options(nomem)) }
+	rflags & (1 << 9) != 0
+}
+
+/// Run `f` with interrupts disabled, restoring the previous state afterwards.
+/// Locks that are also taken by interrupt handlers must only be held in here,
+/// otherwise a handler can spin forever on a lock its own CPU holds.
+pub fn without_interrupts<T>(f: impl FnOnce() -> T) -> T {
+	let enabled = interrupts_enabled();
+	if enabled {
+		disable_interrupts();
+	}
+
+	let ret = f();
+
+	if enabled {
+		enable_interrupts();
+	}
+	ret
+}
+
+/// Ask the CPU about itself
+pub fn cpuid(leaf: u32, subleaf: u32) -> CpuidResult {
+	unsafe { __cpuid_count(leaf, subleaf) }
+}
+
+/// Read a model specific register
+pub unsafe fn read_msr(msr: u32) -> u64 {
+	let (low, high): (u32, u32);
+	asm!(
+		"rdmsr",
+		in("ecx") msr,
+		out("eax") low,
+		out("edx") high,
+		options(nomem, nostack, preserves_flags)
+	);
+	u64::from(high) << 32 | u64::from(low)
+}
+
+/// Write a model specific register. This can change just about anything
+/// about how the CPU behaves.
+pub unsafe fn write_msr(msr: u32, value: u64) {
+	asm!(
+		"wrmsr",
+		in("ecx") msr,
+		in("eax") value as u32,
+		in("edx") (value >> 32) as u32,
+		options(nostack, preserves_flags)
+	);
+}
+
+/// Reset the machine by pulsing the reset line through the PS/2 controller,
+/// or failing that, by triple faulting
+pub fn reboot() -> ! {
+	disable_interrupts();
+
+	// if there's no controller this times out, and the triple fault does it
+	let _ = ps2::pulse_reset();
+
+	unsafe {
+		// with an empty IDT, the breakpoint can't be delivered and neither can
+		// the faults that follow
+		let empty_idt = [0u16; 5];
+		asm!("lidt [{}]", "int3", in(reg) &empty_idt, options(nostack));
+	}
+
+	loop {
+		wait_for_interrupt();
+	}
 }
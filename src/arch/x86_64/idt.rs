@@ -0,0 +1,204 @@
+use core::fmt;
+use spin::Mutex;
+
+/// What the CPU pushes onto the stack before calling an interrupt handler
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct InterruptStackFrame {
+	pub instruction_pointer: u64,
+	pub code_segment: u64,
+	pub cpu_flags: u64,
+	pub stack_pointer: u64,
+	pub stack_segment: u64,
+}
+
+impl fmt::Debug for InterruptStackFrame {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("InterruptStackFrame")
+			.field("rip", &format_args!("{:#x}", self.instruction_pointer))
+			.field("cs", &format_args!("{:#x}", self.code_segment))
+			.field("rflags", &format_args!("{:#x}", self.cpu_flags))
+			.field("rsp", &format_args!("{:#x}", self.stack_pointer))
+			.field("ss", &format_args!("{:#x}", self.stack_segment))
+			.finish()
+	}
+}
+
+pub type Handler = extern "x86-interrupt" fn(InterruptStackFrame);
+pub type HandlerWithErrorCode =
+	extern "x86-interrupt" fn(InterruptStackFrame, u64);
+pub type DivergingHandlerWithErrorCode =
+	extern "x86-interrupt" fn(InterruptStackFrame, u64) -> !;
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Entry {
+	offset_low: u16,
+	selector: u16,
+	ist: u8,
+	flags: u8,
+	offset_mid: u16,
+	offset_high: u32,
+	reserved: u32,
+}
+
+impl Entry {
+	// present, ring 0, 64-bit interrupt gate
+	const INTERRUPT_GATE: u8 = 0x8E;
+
+	const fn missing() -> Self {
+		Self {
+			offset_low: 0,
+			selector: 0,
+			ist: 0,
+			flags: 0,
+			offset_mid: 0,
+			offset_high: 0,
+			reserved: 0,
+		}
+	}
+
+	fn new(handler: usize, selector: u16) -> Self {
+		Self {
+			offset_low: handler as u16,
+			selector,
+			ist: 0,
+			flags: Self::INTERRUPT_GATE,
+			offset_mid: (handler >> 16) as u16,
+			offset_high: (handler >> 32) as u32,
+			reserved: 0,
+		}
+	}
+}
+
+#[repr(C, packed)]
+struct IdtPointer {
+	limit: u16,
+	base: u64,
+}
+
+static IDT: Mutex<[Entry; 256]> = Mutex::new([Entry::missing(); 256]);
+
+/// Vectors `allocate_vectors` hands out, between the remapped PIC IRQs and
+/// the local APIC's spurious vector
+pub const FIRST_FREE_VECTOR: u8 = 48;
+pub const LAST_FREE_VECTOR: u8 = 239;
+
+static ALLOCATED: Mutex<[bool; 256]> = Mutex::new([false; 256]);
+
+fn code_segment() -> u16 {
+	let cs: u16;
+	unsafe { asm!("mov {:x}, cs", out(reg) cs, options(nomem, nostack)) }
+	cs
+}
+
+fn set_raw(vector: u8, handler: usize) {
+	IDT.lock()[vector as usize] = Entry::new(handler, code_segment());
+}
+
+pub fn set_handler(vector: u8, handler: Handler) {
+	set_raw(vector, handler as usize);
+}
+
+pub fn set_handler_with_error_code(vector: u8, handler: HandlerWithErrorCode) {
+	set_raw(vector, handler as usize);
+}
+
+pub fn set_diverging_handler_with_error_code(
+	vector: u8,
+	handler: DivergingHandlerWithErrorCode,
+) {
+	set_raw(vector, handler as usize);
+}
+
+/// Reserve `count` vectors in a row, returning the first. It's aligned to
+/// `count` rounded up to a power of two, which multi-vector MSI needs.
+pub fn allocate_vectors(count: usize) -> Option<u8> {
+	if count == 0 {
+		return None;
+	}
+	let align = count.next_power_of_two();
+	let first = usize::from(FIRST_FREE_VECTOR);
+	let last = (usize::from(LAST_FREE_VECTOR) + 1).checked_sub(count)?;
+	let mut allocated = ALLOCATED.lock();
+
+	let start = (first + align - 1) / align * align;
+	let vector = (start..=last)
+		.step_by(align)
+		.find(|&vector| !allocated[vector..vector + count].contains(&true))?;
+
+	for taken in &mut allocated[vector..vector + count] {
+		*taken = true;
+	}
+	Some(vector as u8)
+}
+
+/// Give back vectors from `allocate_vectors`, removing their handlers
+pub fn free_vectors(first: u8, count: usize) {
+	let first = usize::from(first);
+	let mut allocated = ALLOCATED.lock();
+	let mut idt = IDT.lock();
+
+	for vector in first..first + count {
+		allocated[vector] = false;
+		idt[vector] = Entry::missing();
+	}
+}
+
+/// Install the CPU exception handlers and load the IDT. Other handlers can be
+/// added (or replaced) at any time afterwards.
+pub fn init() {
+	set_handler(0, divide_error);
+	set_handler(6, invalid_opcode);
+	set_diverging_handler_with_error_code(8, double_fault);
+	set_handler_with_error_code(13, general_protection_fault);
+	set_handler_with_error_code(14, page_fault);
+
+	let idt = IDT.lock();
+	let pointer = IdtPointer {
+		limit: (core::mem::size_of_val(&*idt) - 1) as u16,
+		base: idt.as_ptr() as u64,
+	};
+
+	// SAFETY: the table lives in a static, so it outlives the pointer we hand
+	// to the CPU
+	unsafe { asm!("lidt [{}]", in(reg) &pointer, options(readonly, nostack)) }
+}
+
+extern "x86-interrupt" fn divide_error(frame: InterruptStackFrame) {
+	panic!("Divide error\n{:#?}", frame);
+}
+
+extern "x86-interrupt" fn invalid_opcode(frame: InterruptStackFrame) {
+	panic!("Invalid opcode\n{:#?}", frame);
+}
+
+extern "x86-interrupt" fn double_fault(
+	frame: InterruptStackFrame,
+	_error_code: u64,
+) -> ! {
+	panic!("Double fault\n{:#?}", frame);
+}
+
+extern "x86-interrupt" fn general_protection_fault(
+	frame: InterruptStackFrame,
+	error_code: u64,
+) {
+	panic!(
+		"General protection fault (error code: {:#x})\n{:#?}",
+		error_code, frame
+	);
+}
+
+extern "x86-interrupt" fn page_fault(
+	frame: InterruptStackFrame,
+	error_code: u64,
+) {
+	let address: u64;
+	unsafe { asm!("mov {}, cr2", out(reg) address, options(nomem, nostack)) }
+
+	panic!(
+		"Page fault at {:#x} (error code: {:#x})\n{:#?}",
+		address, error_code, frame
+	);
+}
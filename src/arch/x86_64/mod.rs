@@ -1 +1,7 @@
+pub mod acpi;
+pub mod apic;
 pub mod cpu;
+pub mod idt;
+pub mod pic;
+pub mod pit;
+pub mod port;
@@ -0,0 +1,73 @@
+use super::port::{inb, io_wait, outb};
+
+/// Vector the first IRQ is remapped to, right after the CPU exceptions
+pub const IRQ_BASE: u8 = 32;
+
+const PRIMARY_COMMAND: u16 = 0x20;
+const PRIMARY_DATA: u16 = 0x21;
+const SECONDARY_COMMAND: u16 = 0xA0;
+const SECONDARY_DATA: u16 = 0xA1;
+
+const ICW1_INIT: u8 = 0x11;
+const ICW4_8086: u8 = 0x01;
+const END_OF_INTERRUPT: u8 = 0x20;
+
+/// IRQ line the secondary PIC is chained to on the primary
+const CASCADE_IRQ: u8 = 2;
+
+/// Interrupt vector an IRQ line is delivered on
+pub const fn vector(irq: u8) -> u8 {
+	IRQ_BASE + irq
+}
+
+/// Remap both 8259 PICs so IRQs don't collide with CPU exceptions, leaving
+/// every line except the cascade masked
+pub fn init() {
+	unsafe {
+		outb(PRIMARY_COMMAND, ICW1_INIT);
+		io_wait();
+		outb(SECONDARY_COMMAND, ICW1_INIT);
+		io_wait();
+
+		outb(PRIMARY_DATA, IRQ_BASE);
+		io_wait();
+		outb(SECONDARY_DATA, IRQ_BASE + 8);
+		io_wait();
+
+		outb(PRIMARY_DATA, 1 << CASCADE_IRQ);
+		io_wait();
+		outb(SECONDARY_DATA, CASCADE_IRQ);
+		io_wait();
+
+		outb(PRIMARY_DATA, ICW4_8086);
+		io_wait();
+		outb(SECONDARY_DATA, ICW4_8086);
+		io_wait();
+
+		outb(PRIMARY_DATA, !(1 << CASCADE_IRQ));
+		outb(SECONDARY_DATA, 0xFF);
+	}
+}
+
+fn data_port(irq: u8) -> (u16, u8) {
+	if irq < 8 {
+		(PRIMARY_DATA, irq)
+	} else {
+		(SECONDARY_DATA, irq - 8)
+	}
+}
+
+pub fn unmask(irq: u8) {
+	let (port, line) = data_port(irq);
+	unsafe { outb(port, inb(port) & !(1 << line)) }
+}
+
+/// Acknowledge an IRQ; must be sent before returning from its handler
+pub fn eoi(irq: u8) {
+	unsafe {
+		if irq >= 8 {
+			outb(SECONDARY_COMMAND, END_OF_INTERRUPT);
+		}
+		outb(PRIMARY_COMMAND, END_OF_INTERRUPT);
+	}
+}
@@ -0,0 +1,104 @@
+use super::{
+	cpu,
+	idt::{self, InterruptStackFrame},
+	pic,
+	port::outb,
+};
+use core::sync::atomic::{AtomicU64, Ordering};
+use spin::Mutex;
+
+/// Input clock of the 8253/8254 PIT
+const PIT_FREQUENCY: u32 = 1_193_182;
+/// How often the timer interrupt fires
+pub const TICK_HZ: u32 = 1000;
+
+const CHANNEL_0: u16 = 0x40;
+const COMMAND: u16 = 0x43;
+// channel 0, lobyte/hibyte access, rate generator
+const RATE_GENERATOR: u8 = 0b0011_0100;
+
+const TIMER_IRQ: u8 = 0;
+
+const MAX_TICK_HANDLERS: usize = 4;
+
+static TICKS: AtomicU64 = AtomicU64::new(0);
+static TICK_HANDLERS: Mutex<[Option<fn(u64)>; MAX_TICK_HANDLERS]> =
+	Mutex::new([None; MAX_TICK_HANDLERS]);
+
+extern "x86-interrupt" fn timer_interrupt(_frame: InterruptStackFrame) {
+	let ticks = TICKS.fetch_add(1, Ordering::Relaxed) + 1;
+
+	let handlers = *TICK_HANDLERS.lock();
+	for handler in handlers.iter().flatten() {
+		handler(ticks);
+	}
+
+	pic::eoi(TIMER_IRQ);
+}
+
+/// Call `handler` with the tick count on every timer interrupt, returning
+/// false if there are already too many handlers. Handlers run in interrupt
+/// context, so they must be quick and must never block on a lock.
+pub fn on_tick(handler: fn(u64)) -> bool {
+	cpu::without_interrupts(|| {
+		let mut handlers = TICK_HANDLERS.lock();
+
+		match handlers.iter_mut().find(|slot| slot.is_none()) {
+			Some(slot) => {
+				*slot = Some(handler);
+				true
+			}
+			None => false,
+		}
+	})
+}
+
+/// Start the PIT ticking at `TICK_HZ`
+pub fn init() {
+	let divisor = (PIT_FREQUENCY / TICK_HZ) as u16;
+
+	unsafe {
+		outb(COMMAND, RATE_GENERATOR);
+		outb(CHANNEL_0, divisor as u8);
+		outb(CHANNEL_0, (divisor >> 8) as u8);
+	}
+
+	idt::set_handler(pic::vector(TIMER_IRQ), timer_interrupt);
+	pic::unmask(TIMER_IRQ);
+}
+
+/// Timer interrupts since `init`
+pub fn ticks() -> u64 {
+	TICKS.load(Ordering::Relaxed)
+}
+
+/// Milliseconds since `init`
+pub fn uptime_ms() -> u64 {
+	ticks() * 1000 / TICK_HZ as u64
+}
+
+/// Wait up to `timeout_ms` milliseconds for `done` to return true. With
+/// `sleep` the CPU halts between checks, which is only worth it when an
+/// interrupt is coming to wake it up sooner than the next tick. Either way
+/// the timeout is counted in ticks, so interrupts have to be on.
+pub fn wait_until(
+	sleep: bool,
+	timeout_ms: u64,
+	mut done: impl FnMut() -> bool,
+) -> bool {
+	let deadline = ticks() + timeout_ms * u64::from(TICK_HZ) / 1000;
+
+	loop {
+		if done() {
+			return true;
+		}
+		if ticks() > deadline {
+			return false;
+		}
+
+		// halting with interrupts off would never wake up
+		if sleep && cpu::interrupts_enabled() {
+			cpu::wait_for_interrupt();
+		}
+	}
+}
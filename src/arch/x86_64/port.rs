@@ -0,0 +1,81 @@
+// SAFETY (for all functions in this module): port I/O can have arbitrary side
+// effects on hardware, so callers must make sure the port actually belongs to
+// the device they think they're talking to.
+
+/// Read a byte from an I/O port
+#[inline(always)]
+pub unsafe fn inb(port: u16) -> u8 {
+	let value: u8;
+	asm!(
+		"in al, dx",
+		out("al") value,
+		in("dx") port,
+		options(nomem, nostack, preserves_flags)
+	);
+	value
+}
+
+/// Write a byte to an I/O port
+#[inline(always)]
+pub unsafe fn outb(port: u16, value: u8) {
+	asm!(
+		"out dx, al",
+		in("dx") port,
+		in("al") value,
+		options(nomem, nostack, preserves_flags)
+	);
+}
+
+/// Read a word from an I/O port
+#[inline(always)]
+pub unsafe fn inw(port: u16) -> u16 {
+	let value: u16;
+	asm!(
+		"in ax, dx",
+		out("ax") value,
+		in("dx") port,
+		options(nomem, nostack, preserves_flags)
+	);
+	value
+}
+
+/// Write a word to an I/O port
+#[inline(always)]
+pub unsafe fn outw(port: u16, value: u16) {
+	asm!(
+		"out dx, ax",
+		in("dx") port,
+		in("ax") value,
+		options(nomem, nostack, preserves_flags)
+	);
+}
+
+/// Read a doubleword from an I/O port
+#[inline(always)]
+pub unsafe fn inl(port: u16) -> u32 {
+	let value: u32;
+	asm!(
+		"in eax, dx",
+		out("eax") value,
+		in("dx") port,
+		options(nomem, nostack, preserves_flags)
+	);
+	value
+}
+
+/// Write a doubleword to an I/O port
+#[inline(always)]
+pub unsafe fn outl(port: u16, value: u32) {
+	asm!(
+		"out dx, eax",
+		in("dx") port,
+		in("eax") value,
+		options(nomem, nostack, preserves_flags)
+	);
+}
+
+/// Give slow devices some time to catch up by writing to an unused port
+#[inline(always)]
+pub unsafe fn io_wait() {
+	outb(0x80, 0);
+}
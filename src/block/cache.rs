@@ -0,0 +1,272 @@
+//! Buffer cache. Devices are read and written in page sized blocks kept in
+//! memory, least recently used blocks making room for new ones. Writes only
+//! reach the device when their block is evicted or `sync` is called.
+
+use super::{BlockDevice, BlockError, DeviceId};
+use crate::mm::{pmm, PAGE_SIZE};
+use core::slice;
+use spin::Mutex;
+
+pub const BLOCK_SIZE: usize = PAGE_SIZE;
+/// Blocks kept in memory, 256 KiB worth
+const CACHE_BLOCKS: usize = 64;
+
+#[derive(Clone, Copy)]
+struct Entry {
+	/// `None` if the slot is empty
+	device: Option<DeviceId>,
+	block: u64,
+	dirty: bool,
+	/// `Cache::clock` when the block was last touched
+	last_used: u64,
+}
+
+impl Entry {
+	const EMPTY: Self = Self {
+		device: None,
+		block: 0,
+		dirty: false,
+		last_used: 0,
+	};
+}
+
+struct Cache {
+	entries: [Entry; CACHE_BLOCKS],
+	/// `CACHE_BLOCKS` pages from the PMM, one per entry
+	data: Option<usize>,
+	clock: u64,
+}
+
+static CACHE: Mutex<Cache> = Mutex::new(Cache {
+	entries: [Entry::EMPTY; CACHE_BLOCKS],
+	data: None,
+	clock: 0,
+});
+
+fn device(id: DeviceId) -> Result<&'static dyn BlockDevice, BlockError> {
+	super::device(id).ok_or(BlockError::NoDevice)
+}
+
+/// The part of the device's sectors that block `block` covers, as the first
+/// sector and the number of bytes that are actually on the device
+fn block_extent(device: &dyn BlockDevice, block: u64) -> (u64, usize) {
+	let per_block = (BLOCK_SIZE / device.sector_size()) as u64;
+	let first = block * per_block;
+	// the last block can hang off the end of the device
+	let sectors = per_block.min(device.sectors().saturating_sub(first));
+
+	(first, sectors as usize * device.sector_size())
+}
+
+impl Cache {
+	fn buffer(&mut self, index: usize) -> &mut [u8] {
+		let data = self.data.expect("Buffer cache used before cache::init");
+
+		// SAFETY: the PMM handed us these pages, and only this cache (whose
+		// lock is held) touches them
+		unsafe {
+			slice::from_raw_parts_mut(
+				(data + index * BLOCK_SIZE) as *mut u8,
+				BLOCK_SIZE,
+			)
+		}
+	}
+
+	fn write_back(&mut self, index: usize) -> Result<(), BlockError> {
+		let entry = self.entries[index];
+		let id = match entry.device {
+			Some(id) if entry.dirty => id,
+			_ => return Ok(()),
+		};
+
+		let device = device(id)?;
+		let (sector, len) = block_extent(device, entry.block);
+		device.write(sector, &self.buffer(index)[..len])?;
+
+		self.entries[index].dirty = false;
+		Ok(())
+	}
+
+	/// The least recently used entry `usable` accepts. Empty slots have never
+	/// been used, so they go first.
+	fn oldest(&self, usable: impl Fn(&Entry) -> bool) -> Option<usize> {
+		(0..CACHE_BLOCKS)
+			.filter(|&index| usable(&self.entries[index]))
+			.min_by_key(|&index| match self.entries[index].device {
+				Some(_) => self.entries[index].last_used,
+				None => 0,
+			})
+	}
+
+	/// Index of the entry holding block `block` of `id`, reading it in if it
+	/// isn't cached yet
+	fn lookup(
+		&mut self,
+		id: DeviceId,
+		block: u64,
+	) -> Result<usize, BlockError> {
+		self.clock += 1;
+		let now = self.clock;
+
+		let cached = self
+			.entries
+			.iter()
+			.position(|entry| entry.device == Some(id) && entry.block == block);
+		if let Some(index) = cached {
+			self.entries[index].last_used = now;
+			return Ok(index);
+		}
+
+		let victim = self.oldest(|_| true).unwrap_or(0);
+		// a block that won't write back has to stay cached so the write
+		// isn't lost, so the oldest one with nothing to write goes instead
+		let index = match self.write_back(victim) {
+			Ok(()) => victim,
+			Err(e) => self.oldest(|entry| !entry.dirty).ok_or(e)?,
+		};
+		self.entries[index] = Entry::EMPTY;
+
+		let device = device(id)?;
+		let (sector, len) = block_extent(device, block);
+		let buffer = self.buffer(index);
+		device.read(sector, &mut buffer[..len])?;
+		buffer[len..].fill(0);
+
+		self.entries[index] = Entry {
+			device: Some(id),
+			block,
+			dirty: false,
+			last_used: now,
+		};
+		Ok(index)
+	}
+}
+
+/// Grab memory for the cache. Nothing can be read or written before this.
+pub fn init() {
+	let data = pmm::alloc_pages(CACHE_BLOCKS) as usize;
+	CACHE.lock().data = Some(data);
+}
+
+/// Make sure `len` bytes at `offset` are on the device
+fn check_bounds(
+	device: &dyn BlockDevice,
+	offset: u64,
+	len: usize,
+) -> Result<(), BlockError> {
+	let size = device.sectors() * device.sector_size() as u64;
+	match offset.checked_add(len as u64) {
+		Some(end) if end <= size => Ok(()),
+		_ => Err(BlockError::OutOfRange),
+	}
+}
+
+/// Run `f` on each cached block `len` bytes at `offset` touch, with the
+/// entry index and the ranges in the block and in the caller's buffer
+fn for_each_block(
+	cache: &mut Cache,
+	id: DeviceId,
+	offset: u64,
+	len: usize,
+	mut f: impl FnMut(&mut Cache, usize, usize, usize, usize),
+) -> Result<(), BlockError> {
+	let mut done = 0;
+	while done < len {
+		let position = offset + done as u64;
+		let block = position / BLOCK_SIZE as u64;
+		let within = (position % BLOCK_SIZE as u64) as usize;
+		let count = (BLOCK_SIZE - within).min(len - done);
+
+		let index = cache.lookup(id, block)?;
+		f(cache, index, within, done, count);
+		done += count;
+	}
+	Ok(())
+}
+
+/// Read any number of bytes at any offset, through the cache
+pub fn read(
+	id: DeviceId,
+	offset: u64,
+	buf: &mut [u8],
+) -> Result<(), BlockError> {
+	check_bounds(device(id)?, offset, buf.len())?;
+
+	let mut cache = CACHE.lock();
+	for_each_block(
+		&mut cache,
+		id,
+		offset,
+		buf.len(),
+		|cache, index, within, done, count| {
+			let block = &cache.buffer(index)[within..within + count];
+			buf[done..done + count].copy_from_slice(block);
+		},
+	)
+}
+
+/// Write any number of bytes at any offset into the cache. They reach the
+/// device later, see `sync`.
+pub fn write(id: DeviceId, offset: u64, buf: &[u8]) -> Result<(), BlockError> {
+	let device = device(id)?;
+	if device.read_only() {
+		return Err(BlockError::ReadOnly);
+	}
+	check_bounds(device, offset, buf.len())?;
+
+	let mut cache = CACHE.lock();
+	for_each_block(
+		&mut cache,
+		id,
+		offset,
+		buf.len(),
+		|cache, index, within, done, count| {
+			let block = &mut cache.buffer(index)[within..within + count];
+			block.copy_from_slice(&buf[done..done + count]);
+			cache.entries[index].dirty = true;
+		},
+	)
+}
+
+/// Write every dirty block back and flush the devices they belong to,
+/// returning the first error once everything has been tried
+pub fn sync() -> Result<(), BlockError> {
+	let mut cache = CACHE.lock();
+
+	// one bad device shouldn't stop everything else from being written
+	let mut result = Ok(());
+	for index in 0..CACHE_BLOCKS {
+		result = result.and(cache.write_back(index));
+	}
+	for (_, device) in super::devices() {
+		result = result.and(device.flush());
+	}
+	result
+}
+
+/// Forget every cached block of `id` (say, because something changed the
+/// device behind the cache's back), writing dirty ones back first. Blocks
+/// that can't be written back are kept, since they're the only copy; the
+/// first error is returned once everything else has been dropped.
+pub fn invalidate(id: DeviceId) -> Result<(), BlockError> {
+	let mut cache = CACHE.lock();
+
+	let mut result = Ok(());
+	for index in 0..CACHE_BLOCKS {
+		if cache.entries[index].device == Some(id) {
+			match cache.write_back(index) {
+				Ok(()) => cache.entries[index] = Entry::EMPTY,
+				Err(e) => result = result.and(Err(e)),
+			}
+		}
+	}
+	result
+}
+
+/// Blocks cached and how many of them are dirty
+pub fn usage() -> (usize, usize) {
+	let cache = CACHE.lock();
+	let cached = cache.entries.iter().filter(|e| e.device.is_some());
+
+	(cached.clone().count(), cached.filter(|e| e.dirty).count())
+}
@@ -0,0 +1,101 @@
+//! Block devices, anything that stores data in fixed size sectors. Drivers
+//! register their devices here, and everything else reads and writes them
+//! through the buffer cache in `cache` rather than directly.
+
+pub mod cache;
+
+use spin::Mutex;
+
+const MAX_DEVICES: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlockError {
+	/// Past the end of the device
+	OutOfRange,
+	/// The buffer isn't a whole number of sectors
+	Unaligned,
+	ReadOnly,
+	/// No device is registered with that ID
+	NoDevice,
+	/// The device didn't answer in time
+	Timeout,
+	/// The device reported an error, with whatever status it gave
+	Device(u32),
+}
+
+/// Something that stores sectors. Methods take `&self` since devices are
+/// shared; drivers lock whatever state they need.
+pub trait BlockDevice: Sync {
+	fn name(&self) -> &str;
+
+	/// Bytes per sector, a power of two no bigger than a page
+	fn sector_size(&self) -> usize;
+
+	/// Capacity in sectors
+	fn sectors(&self) -> u64;
+
+	fn read_only(&self) -> bool {
+		false
+	}
+
+	/// Read whole sectors starting at `sector` into `buf`
+	fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), BlockError>;
+
+	/// Write whole sectors starting at `sector` from `buf`
+	fn write(&self, sector: u64, buf: &[u8]) -> Result<(), BlockError>;
+
+	/// Make sure everything written so far is actually stored
+	fn flush(&self) -> Result<(), BlockError> {
+		Ok(())
+	}
+}
+
+/// Check a transfer of `len` bytes starting at `sector` fits on `device`,
+/// returning how many sectors it is
+pub fn check_range(
+	device: &dyn BlockDevice,
+	sector: u64,
+	len: usize,
+) -> Result<u64, BlockError> {
+	if len % device.sector_size() != 0 {
+		return Err(BlockError::Unaligned);
+	}
+
+	let count = (len / device.sector_size()) as u64;
+	match sector.checked_add(count) {
+		Some(end) if end <= device.sectors() => Ok(count),
+		_ => Err(BlockError::OutOfRange),
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceId(usize);
+
+static DEVICES: Mutex<[Option<&'static dyn BlockDevice>; MAX_DEVICES]> =
+	Mutex::new([None; MAX_DEVICES]);
+
+/// Add a device, or `None` if there's no room left
+pub fn register(device: &'static dyn BlockDevice) -> Option<DeviceId> {
+	let mut devices = DEVICES.lock();
+	let index = devices.iter().position(Option::is_none)?;
+
+	devices[index] = Some(device);
+	Some(DeviceId(index))
+}
+
+pub fn device(id: DeviceId) -> Option<&'static dyn BlockDevice> {
+	DEVICES.lock().get(id.0).copied().flatten()
+}
+
+/// Every registered device, in the order they were registered
+pub fn devices() -> impl Iterator<Item = (DeviceId, &'static dyn BlockDevice)> {
+	let devices = *DEVICES.lock();
+	(0..MAX_DEVICES)
+		.filter_map(move |index| Some((DeviceId(index), devices[index]?)))
+}
+
+pub fn by_name(name: &str) -> Option<DeviceId> {
+	devices()
+		.find(|(_, device)| device.name() == name)
+		.map(|(id, _)| id)
+}
@@ -35,3 +35,30 @@ impl StivaleInfo {
 }
 
 pub static STIVALE_STRUCT: StivaleInfo = StivaleInfo(UnsafeCell::new(None));
+
+fn find_module(name: &str) -> Option<(usize, usize)> {
+	let module = STIVALE_STRUCT
+		.inner()
+		.modules()?
+		.iter()
+		.find(|module| module.as_str() == name)?;
+
+	Some((module.start_address() as usize, module.size() as usize))
+}
+
+/// Contents of the boot module whose string is `name`, if the bootloader
+/// loaded one
+pub fn module(name: &str) -> Option<&'static [u8]> {
+	let (start, size) = find_module(name)?;
+
+	// SAFETY: modules live in bootloader-reclaimable memory, which the PMM
+	// never hands out, inside the identity-mapped first 4 GiB
+	unsafe { Some(core::slice::from_raw_parts(start as *const u8, size)) }
+}
+
+/// Like `module`, but writable. The caller has to make sure nothing else
+/// ever gets at the same module.
+pub unsafe fn module_mut(name: &str) -> Option<&'static mut [u8]> {
+	let (start, size) = find_module(name)?;
+	Some(core::slice::from_raw_parts_mut(start as *mut u8, size))
+}
@@ -0,0 +1,137 @@
+//! What the HBA reads from memory to run a command: a header in the port's
+//! command list, pointing at a table holding the FIS to send and the PRDT
+//! (physical region descriptor table) saying where the data goes.
+
+const FIS_REGISTER_HOST_TO_DEVICE: u8 = 0x27;
+/// Set when the FIS carries a command rather than a device control update
+const FIS_COMMAND: u8 = 1 << 7;
+const DEVICE_LBA: u8 = 1 << 6;
+
+const HEADER_FIS_DWORDS: u16 = (core::mem::size_of::<RegisterFis>() / 4) as u16;
+/// Data goes from memory to the device
+const HEADER_WRITE: u16 = 1 << 6;
+
+/// Ask for an interrupt once the entry's data has been moved
+const PRDT_INTERRUPT: u32 = 1 << 31;
+
+/// Host to device register FIS, which is how ATA commands are sent
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct RegisterFis {
+	fis_type: u8,
+	flags: u8,
+	command: u8,
+	features_low: u8,
+	lba_low: [u8; 3],
+	device: u8,
+	lba_high: [u8; 3],
+	features_high: u8,
+	count: u16,
+	icc: u8,
+	control: u8,
+	reserved: u32,
+}
+
+impl RegisterFis {
+	pub fn new(command: u8) -> Self {
+		Self {
+			fis_type: FIS_REGISTER_HOST_TO_DEVICE,
+			flags: FIS_COMMAND,
+			command,
+			device: DEVICE_LBA,
+			..Self::default()
+		}
+	}
+
+	pub fn lba(mut self, lba: u64) -> Self {
+		let bytes = lba.to_le_bytes();
+		self.lba_low.copy_from_slice(&bytes[..3]);
+		self.lba_high.copy_from_slice(&bytes[3..6]);
+		self
+	}
+
+	pub fn count(mut self, count: u16) -> Self {
+		self.count = count;
+		self
+	}
+
+	/// NCQ commands move the sector count to the features register, making
+	/// room for the slot's tag in the count register
+	pub fn queued(mut self, count: u16, tag: u8) -> Self {
+		let [low, high] = count.to_le_bytes();
+		self.features_low = low;
+		self.features_high = high;
+		self.count = u16::from(tag) << 3;
+		self
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct Header {
+	/// FIS length in dwords, direction and a few other bits
+	flags: u16,
+	prdt_entries: u16,
+	/// Bytes the HBA has moved so far
+	transferred: u32,
+	table: u64,
+	reserved: [u32; 4],
+}
+
+impl Header {
+	/// Command whose table is at physical address `table`, with `prdt_entries`
+	/// entries in it
+	pub fn new(table: u64, prdt_entries: u16, write: bool) -> Self {
+		let direction = if write { HEADER_WRITE } else { 0 };
+		Self {
+			flags: HEADER_FIS_DWORDS | direction,
+			prdt_entries,
+			table,
+			..Self::default()
+		}
+	}
+}
+
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct PrdtEntry {
+	address: u64,
+	reserved: u32,
+	/// Byte count minus one, and the interrupt bit
+	count: u32,
+}
+
+impl PrdtEntry {
+	/// `len` bytes at physical address `address`. `len` has to be even and
+	/// at most 4 MiB.
+	pub fn new(address: u64, len: usize) -> Self {
+		Self {
+			address,
+			reserved: 0,
+			count: (len as u32 - 1) | PRDT_INTERRUPT,
+		}
+	}
+}
+
+/// Command table with room for a single PRDT entry. The HBA wants these
+/// aligned to 128 bytes, which also pads them out to 256.
+#[repr(C, align(128))]
+pub struct Table {
+	fis: RegisterFis,
+	fis_padding: [u8; 64 - core::mem::size_of::<RegisterFis>()],
+	atapi_command: [u8; 16],
+	reserved: [u8; 48],
+	prdt: [PrdtEntry; 1],
+}
+
+impl Table {
+	pub fn new(fis: RegisterFis, prdt: PrdtEntry) -> Self {
+		Self {
+			fis,
+			fis_padding: [0; 64 - core::mem::size_of::<RegisterFis>()],
+			atapi_command: [0; 16],
+			reserved: [0; 48],
+			prdt: [prdt],
+		}
+	}
+}
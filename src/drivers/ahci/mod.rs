@@ -0,0 +1,199 @@
+//! SATA disks behind an AHCI controller (PCI class 01:06). Commands go
+//! through each port's command list and data moves by DMA, with up to
+//! `port::MAX_SLOTS` commands in flight at once (queued with NCQ when the
+//! drive can do that). Completion comes in through MSI when there's a local
+//! APIC to send it to, otherwise the ports are polled.
+
+pub mod command;
+pub mod port;
+
+use crate::{
+	arch::{apic, idt::InterruptStackFrame},
+	drivers::{
+		mmio::Mmio,
+		pci::{self, msi, Bar, Command, Function, Match},
+	},
+	kinfo, kwarn,
+	mm::IDENTITY_MAPPED,
+};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use port::PORTS;
+
+// generic host control registers
+const CAPABILITIES: usize = 0x00;
+const GLOBAL_CONTROL: usize = 0x04;
+const INTERRUPT_STATUS: usize = 0x08;
+const PORTS_IMPLEMENTED: usize = 0x0C;
+const CAPABILITIES_2: usize = 0x24;
+const HANDOFF: usize = 0x28;
+
+const CAPABILITIES_NCQ: u32 = 1 << 30;
+const CAPABILITIES_STAGGERED_SPIN_UP: u32 = 1 << 27;
+const CAPABILITIES_SLOTS_SHIFT: u32 = 8;
+const CAPABILITIES_SLOTS_MASK: u32 = 0x1F;
+const CAPABILITIES_2_HANDOFF: u32 = 1 << 0;
+
+const CONTROL_RESET: u32 = 1 << 0;
+const CONTROL_INTERRUPTS: u32 = 1 << 1;
+const CONTROL_AHCI_ENABLE: u32 = 1 << 31;
+
+const HANDOFF_BIOS_OWNED: u32 = 1 << 0;
+const HANDOFF_OS_OWNED: u32 = 1 << 1;
+const HANDOFF_BIOS_BUSY: u32 = 1 << 4;
+
+const PORT_REGISTERS: usize = 0x100;
+const PORT_STRIDE: usize = 0x80;
+const PORT_COUNT: usize = 32;
+
+/// The HBA's registers are in BAR 5, called ABAR in the spec
+const ABAR: usize = 5;
+const MAX_CONTROLLERS: usize = 2;
+
+/// How long the HBA and its ports get to act on a register write, in
+/// milliseconds
+pub const REGISTER_TIMEOUT_MS: u64 = 1000;
+
+/// HBA registers of each controller found, 0 for unused entries
+static CONTROLLERS: [AtomicUsize; MAX_CONTROLLERS] =
+	[AtomicUsize::new(0), AtomicUsize::new(0)];
+
+extern "x86-interrupt" fn interrupt(_frame: InterruptStackFrame) {
+	// every controller shares this handler, and a port with nothing to
+	// report is cheap to check
+	for port in PORTS.iter() {
+		port.reap();
+	}
+	// port statuses have to be cleared before the HBA's
+	for controller in CONTROLLERS.iter() {
+		let base = controller.load(Ordering::Acquire);
+		if base != 0 {
+			// SAFETY: only ever set to an HBA's registers, in `probe`
+			let hba = unsafe { Mmio::new(base) };
+			hba.write(INTERRUPT_STATUS, hba.read(INTERRUPT_STATUS));
+		}
+	}
+
+	apic::eoi();
+}
+
+/// Take the HBA from the firmware if it says it's holding onto it
+fn take_ownership(hba: Mmio) {
+	if hba.read(CAPABILITIES_2) & CAPABILITIES_2_HANDOFF == 0 {
+		return;
+	}
+
+	hba.write(HANDOFF, hba.read(HANDOFF) | HANDOFF_OS_OWNED);
+	if !hba.poll(HANDOFF, HANDOFF_BIOS_OWNED, 0, REGISTER_TIMEOUT_MS)
+		|| !hba.poll(HANDOFF, HANDOFF_BIOS_BUSY, 0, REGISTER_TIMEOUT_MS)
+	{
+		kwarn!("AHCI: firmware won't let go of the HBA, taking it anyway");
+	}
+}
+
+fn reset(hba: Mmio) -> bool {
+	hba.write(GLOBAL_CONTROL, CONTROL_AHCI_ENABLE);
+	hba.write(GLOBAL_CONTROL, CONTROL_AHCI_ENABLE | CONTROL_RESET);
+	if !hba.poll(GLOBAL_CONTROL, CONTROL_RESET, 0, REGISTER_TIMEOUT_MS) {
+		return false;
+	}
+
+	// the reset turns AHCI mode back off on some HBAs
+	hba.write(GLOBAL_CONTROL, CONTROL_AHCI_ENABLE);
+	true
+}
+
+fn probe(function: &Function) -> bool {
+	let address = match function.bar(ABAR) {
+		Some(Bar::Memory { address, size, .. })
+			if address + size <= IDENTITY_MAPPED =>
+		{
+			address as usize
+		}
+		_ => {
+			kwarn!("AHCI: {} has no usable ABAR", function.address);
+			return false;
+		}
+	};
+	let controller = match CONTROLLERS
+		.iter()
+		.find(|controller| controller.load(Ordering::Acquire) == 0)
+	{
+		Some(controller) => controller,
+		None => {
+			kwarn!("AHCI: too many controllers, ignoring {}", function.address);
+			return false;
+		}
+	};
+
+	function.enable(Command::MEMORY_SPACE | Command::BUS_MASTER);
+	// SAFETY: the ABAR is a memory BAR inside the identity map
+	let hba = unsafe { Mmio::new(address) };
+	take_ownership(hba);
+	if !reset(hba) {
+		kwarn!("AHCI: {} didn't come out of reset", function.address);
+		return false;
+	}
+
+	let capabilities = hba.read(CAPABILITIES);
+	let slots = ((capabilities >> CAPABILITIES_SLOTS_SHIFT)
+		& CAPABILITIES_SLOTS_MASK) as usize
+		+ 1;
+	let ncq = capabilities & CAPABILITIES_NCQ != 0;
+	let staggered = capabilities & CAPABILITIES_STAGGERED_SPIN_UP != 0;
+
+	controller.store(address, Ordering::Release);
+	let interrupts = match msi::enable(function, interrupt) {
+		Ok(vector) => {
+			hba.write(INTERRUPT_STATUS, !0);
+			hba.write(GLOBAL_CONTROL, CONTROL_AHCI_ENABLE | CONTROL_INTERRUPTS);
+			kinfo!(
+				"AHCI: {} interrupts on vector {}",
+				function.address,
+				vector
+			);
+			true
+		}
+		Err(e) => {
+			kwarn!("AHCI: no MSI for {} ({:?}), polling", function.address, e);
+			false
+		}
+	};
+
+	let implemented = hba.read(PORTS_IMPLEMENTED);
+	let mut found = false;
+	for number in (0..PORT_COUNT).filter(|n| implemented & 1 << n != 0) {
+		let registers = hba.at(PORT_REGISTERS + number * PORT_STRIDE);
+		// nothing on the port is known until it's been spun up after the
+		// reset
+		if !port::spin_up(registers, staggered) || !port::is_ata(registers) {
+			continue;
+		}
+		let port = match PORTS.iter().find(|port| !port.in_use()) {
+			Some(port) => port,
+			None => {
+				kwarn!("AHCI: no room for any more drives");
+				break;
+			}
+		};
+
+		found |= port.init(registers, slots, ncq, interrupts);
+	}
+	found
+}
+
+static DRIVER: pci::Driver = pci::Driver {
+	name: "ahci",
+	matches: &[Match::Class {
+		class: 0x01,
+		subclass: 0x06,
+		prog_if: Some(0x01),
+	}],
+	probe,
+};
+
+/// Start handling AHCI controllers, now and whenever PCI finds one
+pub fn init() {
+	if !pci::register_driver(&DRIVER) {
+		kwarn!("AHCI: couldn't register the PCI driver");
+	}
+}
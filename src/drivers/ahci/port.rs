@@ -0,0 +1,666 @@
+//! A port of an HBA with a SATA drive on it, which is what gets registered
+//! as a block device. Data goes through a bounce buffer for each command
+//! slot (see `mm::IDENTITY_MAPPED`).
+
+use super::{
+	command::{Header, PrdtEntry, RegisterFis, Table},
+	REGISTER_TIMEOUT_MS,
+};
+use crate::{
+	arch::{cpu, pit},
+	block::{self, BlockDevice, BlockError},
+	drivers::{
+		ata::{self, Info, SECTOR_SIZE},
+		mmio::Mmio,
+	},
+	kinfo, kwarn,
+	mm::{pmm, PAGE_SIZE},
+};
+use bitflags::bitflags;
+use core::{
+	ptr, slice,
+	sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+};
+use spin::Mutex;
+
+/// Commands in flight at once on a port, whatever the HBA and drive allow
+pub const MAX_SLOTS: usize = 8;
+pub const MAX_PORTS: usize = 8;
+
+const BOUNCE_PAGES: usize = 4;
+const BOUNCE_SIZE: usize = BOUNCE_PAGES * PAGE_SIZE;
+
+// each port gets one page, holding the command list (room for all 32
+// headers), the received FIS area and a command table per slot
+const LIST_OFFSET: usize = 0x000;
+const RECEIVED_FIS_OFFSET: usize = 0x400;
+const TABLES_OFFSET: usize = 0x800;
+const TABLE_SIZE: usize = 0x100;
+const HEADER_SIZE: usize = 0x20;
+
+// port registers
+const LIST_BASE: usize = 0x00;
+const LIST_BASE_HIGH: usize = 0x04;
+const FIS_BASE: usize = 0x08;
+const FIS_BASE_HIGH: usize = 0x0C;
+const INTERRUPT_STATUS: usize = 0x10;
+const INTERRUPT_ENABLE: usize = 0x14;
+const COMMAND: usize = 0x18;
+const TASK_FILE: usize = 0x20;
+const SIGNATURE: usize = 0x24;
+const SATA_STATUS: usize = 0x28;
+const SATA_CONTROL: usize = 0x2C;
+const SATA_ERROR: usize = 0x30;
+const SATA_ACTIVE: usize = 0x34;
+const COMMAND_ISSUE: usize = 0x38;
+
+const TASK_FILE_BUSY: u32 = 1 << 7;
+const TASK_FILE_DATA_REQUEST: u32 = 1 << 3;
+const TASK_FILE_ERROR_SHIFT: u32 = 8;
+
+/// Device detection in the SATA status: a device is there and talking
+const STATUS_DETECTED: u32 = 0x3;
+const STATUS_DETECTION_MASK: u32 = 0xF;
+/// Device detection in the SATA control: send COMRESET for as long as it's
+/// set
+const CONTROL_DETECTION_RESET: u32 = 0x1;
+const CONTROL_DETECTION_MASK: u32 = 0xF;
+const SIGNATURE_ATA: u32 = 0x0000_0101;
+/// What the signature reads as until the drive's first FIS arrives
+const SIGNATURE_NONE: u32 = 0xFFFF_FFFF;
+
+const READ_DMA_EXT: u8 = 0x25;
+const WRITE_DMA_EXT: u8 = 0x35;
+const READ_FPDMA_QUEUED: u8 = 0x60;
+const WRITE_FPDMA_QUEUED: u8 = 0x61;
+const READ_LOG_EXT: u8 = 0x2F;
+
+/// Log page saying which NCQ command failed. Reading it is also what gets
+/// the drive to take commands again after one does.
+const NCQ_ERROR_LOG: u64 = 0x10;
+/// Set in the log's first byte if the error wasn't from an NCQ command
+const NCQ_ERROR_LOG_NOT_QUEUED: u8 = 1 << 7;
+const NCQ_ERROR_LOG_TAG_MASK: u8 = 0x1F;
+
+// IDENTIFY words about NCQ
+const IDENTIFY_QUEUE_DEPTH: usize = 75;
+const IDENTIFY_SATA_CAPABILITIES: usize = 76;
+const QUEUE_DEPTH_MASK: u16 = 0x1F;
+const SUPPORTS_NCQ: u16 = 1 << 8;
+
+/// How long a command gets before the port is restarted, in milliseconds
+const TIMEOUT_MS: u64 = 5000;
+/// How long a port gets to start talking to its drive, if there is one
+const LINK_TIMEOUT_MS: u64 = 100;
+/// How long a drive gets to spin up and send its first FIS
+const SIGNATURE_TIMEOUT_MS: u64 = 10_000;
+/// How long COMRESET is held; the spec asks for at least 1ms
+const COMRESET_MS: u64 = 1;
+
+bitflags! {
+	struct PortCommand: u32 {
+		const START = 1 << 0;
+		const SPIN_UP = 1 << 1;
+		const POWER_ON = 1 << 2;
+		const FIS_RECEIVE = 1 << 4;
+		const FIS_RUNNING = 1 << 14;
+		const LIST_RUNNING = 1 << 15;
+	}
+}
+
+bitflags! {
+	/// Bits in both the interrupt status and enable registers
+	struct Interrupts: u32 {
+		/// A command without NCQ finished
+		const DEVICE_TO_HOST = 1 << 0;
+		const PIO_SETUP = 1 << 1;
+		const DMA_SETUP = 1 << 2;
+		/// An NCQ command finished
+		const SET_DEVICE_BITS = 1 << 3;
+		const DESCRIPTOR_PROCESSED = 1 << 5;
+		const OVERFLOW = 1 << 24;
+		const INTERFACE_NOT_FATAL = 1 << 26;
+		const INTERFACE_FATAL = 1 << 27;
+		const HOST_BUS_DATA = 1 << 28;
+		const HOST_BUS_FATAL = 1 << 29;
+		const TASK_FILE_ERROR = 1 << 30;
+
+		/// Ones that stop the port
+		const ERRORS = Self::OVERFLOW.bits
+			| Self::INTERFACE_FATAL.bits
+			| Self::HOST_BUS_DATA.bits
+			| Self::HOST_BUS_FATAL.bits
+			| Self::TASK_FILE_ERROR.bits;
+	}
+}
+
+/// Everything set up for a port, only touched with its lock held
+struct State {
+	registers: Mmio,
+	/// Page holding the command list, received FISes and command tables
+	memory: usize,
+	/// `MAX_SLOTS` bounce buffers of `BOUNCE_SIZE`
+	bounce: usize,
+	/// Slots actually used, no more than `MAX_SLOTS`
+	slots: usize,
+	ncq: bool,
+	sectors: u64,
+}
+
+impl State {
+	fn bounce(&mut self, slot: usize, len: usize) -> &mut [u8] {
+		// SAFETY: the buffers came from the PMM for this port alone, and the
+		// HBA is only ever pointed at one while its command is running
+		unsafe {
+			slice::from_raw_parts_mut(
+				(self.bounce + slot * BOUNCE_SIZE) as *mut u8,
+				len,
+			)
+		}
+	}
+}
+
+pub struct Port {
+	name: &'static str,
+	/// Base of the port's registers, 0 until a drive is set up on it
+	registers: AtomicUsize,
+	/// Slots issued and not seen finishing yet
+	issued: AtomicU32,
+	/// Slots that finished, until the waiter picks them up
+	finished: AtomicU32,
+	/// Slots the port gave up on, until the waiter picks them up
+	failed: AtomicU32,
+	/// Task file register as of the last failure
+	task_file: AtomicU32,
+	/// Whether the HBA's interrupts reap finished commands, or the waiter
+	/// has to poll
+	interrupts: AtomicBool,
+	state: Mutex<Option<State>>,
+}
+
+pub static PORTS: [Port; MAX_PORTS] = [
+	Port::new("sata0"),
+	Port::new("sata1"),
+	Port::new("sata2"),
+	Port::new("sata3"),
+	Port::new("sata4"),
+	Port::new("sata5"),
+	Port::new("sata6"),
+	Port::new("sata7"),
+];
+
+/// Spin up the port's drive (ports start off powered down on HBAs with
+/// staggered spin-up) and wait for it to report in, returning false if
+/// there's nothing on the port
+pub fn spin_up(registers: Mmio, staggered: bool) -> bool {
+	if staggered {
+		let command = PortCommand::from_bits_truncate(registers.read(COMMAND));
+		let command = command | PortCommand::SPIN_UP | PortCommand::POWER_ON;
+		registers.write(COMMAND, command.bits());
+	}
+
+	wait_for_drive(registers)
+}
+
+/// Wait for the link to come up and the drive to send its signature, which
+/// it does after powering up or being reset
+fn wait_for_drive(registers: Mmio) -> bool {
+	let detected = registers.poll(
+		SATA_STATUS,
+		STATUS_DETECTION_MASK,
+		STATUS_DETECTED,
+		LINK_TIMEOUT_MS,
+	);
+	// the signature is only valid once the drive stops being busy
+	detected
+		&& pit::wait_until(false, SIGNATURE_TIMEOUT_MS, || {
+			registers.read(TASK_FILE) & TASK_FILE_BUSY == 0
+				&& registers.read(SIGNATURE) != SIGNATURE_NONE
+		})
+}
+
+/// Reset the link and the drive on it, for when nothing else gets the port
+/// going. It has to be stopped first. Returns whether the same kind of drive
+/// came back.
+fn comreset(registers: Mmio) -> bool {
+	let control = registers.read(SATA_CONTROL) & !CONTROL_DETECTION_MASK;
+	registers.write(SATA_CONTROL, control | CONTROL_DETECTION_RESET);
+	pit::wait_until(true, COMRESET_MS, || false);
+	registers.write(SATA_CONTROL, control);
+
+	let back = wait_for_drive(registers);
+	// the link going down and up again leaves errors behind
+	registers.write(SATA_ERROR, !0);
+	back && is_ata(registers)
+}
+
+/// Whether there's an ATA drive on the port (rather than nothing, or an
+/// ATAPI drive or port multiplier)
+pub fn is_ata(registers: Mmio) -> bool {
+	registers.read(SATA_STATUS) & STATUS_DETECTION_MASK == STATUS_DETECTED
+		&& registers.read(SIGNATURE) == SIGNATURE_ATA
+}
+
+/// Stop the port processing its command list and receiving FISes
+fn stop(registers: Mmio) -> bool {
+	let command = PortCommand::from_bits_truncate(registers.read(COMMAND));
+	registers.write(COMMAND, (command - PortCommand::START).bits());
+	let running = PortCommand::LIST_RUNNING.bits();
+	if !registers.poll(COMMAND, running, 0, REGISTER_TIMEOUT_MS) {
+		return false;
+	}
+
+	let command = PortCommand::from_bits_truncate(registers.read(COMMAND));
+	registers.write(COMMAND, (command - PortCommand::FIS_RECEIVE).bits());
+	let running = PortCommand::FIS_RUNNING.bits();
+	registers.poll(COMMAND, running, 0, REGISTER_TIMEOUT_MS)
+}
+
+fn start(registers: Mmio) -> bool {
+	let busy = TASK_FILE_BUSY | TASK_FILE_DATA_REQUEST;
+	let idle = registers.poll(TASK_FILE, busy, 0, REGISTER_TIMEOUT_MS);
+
+	let command = PortCommand::from_bits_truncate(registers.read(COMMAND));
+	registers.write(COMMAND, (command | PortCommand::FIS_RECEIVE).bits());
+	let command = command | PortCommand::FIS_RECEIVE | PortCommand::START;
+	registers.write(COMMAND, command.bits());
+	idle
+}
+
+impl Port {
+	const fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			registers: AtomicUsize::new(0),
+			issued: AtomicU32::new(0),
+			finished: AtomicU32::new(0),
+			failed: AtomicU32::new(0),
+			task_file: AtomicU32::new(0),
+			interrupts: AtomicBool::new(false),
+			state: Mutex::new(None),
+		}
+	}
+
+	pub fn in_use(&self) -> bool {
+		self.registers.load(Ordering::Acquire) != 0
+	}
+
+	/// Note which issued commands have finished or failed. Runs in the
+	/// interrupt handler, or in the waiter when there are no interrupts.
+	pub fn reap(&self) {
+		let base = self.registers.load(Ordering::Acquire);
+		if base == 0 {
+			return;
+		}
+		// SAFETY: only ever set to the port's registers, in `init`
+		let registers = unsafe { Mmio::new(base) };
+
+		let status = registers.read(INTERRUPT_STATUS);
+		registers.write(INTERRUPT_STATUS, status);
+		let issued = self.issued.load(Ordering::Acquire);
+
+		if Interrupts::from_bits_truncate(status).intersects(Interrupts::ERRORS)
+		{
+			// the port stops on an error, taking everything in flight with it
+			self.task_file
+				.store(registers.read(TASK_FILE), Ordering::Release);
+			self.issued.fetch_and(!issued, Ordering::AcqRel);
+			self.failed.fetch_or(issued, Ordering::AcqRel);
+			return;
+		}
+
+		// NCQ commands leave the issue register once the drive has queued
+		// them, and the active register once they're done
+		let busy = registers.read(COMMAND_ISSUE) | registers.read(SATA_ACTIVE);
+		let done = issued & !busy;
+		self.issued.fetch_and(!done, Ordering::AcqRel);
+		self.finished.fetch_or(done, Ordering::AcqRel);
+	}
+
+	/// Set slot `slot` up to send `fis`, moving `len` bytes of the slot's
+	/// bounce buffer
+	fn prepare(
+		&self,
+		state: &State,
+		slot: usize,
+		fis: RegisterFis,
+		len: usize,
+		write: bool,
+	) {
+		let table = state.memory + TABLES_OFFSET + slot * TABLE_SIZE;
+		let header = state.memory + LIST_OFFSET + slot * HEADER_SIZE;
+		let bounce = (state.bounce + slot * BOUNCE_SIZE) as u64;
+		let (prdt, prdt_entries) = match len {
+			0 => (PrdtEntry::default(), 0),
+			_ => (PrdtEntry::new(bounce, len), 1),
+		};
+
+		// SAFETY: both are inside the port's page, in the slot's own spot
+		unsafe {
+			ptr::write_volatile(table as *mut Table, Table::new(fis, prdt));
+			ptr::write_volatile(
+				header as *mut Header,
+				Header::new(table as u64, prdt_entries, write),
+			);
+		}
+	}
+
+	/// Issue the prepared slots in `mask`, and wait until they're all done.
+	/// `queued` says they're NCQ commands.
+	fn issue(
+		&self,
+		state: &State,
+		mask: u32,
+		queued: bool,
+	) -> Result<(), BlockError> {
+		let registers = state.registers;
+
+		// the interrupt handler mustn't see the slots as issued before the
+		// HBA does, or it'll think they already finished
+		cpu::without_interrupts(|| {
+			self.finished.fetch_and(!mask, Ordering::AcqRel);
+			self.failed.fetch_and(!mask, Ordering::AcqRel);
+			self.issued.fetch_or(mask, Ordering::AcqRel);
+			if queued {
+				registers.write(SATA_ACTIVE, mask);
+			}
+			registers.write(COMMAND_ISSUE, mask);
+		});
+
+		let result = self.wait(mask);
+		if result.is_err() {
+			self.recover(state, queued);
+		}
+		result
+	}
+
+	fn wait(&self, mask: u32) -> Result<(), BlockError> {
+		let interrupts = self.interrupts.load(Ordering::Acquire);
+		let mut result = Err(BlockError::Timeout);
+
+		pit::wait_until(interrupts, TIMEOUT_MS, || {
+			// nothing else is going to reap them
+			if !interrupts {
+				cpu::without_interrupts(|| self.reap());
+			}
+
+			if self.failed.load(Ordering::Acquire) & mask != 0 {
+				let task_file = self.task_file.load(Ordering::Acquire);
+				let error = (task_file >> TASK_FILE_ERROR_SHIFT) & 0xFF;
+				result = Err(BlockError::Device(error));
+				return true;
+			}
+			if self.finished.load(Ordering::Acquire) & mask == mask {
+				result = Ok(());
+				return true;
+			}
+			false
+		});
+		result
+	}
+
+	/// Get the port going again after a failed command, forgetting anything
+	/// that was in flight. `queued` says the failed commands were NCQ ones.
+	/// If restarting the port isn't enough, the drive is reset.
+	fn recover(&self, state: &State, queued: bool) {
+		let registers = state.registers;
+		let stopped = stop(registers);
+		registers.write(SATA_ERROR, !0);
+		registers.write(INTERRUPT_STATUS, !0);
+
+		cpu::without_interrupts(|| {
+			self.issued.store(0, Ordering::Release);
+			self.finished.store(0, Ordering::Release);
+			self.failed.store(0, Ordering::Release);
+		});
+
+		let restarted = stopped
+			&& start(registers)
+			&& (!queued || self.read_ncq_error_log(state).is_ok());
+		if restarted {
+			return;
+		}
+
+		kwarn!("AHCI: {} is still failing, resetting it", self.name);
+		stop(registers);
+		if !comreset(registers) {
+			kwarn!("AHCI: {} has no drive after a reset", self.name);
+			return;
+		}
+		registers.write(INTERRUPT_STATUS, !0);
+		if !start(registers) {
+			kwarn!("AHCI: {} is stuck busy after a reset", self.name);
+		}
+	}
+
+	/// Read the NCQ error log with slot 0, which a drive insists on after an
+	/// NCQ command fails (aborting every other one queued), and say what it
+	/// blames
+	fn read_ncq_error_log(&self, state: &State) -> Result<(), BlockError> {
+		let fis = RegisterFis::new(READ_LOG_EXT).lba(NCQ_ERROR_LOG).count(1);
+		self.prepare(state, 0, fis, SECTOR_SIZE, false);
+
+		cpu::without_interrupts(|| {
+			self.issued.fetch_or(1, Ordering::AcqRel);
+			state.registers.write(COMMAND_ISSUE, 1);
+		});
+		// not `issue`, which would recover again if this fails too
+		self.wait(1)?;
+
+		// SAFETY: slot 0's bounce buffer, which the HBA is done with
+		let log = unsafe { &*(state.bounce as *const [u8; 4]) };
+		if log[0] & NCQ_ERROR_LOG_NOT_QUEUED == 0 {
+			kwarn!(
+				"AHCI: {} failed NCQ tag {}, status {:#x}, error {:#x}",
+				self.name,
+				log[0] & NCQ_ERROR_LOG_TAG_MASK,
+				log[2],
+				log[3]
+			);
+		}
+		Ok(())
+	}
+
+	/// Move `len` bytes at `sector` in batches of up to `state.slots`
+	/// commands. `copy` is given each command's offset into the caller's
+	/// buffer and its bounce buffer, to fill before writing or empty after
+	/// reading.
+	fn transfer(
+		&self,
+		sector: u64,
+		len: usize,
+		write: bool,
+		mut copy: impl FnMut(usize, &mut [u8]),
+	) -> Result<(), BlockError> {
+		block::check_range(self, sector, len)?;
+		let mut state = self.state.lock();
+		let state = state.as_mut().ok_or(BlockError::NoDevice)?;
+
+		let mut done = 0;
+		while done < len {
+			let mut mask = 0;
+			let mut offsets = [0; MAX_SLOTS];
+			let mut lens = [0; MAX_SLOTS];
+
+			for slot in 0..state.slots {
+				if done == len {
+					break;
+				}
+				let count = (len - done).min(BOUNCE_SIZE);
+				let lba = sector + (done / SECTOR_SIZE) as u64;
+				let sectors = (count / SECTOR_SIZE) as u16;
+
+				if write {
+					copy(done, state.bounce(slot, count));
+				}
+				let fis = match (state.ncq, write) {
+					(true, false) => RegisterFis::new(READ_FPDMA_QUEUED)
+						.queued(sectors, slot as u8),
+					(true, true) => RegisterFis::new(WRITE_FPDMA_QUEUED)
+						.queued(sectors, slot as u8),
+					(false, false) => {
+						RegisterFis::new(READ_DMA_EXT).count(sectors)
+					}
+					(false, true) => {
+						RegisterFis::new(WRITE_DMA_EXT).count(sectors)
+					}
+				};
+				self.prepare(state, slot, fis.lba(lba), count, write);
+
+				offsets[slot] = done;
+				lens[slot] = count;
+				mask |= 1 << slot;
+				done += count;
+			}
+
+			self.issue(state, mask, state.ncq)?;
+			if !write {
+				for slot in (0..state.slots).filter(|s| mask & 1 << s != 0) {
+					copy(offsets[slot], state.bounce(slot, lens[slot]));
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Ask the drive about itself, using slot 0 without NCQ
+	fn identify(&self, state: &mut State) -> Result<[u16; 256], BlockError> {
+		let fis = RegisterFis::new(ata::IDENTIFY);
+		self.prepare(state, 0, fis, SECTOR_SIZE, false);
+		self.issue(state, 1, false)?;
+
+		let mut words = [0; 256];
+		let bytes = state.bounce(0, SECTOR_SIZE).chunks_exact(2);
+		for (word, bytes) in words.iter_mut().zip(bytes) {
+			*word = u16::from_le_bytes([bytes[0], bytes[1]]);
+		}
+		Ok(words)
+	}
+
+	/// Set the port up and register its drive. `slots` and `ncq` are what the
+	/// HBA supports.
+	pub fn init(
+		&'static self,
+		registers: Mmio,
+		slots: usize,
+		ncq: bool,
+		interrupts: bool,
+	) -> bool {
+		if !stop(registers) {
+			kwarn!("AHCI: {} won't stop", self.name);
+			return false;
+		}
+
+		let memory = pmm::alloc_pages(1) as usize;
+		let bounce = pmm::alloc_pages(MAX_SLOTS * BOUNCE_PAGES) as usize;
+		// SAFETY: fresh from the PMM
+		unsafe { ptr::write_bytes(memory as *mut u8, 0, PAGE_SIZE) };
+
+		let list = (memory + LIST_OFFSET) as u64;
+		let fis = (memory + RECEIVED_FIS_OFFSET) as u64;
+		registers.write(LIST_BASE, list as u32);
+		registers.write(LIST_BASE_HIGH, (list >> 32) as u32);
+		registers.write(FIS_BASE, fis as u32);
+		registers.write(FIS_BASE_HIGH, (fis >> 32) as u32);
+		registers.write(SATA_ERROR, !0);
+		registers.write(INTERRUPT_STATUS, !0);
+		registers.write(INTERRUPT_ENABLE, Interrupts::all().bits());
+		start(registers);
+
+		self.interrupts.store(interrupts, Ordering::Release);
+		self.registers.store(registers.base(), Ordering::Release);
+
+		let mut state = State {
+			registers,
+			memory,
+			bounce,
+			slots: 1,
+			ncq: false,
+			sectors: 0,
+		};
+		let words = match self.identify(&mut state) {
+			Ok(words) => words,
+			Err(e) => {
+				kwarn!("AHCI: couldn't identify {}: {:?}", self.name, e);
+				stop(registers);
+				self.registers.store(0, Ordering::Release);
+				// SAFETY: the HBA isn't pointed at these anymore
+				unsafe {
+					pmm::free_pages(memory as *mut u8, 1);
+					pmm::free_pages(
+						bounce as *mut u8,
+						MAX_SLOTS * BOUNCE_PAGES,
+					);
+				}
+				return false;
+			}
+		};
+
+		// every SATA drive does LBA48, which the DMA commands need
+		let info = Info::parse(&words);
+		state.sectors = info.sectors;
+		state.ncq =
+			ncq && words[IDENTIFY_SATA_CAPABILITIES] & SUPPORTS_NCQ != 0;
+		state.slots = if state.ncq {
+			let depth = (words[IDENTIFY_QUEUE_DEPTH] & QUEUE_DEPTH_MASK) + 1;
+			slots.min(depth.into()).min(MAX_SLOTS)
+		} else {
+			slots.min(MAX_SLOTS)
+		};
+
+		kinfo!(
+			"AHCI: {} is a {} MiB {}, {} slots{}",
+			self.name,
+			info.sectors * SECTOR_SIZE as u64 / (1024 * 1024),
+			info.model(),
+			state.slots,
+			if state.ncq { " (NCQ)" } else { "" }
+		);
+		*self.state.lock() = Some(state);
+
+		if block::register(self).is_none() {
+			kwarn!("AHCI: no room to register {}", self.name);
+		}
+		true
+	}
+}
+
+impl BlockDevice for Port {
+	fn name(&self) -> &str {
+		self.name
+	}
+
+	fn sector_size(&self) -> usize {
+		SECTOR_SIZE
+	}
+
+	fn sectors(&self) -> u64 {
+		self.state.lock().as_ref().map_or(0, |state| state.sectors)
+	}
+
+	fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+		self.transfer(sector, buf.len(), false, |offset, bounce| {
+			buf[offset..offset + bounce.len()].copy_from_slice(bounce)
+		})
+	}
+
+	fn write(&self, sector: u64, buf: &[u8]) -> Result<(), BlockError> {
+		self.transfer(sector, buf.len(), true, |offset, bounce| {
+			bounce.copy_from_slice(&buf[offset..offset + bounce.len()])
+		})
+	}
+
+	fn flush(&self) -> Result<(), BlockError> {
+		let state = self.state.lock();
+		let state = state.as_ref().ok_or(BlockError::NoDevice)?;
+
+		self.prepare(
+			state,
+			0,
+			RegisterFis::new(ata::CACHE_FLUSH_EXT),
+			0,
+			false,
+		);
+		self.issue(state, 1, false)
+	}
+}
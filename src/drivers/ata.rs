@@ -0,0 +1,530 @@
+//! ATA disks on an IDE controller, using PIO with polling. The controller is
+//! found through PCI (class 01:01), and each channel is used at the legacy
+//! ports unless the controller says it's in native mode. Up to four drives
+//! (a master and a slave on each channel) show up as block devices.
+
+use crate::{
+	arch::{
+		pit,
+		port::{inb, inw, outb, outw},
+	},
+	block::{self, BlockDevice, BlockError},
+	drivers::pci::{self, Bar, Command, Function, Match},
+	kinfo, kwarn,
+};
+use core::str;
+use spin::Mutex;
+
+pub const SECTOR_SIZE: usize = 512;
+
+// registers, as offsets from the channel's I/O ports
+const DATA: u16 = 0;
+const ERROR: u16 = 1;
+const SECTOR_COUNT: u16 = 2;
+const LBA_LOW: u16 = 3;
+const LBA_MID: u16 = 4;
+const LBA_HIGH: u16 = 5;
+const DRIVE_SELECT: u16 = 6;
+// reads give the status, writes send a command
+const STATUS: u16 = 7;
+const COMMAND: u16 = 7;
+// in the control block, reads give the status without acknowledging
+// anything, writes set the device control register
+const ALT_STATUS: u16 = 0;
+const DEVICE_CONTROL: u16 = 0;
+
+const STATUS_ERROR: u8 = 1 << 0;
+const STATUS_DATA_REQUEST: u8 = 1 << 3;
+const STATUS_DRIVE_FAULT: u8 = 1 << 5;
+const STATUS_BUSY: u8 = 1 << 7;
+/// What an empty channel's pulled up bus reads as
+const FLOATING_BUS: u8 = 0xFF;
+
+/// Stops the drives from raising interrupts, since everything is polled
+const CONTROL_NO_INTERRUPTS: u8 = 1 << 1;
+const CONTROL_RESET: u8 = 1 << 2;
+
+const SELECT_BASE: u8 = 0xA0;
+const SELECT_LBA: u8 = 1 << 6;
+const SELECT_SLAVE: u8 = 1 << 4;
+
+pub const IDENTIFY: u8 = 0xEC;
+const READ_SECTORS: u8 = 0x20;
+const READ_SECTORS_EXT: u8 = 0x24;
+const WRITE_SECTORS: u8 = 0x30;
+const WRITE_SECTORS_EXT: u8 = 0x34;
+const CACHE_FLUSH: u8 = 0xE7;
+pub const CACHE_FLUSH_EXT: u8 = 0xEA;
+
+// IDENTIFY words
+const IDENTIFY_MODEL: usize = 27;
+const IDENTIFY_MODEL_WORDS: usize = 20;
+const IDENTIFY_LBA28_SECTORS: usize = 60;
+const IDENTIFY_COMMAND_SETS: usize = 83;
+const IDENTIFY_LBA48_SECTORS: usize = 100;
+const SUPPORTS_LBA48: u16 = 1 << 10;
+
+/// Highest sector LBA28 can address, plus one
+const LBA28_LIMIT: u64 = 1 << 28;
+/// Sectors a single command moves, LBA28 encoding 256 as 0
+const SECTORS_PER_COMMAND: usize = 256;
+
+/// Milliseconds to wait for a drive before giving up on it
+const TIMEOUT_MS: u64 = 5000;
+/// Milliseconds to hold a channel in reset; the spec only asks for 5us, but
+/// timer ticks are as fine as waiting gets
+const RESET_HOLD_MS: u64 = 1;
+/// Milliseconds to leave the drives alone after a reset before their status
+/// means anything
+const RESET_SETTLE_MS: u64 = 2;
+
+/// Where the channels are when the controller is in compatibility mode
+const LEGACY_PORTS: [Ports; 2] = [
+	Ports {
+		io: 0x1F0,
+		control: 0x3F6,
+	},
+	Ports {
+		io: 0x170,
+		control: 0x376,
+	},
+];
+/// The control block BAR points 2 ports before the device control register
+const CONTROL_BAR_OFFSET: u16 = 2;
+
+#[derive(Clone, Copy, Debug)]
+struct Ports {
+	io: u16,
+	control: u16,
+}
+
+impl Ports {
+	unsafe fn read(self, register: u16) -> u8 {
+		inb(self.io + register)
+	}
+
+	unsafe fn write(self, register: u16, value: u8) {
+		outb(self.io + register, value)
+	}
+
+	fn alt_status(self) -> u8 {
+		unsafe { inb(self.control + ALT_STATUS) }
+	}
+
+	/// Reading the status port takes long enough that four reads give the
+	/// drive the 400ns it needs after being selected or given a command
+	fn delay(self) {
+		for _ in 0..4 {
+			self.alt_status();
+		}
+	}
+
+	// drives are usually ready within microseconds, so these spin rather
+	// than sleep until the next tick
+
+	fn wait_not_busy(self) -> Result<u8, BlockError> {
+		let mut status = 0;
+		let ready = pit::wait_until(false, TIMEOUT_MS, || {
+			status = self.alt_status();
+			status & STATUS_BUSY == 0
+		});
+
+		if ready {
+			Ok(status)
+		} else {
+			Err(BlockError::Timeout)
+		}
+	}
+
+	/// Wait until the drive wants to move a sector of data
+	fn wait_data(self) -> Result<(), BlockError> {
+		let mut status = 0;
+		let ready = pit::wait_until(false, TIMEOUT_MS, || {
+			status = self.alt_status();
+			status & STATUS_BUSY == 0
+				&& status
+					& (STATUS_ERROR | STATUS_DRIVE_FAULT | STATUS_DATA_REQUEST)
+					!= 0
+		});
+
+		if !ready {
+			Err(BlockError::Timeout)
+		} else if status & (STATUS_ERROR | STATUS_DRIVE_FAULT) != 0 {
+			Err(BlockError::Device(unsafe { self.read(ERROR) }.into()))
+		} else {
+			Ok(())
+		}
+	}
+
+	/// Software reset both drives on the channel, then wait for them to
+	/// finish
+	fn reset(self) {
+		unsafe {
+			outb(self.control + DEVICE_CONTROL, CONTROL_RESET);
+		}
+		pit::wait_until(true, RESET_HOLD_MS, || false);
+		unsafe {
+			outb(self.control + DEVICE_CONTROL, CONTROL_NO_INTERRUPTS);
+		}
+		pit::wait_until(true, RESET_SETTLE_MS, || false);
+
+		let _ = self.wait_not_busy();
+	}
+}
+
+/// What IDENTIFY said about a drive
+#[derive(Clone, Copy)]
+pub struct Info {
+	pub sectors: u64,
+	pub lba48: bool,
+	model: [u8; IDENTIFY_MODEL_WORDS * 2],
+}
+
+impl Info {
+	/// Pick apart the 256 words IDENTIFY returns
+	pub fn parse(words: &[u16; 256]) -> Self {
+		let lba48 = words[IDENTIFY_COMMAND_SETS] & SUPPORTS_LBA48 != 0;
+		let sectors = if lba48 {
+			(0..4).fold(0, |sectors, i| {
+				sectors
+					| u64::from(words[IDENTIFY_LBA48_SECTORS + i]) << (16 * i)
+			})
+		} else {
+			u64::from(words[IDENTIFY_LBA28_SECTORS])
+				| u64::from(words[IDENTIFY_LBA28_SECTORS + 1]) << 16
+		};
+
+		// the model string has the bytes of each word swapped
+		let mut model = [0; IDENTIFY_MODEL_WORDS * 2];
+		let model_words =
+			&words[IDENTIFY_MODEL..IDENTIFY_MODEL + IDENTIFY_MODEL_WORDS];
+		for (bytes, word) in model.chunks_exact_mut(2).zip(model_words) {
+			bytes.copy_from_slice(&word.to_be_bytes());
+		}
+
+		Self {
+			sectors,
+			lba48,
+			model,
+		}
+	}
+
+	pub fn model(&self) -> &str {
+		str::from_utf8(&self.model).unwrap_or("?").trim()
+	}
+}
+
+pub struct Drive {
+	name: &'static str,
+	channel: usize,
+	slave: bool,
+	info: Mutex<Option<Info>>,
+}
+
+/// Ports of each channel. Holding the lock owns the channel, since only one
+/// of its drives can do anything at a time.
+static CHANNELS: [Mutex<Option<Ports>>; 2] =
+	[Mutex::new(None), Mutex::new(None)];
+
+static DRIVES: [Drive; 4] = [
+	Drive::new("ata0", 0, false),
+	Drive::new("ata1", 0, true),
+	Drive::new("ata2", 1, false),
+	Drive::new("ata3", 1, true),
+];
+
+impl Drive {
+	const fn new(name: &'static str, channel: usize, slave: bool) -> Self {
+		Self {
+			name,
+			channel,
+			slave,
+			info: Mutex::new(None),
+		}
+	}
+
+	fn info(&self) -> Result<Info, BlockError> {
+		self.info.lock().ok_or(BlockError::NoDevice)
+	}
+
+	fn select(&self, ports: Ports, bits: u8) {
+		let slave = if self.slave { SELECT_SLAVE } else { 0 };
+		unsafe { ports.write(DRIVE_SELECT, SELECT_BASE | slave | bits) };
+		ports.delay();
+	}
+
+	/// Ask the drive about itself, or `None` if there isn't an ATA drive
+	fn identify(&self, ports: Ports) -> Option<Info> {
+		self.select(ports, 0);
+		unsafe {
+			ports.write(SECTOR_COUNT, 0);
+			ports.write(LBA_LOW, 0);
+			ports.write(LBA_MID, 0);
+			ports.write(LBA_HIGH, 0);
+			ports.write(COMMAND, IDENTIFY);
+		}
+		ports.delay();
+		if unsafe { ports.read(STATUS) } == 0 {
+			return None;
+		}
+		ports.wait_not_busy().ok()?;
+
+		// ATAPI and SATA devices put a signature here and abort the command
+		if unsafe { ports.read(LBA_MID) != 0 || ports.read(LBA_HIGH) != 0 } {
+			return None;
+		}
+		ports.wait_data().ok()?;
+
+		let mut words = [0u16; 256];
+		for word in words.iter_mut() {
+			*word = unsafe { inw(ports.io + DATA) };
+		}
+
+		Some(Info::parse(&words))
+	}
+
+	/// Send a read or write command for `count` sectors at `lba`
+	fn command(
+		&self,
+		ports: Ports,
+		info: Info,
+		lba: u64,
+		count: usize,
+		commands: (u8, u8),
+	) -> Result<(), BlockError> {
+		let (lba28_command, lba48_command) = commands;
+		ports.wait_not_busy()?;
+
+		let lba_bytes = lba.to_le_bytes();
+		let lba48 = lba + count as u64 > LBA28_LIMIT && info.lba48;
+		if lba48 {
+			self.select(ports, SELECT_LBA);
+		} else {
+			self.select(ports, SELECT_LBA | (lba_bytes[3] & 0xF));
+		}
+		// the drive that's just been selected may still be busy itself
+		ports.wait_not_busy()?;
+
+		unsafe {
+			if lba48 {
+				// the high bytes go first, then the low ones on top of them
+				ports.write(SECTOR_COUNT, (count >> 8) as u8);
+				ports.write(LBA_LOW, lba_bytes[3]);
+				ports.write(LBA_MID, lba_bytes[4]);
+				ports.write(LBA_HIGH, lba_bytes[5]);
+				ports.write(SECTOR_COUNT, count as u8);
+				ports.write(LBA_LOW, lba_bytes[0]);
+				ports.write(LBA_MID, lba_bytes[1]);
+				ports.write(LBA_HIGH, lba_bytes[2]);
+				ports.write(COMMAND, lba48_command);
+			} else {
+				// 256 sectors is written as 0
+				ports.write(SECTOR_COUNT, count as u8);
+				ports.write(LBA_LOW, lba_bytes[0]);
+				ports.write(LBA_MID, lba_bytes[1]);
+				ports.write(LBA_HIGH, lba_bytes[2]);
+				ports.write(COMMAND, lba28_command);
+			}
+		}
+		// status isn't valid until 400ns after the command
+		ports.delay();
+		Ok(())
+	}
+
+	/// Check a transfer fits, and that LBA28 can reach it if that's all the
+	/// drive has
+	fn check(
+		&self,
+		info: Info,
+		lba: u64,
+		len: usize,
+	) -> Result<(), BlockError> {
+		let count = block::check_range(self, lba, len)?;
+		if !info.lba48 && lba + count > LBA28_LIMIT {
+			return Err(BlockError::OutOfRange);
+		}
+		Ok(())
+	}
+}
+
+impl BlockDevice for Drive {
+	fn name(&self) -> &str {
+		self.name
+	}
+
+	fn sector_size(&self) -> usize {
+		SECTOR_SIZE
+	}
+
+	fn sectors(&self) -> u64 {
+		self.info().map_or(0, |info| info.sectors)
+	}
+
+	fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+		let info = self.info()?;
+		self.check(info, sector, buf.len())?;
+		let channel = CHANNELS[self.channel].lock();
+		let ports = channel.ok_or(BlockError::NoDevice)?;
+
+		let chunks = buf.chunks_mut(SECTORS_PER_COMMAND * SECTOR_SIZE);
+		for (lba, chunk) in (sector..).step_by(SECTORS_PER_COMMAND).zip(chunks)
+		{
+			let count = chunk.len() / SECTOR_SIZE;
+			self.command(
+				ports,
+				info,
+				lba,
+				count,
+				(READ_SECTORS, READ_SECTORS_EXT),
+			)?;
+
+			for sector in chunk.chunks_exact_mut(SECTOR_SIZE) {
+				ports.wait_data()?;
+				for bytes in sector.chunks_exact_mut(2) {
+					let word = unsafe { inw(ports.io + DATA) };
+					bytes.copy_from_slice(&word.to_le_bytes());
+				}
+				// or after each sector
+				ports.delay();
+			}
+		}
+		Ok(())
+	}
+
+	fn write(&self, sector: u64, buf: &[u8]) -> Result<(), BlockError> {
+		let info = self.info()?;
+		self.check(info, sector, buf.len())?;
+		let channel = CHANNELS[self.channel].lock();
+		let ports = channel.ok_or(BlockError::NoDevice)?;
+
+		let chunks = buf.chunks(SECTORS_PER_COMMAND * SECTOR_SIZE);
+		for (lba, chunk) in (sector..).step_by(SECTORS_PER_COMMAND).zip(chunks)
+		{
+			let count = chunk.len() / SECTOR_SIZE;
+			self.command(
+				ports,
+				info,
+				lba,
+				count,
+				(WRITE_SECTORS, WRITE_SECTORS_EXT),
+			)?;
+
+			for sector in chunk.chunks_exact(SECTOR_SIZE) {
+				ports.wait_data()?;
+				for bytes in sector.chunks_exact(2) {
+					let word = u16::from_le_bytes([bytes[0], bytes[1]]);
+					unsafe { outw(ports.io + DATA, word) };
+				}
+				// or after each sector
+				ports.delay();
+			}
+		}
+
+		// the last sector isn't written until the drive stops being busy
+		let status = ports.wait_not_busy()?;
+		if status & (STATUS_ERROR | STATUS_DRIVE_FAULT) != 0 {
+			return Err(BlockError::Device(
+				unsafe { ports.read(ERROR) }.into(),
+			));
+		}
+		Ok(())
+	}
+
+	fn flush(&self) -> Result<(), BlockError> {
+		let info = self.info()?;
+		let channel = CHANNELS[self.channel].lock();
+		let ports = channel.ok_or(BlockError::NoDevice)?;
+
+		self.select(ports, 0);
+		ports.wait_not_busy()?;
+		let command = if info.lba48 {
+			CACHE_FLUSH_EXT
+		} else {
+			CACHE_FLUSH
+		};
+		unsafe { ports.write(COMMAND, command) };
+		ports.delay();
+
+		let status = ports.wait_not_busy()?;
+		if status & STATUS_ERROR != 0 {
+			return Err(BlockError::Device(
+				unsafe { ports.read(ERROR) }.into(),
+			));
+		}
+		Ok(())
+	}
+}
+
+/// Ports of one of the controller's channels. Bit 0 (channel 0) or bit 2
+/// (channel 1) of the programming interface says it's in native mode and
+/// uses BARs instead of the legacy ports.
+fn channel_ports(function: &Function, channel: usize) -> Option<Ports> {
+	if function.class.prog_if & (1 << (channel * 2)) == 0 {
+		return Some(LEGACY_PORTS[channel]);
+	}
+
+	match (function.bar(channel * 2)?, function.bar(channel * 2 + 1)?) {
+		(Bar::Io { port: io, .. }, Bar::Io { port: control, .. }) => {
+			Some(Ports {
+				io,
+				control: control + CONTROL_BAR_OFFSET,
+			})
+		}
+		_ => None,
+	}
+}
+
+fn probe(function: &Function) -> bool {
+	function.enable(Command::IO_SPACE);
+	let mut found = false;
+
+	for (channel, lock) in CHANNELS.iter().enumerate() {
+		let ports = match channel_ports(function, channel) {
+			Some(ports) => ports,
+			None => continue,
+		};
+		if ports.alt_status() == FLOATING_BUS {
+			continue;
+		}
+		ports.reset();
+		*lock.lock() = Some(ports);
+
+		for drive in DRIVES.iter().filter(|drive| drive.channel == channel) {
+			let info = match drive.identify(ports) {
+				Some(info) => info,
+				None => continue,
+			};
+			*drive.info.lock() = Some(info);
+
+			kinfo!(
+				"ATA: {} is a {} MiB {}{}",
+				drive.name,
+				info.sectors * SECTOR_SIZE as u64 / (1024 * 1024),
+				info.model(),
+				if info.lba48 { " (LBA48)" } else { "" }
+			);
+			if block::register(drive).is_none() {
+				kwarn!("ATA: no room to register {}", drive.name);
+			}
+			found = true;
+		}
+	}
+	found
+}
+
+static DRIVER: pci::Driver = pci::Driver {
+	name: "ata",
+	matches: &[Match::Class {
+		class: 0x01,
+		subclass: 0x01,
+		prog_if: None,
+	}],
+	probe,
+};
+
+/// Start handling IDE controllers, now and whenever PCI finds one
+pub fn init() {
+	if !pci::register_driver(&DRIVER) {
+		kwarn!("ATA: couldn't register the PCI driver");
+	}
+}
@@ -0,0 +1,55 @@
+//! Memory mapped registers, reached through the identity map
+
+use crate::arch::pit;
+use core::ptr;
+
+/// A block of memory mapped registers
+#[derive(Clone, Copy, Debug)]
+pub struct Mmio(usize);
+
+impl Mmio {
+	/// # Safety
+	/// `base` has to be the identity-mapped address of a device's registers
+	pub unsafe fn new(base: usize) -> Self {
+		Self(base)
+	}
+
+	pub fn base(self) -> usize {
+		self.0
+	}
+
+	pub fn read(self, offset: usize) -> u32 {
+		unsafe { ptr::read_volatile((self.0 + offset) as *const u32) }
+	}
+
+	pub fn write(self, offset: usize, value: u32) {
+		unsafe { ptr::write_volatile((self.0 + offset) as *mut u32, value) }
+	}
+
+	/// Read a 64 bit register as two halves, low one first
+	pub fn read64(self, offset: usize) -> u64 {
+		u64::from(self.read(offset)) | u64::from(self.read(offset + 4)) << 32
+	}
+
+	pub fn write64(self, offset: usize, value: u64) {
+		self.write(offset, value as u32);
+		self.write(offset + 4, (value >> 32) as u32);
+	}
+
+	/// Registers `offset` bytes further in
+	pub fn at(self, offset: usize) -> Self {
+		Self(self.0 + offset)
+	}
+
+	/// Wait up to `timeout_ms` milliseconds for the bits in `mask` at
+	/// `offset` to read as `value`, returning false if they never do
+	pub fn poll(
+		self,
+		offset: usize,
+		mask: u32,
+		value: u32,
+		timeout_ms: u64,
+	) -> bool {
+		pit::wait_until(false, timeout_ms, || self.read(offset) & mask == value)
+	}
+}
@@ -0,0 +1,10 @@
+//! Drivers for devices that aren't part of the CPU or chipset basics in
+//! `arch`
+
+pub mod ahci;
+pub mod ata;
+pub mod mmio;
+pub mod nvme;
+pub mod pci;
+pub mod ps2;
+pub mod ramdisk;
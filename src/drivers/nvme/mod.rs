@@ -0,0 +1,466 @@
+//! NVMe controllers (PCI class 01:08). Each one gets an admin queue and a
+//! single I/O queue pair, and every active namespace on it shows up as a
+//! block device. Commands run one at a time per controller; whoever submits
+//! one sleeps until an MSI (or, without one, the timer) wakes them up to
+//! check for the completion.
+
+pub mod namespace;
+pub mod queue;
+
+use crate::{
+	arch::{apic, idt::InterruptStackFrame, pit},
+	block::BlockError,
+	drivers::{
+		mmio::Mmio,
+		pci::{self, msi, Bar, Command as PciCommand, Function, Match},
+	},
+	kinfo, kwarn,
+	mm::{pmm, IDENTITY_MAPPED, PAGE_SIZE},
+};
+use core::{ops::Range, slice, str};
+use namespace::MAX_NAMESPACES;
+use queue::{Command, Completion, Queue, RunError};
+use spin::Mutex;
+
+// controller registers
+const CAPABILITIES: usize = 0x00;
+const CONFIGURATION: usize = 0x14;
+const STATUS: usize = 0x1C;
+const ADMIN_QUEUE_ATTRIBUTES: usize = 0x24;
+const ADMIN_SUBMISSION_QUEUE: usize = 0x28;
+const ADMIN_COMPLETION_QUEUE: usize = 0x30;
+
+const CAPABILITIES_MAX_ENTRIES: u64 = 0xFFFF;
+/// How long the controller may take to become ready, in 500ms units
+const CAPABILITIES_TIMEOUT_SHIFT: u64 = 24;
+const CAPABILITIES_STRIDE_SHIFT: u64 = 32;
+const CAPABILITIES_NVM_COMMANDS: u64 = 1 << 37;
+/// log2 of the smallest page size it takes, minus 12
+const CAPABILITIES_MIN_PAGE_SHIFT: u64 = 48;
+/// Least time the controller gets to become ready, in milliseconds, even if
+/// it claims to need none
+const MIN_READY_TIMEOUT_MS: u64 = 1000;
+
+const CONFIGURATION_ENABLE: u32 = 1 << 0;
+/// 64 byte submission and 16 byte completion entries, as powers of two
+const CONFIGURATION_ENTRY_SIZES: u32 = 6 << 16 | 4 << 20;
+
+const STATUS_READY: u32 = 1 << 0;
+const STATUS_FATAL: u32 = 1 << 1;
+
+// admin commands
+const CREATE_IO_SUBMISSION_QUEUE: u8 = 0x01;
+const CREATE_IO_COMPLETION_QUEUE: u8 = 0x05;
+const IDENTIFY: u8 = 0x06;
+const ABORT: u8 = 0x08;
+const SET_FEATURES: u8 = 0x09;
+
+// what IDENTIFY returns
+const IDENTIFY_NAMESPACE: u32 = 0x00;
+const IDENTIFY_CONTROLLER: u32 = 0x01;
+const IDENTIFY_ACTIVE_NAMESPACES: u32 = 0x02;
+/// Set Features feature asking for a number of I/O queues
+const FEATURE_QUEUE_COUNT: u32 = 0x07;
+
+const QUEUE_CONTIGUOUS: u32 = 1 << 0;
+const QUEUE_INTERRUPTS: u32 = 1 << 1;
+
+// identify controller fields
+const IDENTIFY_MODEL: Range<usize> = 24..64;
+/// Largest transfer as a power of two of pages, 0 for no limit
+const IDENTIFY_MAX_TRANSFER: usize = 77;
+
+const ADMIN_ENTRIES: u16 = 16;
+const IO_ENTRIES: u16 = 64;
+const IO_QUEUE: u16 = 1;
+
+/// Transfers go through a bounce buffer (see `mm::IDENTITY_MAPPED`)
+const BOUNCE_PAGES: usize = 16;
+const BOUNCE_SIZE: usize = BOUNCE_PAGES * PAGE_SIZE;
+
+pub const MAX_CONTROLLERS: usize = 2;
+
+#[derive(Clone, Copy, Debug)]
+pub enum NvmeError {
+	/// BAR 0 isn't a memory BAR inside the identity map
+	NoRegisters,
+	/// The controller can't do the NVM command set with 4 KiB pages
+	Unsupported,
+	/// The controller didn't become ready (or stop being ready) in time
+	NotReady,
+	/// The controller reported a fatal error
+	Fatal,
+	/// An admin command failed
+	Command(BlockError),
+}
+
+impl From<BlockError> for NvmeError {
+	fn from(e: BlockError) -> Self {
+		Self::Command(e)
+	}
+}
+
+impl From<RunError> for NvmeError {
+	fn from(e: RunError) -> Self {
+		Self::Command(e.into())
+	}
+}
+
+pub struct Controller {
+	pub admin: Queue,
+	pub io: Queue,
+	/// `BOUNCE_PAGES` pages every transfer goes through
+	bounce: usize,
+	/// Page holding a PRP list of the bounce buffer's pages, past the first
+	prp_list: usize,
+	/// Most bytes a single command may move
+	pub max_transfer: usize,
+}
+
+pub static CONTROLLERS: [Mutex<Option<Controller>>; MAX_CONTROLLERS] =
+	[Mutex::new(None), Mutex::new(None)];
+
+impl Controller {
+	/// The first `len` bytes of the bounce buffer
+	pub fn bounce(&mut self, len: usize) -> &mut [u8] {
+		// SAFETY: the pages came from the PMM for this controller alone, and
+		// it's only pointed at them while a command is running
+		unsafe { slice::from_raw_parts_mut(self.bounce as *mut u8, len) }
+	}
+
+	/// PRP entries for the first `len` bytes of the bounce buffer
+	pub fn prp(&self, len: usize) -> (u64, u64) {
+		let first = self.bounce as u64;
+		let second = if len <= PAGE_SIZE {
+			0
+		} else if len <= 2 * PAGE_SIZE {
+			first + PAGE_SIZE as u64
+		} else {
+			self.prp_list as u64
+		};
+		(first, second)
+	}
+
+	/// Run `command` on the I/O queue, aborting it if it times out
+	pub fn run_io(
+		&mut self,
+		command: Command,
+	) -> Result<Completion, BlockError> {
+		match self.io.run(command) {
+			Err(RunError::TimedOut(id)) => {
+				self.abort(id);
+				Err(BlockError::Timeout)
+			}
+			result => result.map_err(BlockError::from),
+		}
+	}
+
+	/// Abort I/O command `id`, which timed out, and wait for it to complete.
+	/// Until it does the bounce buffer may still be in use, so the I/O queue
+	/// refuses anything else.
+	fn abort(&mut self, id: u16) {
+		// the abort may or may not get there first, either way the command
+		// completes
+		let target = u32::from(id) << 16 | u32::from(self.io.id());
+		let _ = self.admin.run(Command::new(ABORT).dword(10, target));
+		if !self.io.settle() {
+			kwarn!("NVMe: I/O command {} won't complete even aborted", id);
+		}
+	}
+
+	/// Read a page of identify data into the bounce buffer
+	fn identify(
+		&mut self,
+		structure: u32,
+		namespace: u32,
+	) -> Result<&[u8], BlockError> {
+		let (first, second) = self.prp(PAGE_SIZE);
+		let command = Command::new(IDENTIFY)
+			.namespace(namespace)
+			.prp(first, second)
+			.dword(10, structure);
+		self.admin.run(command)?;
+		Ok(self.bounce(PAGE_SIZE))
+	}
+
+	/// Give back the queues and the bounce buffer. The controller has to be
+	/// done with all of them.
+	unsafe fn free(self) {
+		self.admin.free();
+		self.io.free();
+		pmm::free_pages(self.bounce as *mut u8, BOUNCE_PAGES);
+		pmm::free_pages(self.prp_list as *mut u8, 1);
+	}
+}
+
+/// A controller `setup` is still working on. Unless it's `finish`ed, it gets
+/// shut back down when dropped, so failing partway doesn't leave it running
+/// on memory and vectors that are about to be forgotten.
+struct Setup<'a> {
+	function: &'a Function,
+	registers: Mmio,
+	timeout_ms: u64,
+	/// The MSI vector, if there is one
+	vector: Option<u8>,
+	controller: Option<Controller>,
+}
+
+impl Setup<'_> {
+	fn controller(&mut self) -> &mut Controller {
+		self.controller.as_mut().expect("finished controller")
+	}
+
+	/// Keep the controller running and hand it over
+	fn finish(mut self) -> Controller {
+		self.controller.take().expect("finished controller")
+	}
+}
+
+impl Drop for Setup<'_> {
+	fn drop(&mut self) {
+		let controller = match self.controller.take() {
+			Some(controller) => controller,
+			None => return,
+		};
+
+		if !disable(self.registers, self.timeout_ms) {
+			kwarn!("NVMe: {} won't stop", self.function.address);
+		}
+		self.function.disable(PciCommand::BUS_MASTER);
+		// SAFETY: without bus mastering the controller can't reach its memory
+		// any more, even if it didn't stop
+		unsafe { controller.free() };
+		if let Some(vector) = self.vector {
+			msi::disable(self.function, vector, 1);
+		}
+	}
+}
+
+extern "x86-interrupt" fn interrupt(_frame: InterruptStackFrame) {
+	// completions are picked up by whoever is waiting for them, this is
+	// only here to wake them up
+	apic::eoi();
+}
+
+/// Clear the controller's enable bit, returning whether it stopped being
+/// ready in time
+fn disable(registers: Mmio, timeout_ms: u64) -> bool {
+	registers.write(CONFIGURATION, 0);
+	pit::wait_until(true, timeout_ms, || {
+		registers.read(STATUS) & STATUS_READY == 0
+	})
+}
+
+/// Disable the controller, then bring it back up with a fresh admin queue
+fn reset(
+	registers: Mmio,
+	admin: &Queue,
+	timeout_ms: u64,
+) -> Result<(), NvmeError> {
+	if !disable(registers, timeout_ms) {
+		return Err(NvmeError::NotReady);
+	}
+
+	let entries = u32::from(admin.entries() - 1);
+	registers.write(ADMIN_QUEUE_ATTRIBUTES, entries << 16 | entries);
+	registers.write64(ADMIN_SUBMISSION_QUEUE, admin.submissions());
+	registers.write64(ADMIN_COMPLETION_QUEUE, admin.completions());
+	registers.write(
+		CONFIGURATION,
+		CONFIGURATION_ENTRY_SIZES | CONFIGURATION_ENABLE,
+	);
+
+	let mut status = 0;
+	let ready = pit::wait_until(true, timeout_ms, || {
+		status = registers.read(STATUS);
+		status & (STATUS_READY | STATUS_FATAL) != 0
+	});
+	if status & STATUS_FATAL != 0 {
+		Err(NvmeError::Fatal)
+	} else if !ready {
+		Err(NvmeError::NotReady)
+	} else {
+		Ok(())
+	}
+}
+
+/// Bring up the controller `function` is and register its namespaces,
+/// keeping it in `CONTROLLERS[index]`. Returns how many namespaces there
+/// were.
+fn setup(function: &Function, index: usize) -> Result<usize, NvmeError> {
+	let address = match function.bar(0) {
+		Some(Bar::Memory { address, size, .. })
+			if address + size <= IDENTITY_MAPPED =>
+		{
+			address as usize
+		}
+		_ => return Err(NvmeError::NoRegisters),
+	};
+	function.enable(PciCommand::MEMORY_SPACE | PciCommand::BUS_MASTER);
+	// SAFETY: BAR 0 is a memory BAR inside the identity map
+	let registers = unsafe { Mmio::new(address) };
+
+	let capabilities = registers.read64(CAPABILITIES);
+	if capabilities & CAPABILITIES_NVM_COMMANDS == 0
+		|| (capabilities >> CAPABILITIES_MIN_PAGE_SHIFT) & 0xF != 0
+	{
+		return Err(NvmeError::Unsupported);
+	}
+	let timeout_ms = (((capabilities >> CAPABILITIES_TIMEOUT_SHIFT) & 0xFF)
+		* 500)
+		.max(MIN_READY_TIMEOUT_MS);
+	let stride = 4 << ((capabilities >> CAPABILITIES_STRIDE_SHIFT) & 0xF);
+	let max_entries = ((capabilities & CAPABILITIES_MAX_ENTRIES) + 1)
+		.min(queue::MAX_ENTRIES.into()) as u16;
+
+	let vector = match msi::enable(function, interrupt) {
+		Ok(vector) => Some(vector),
+		Err(e) => {
+			kwarn!("NVMe: no MSI for {} ({:?}), polling", function.address, e);
+			None
+		}
+	};
+	let interrupts = vector.is_some();
+
+	let admin = Queue::new(
+		registers,
+		0,
+		ADMIN_ENTRIES.min(max_entries),
+		stride,
+		interrupts,
+	);
+	let io = Queue::new(
+		registers,
+		IO_QUEUE,
+		IO_ENTRIES.min(max_entries),
+		stride,
+		interrupts,
+	);
+	let bounce = pmm::alloc_pages(BOUNCE_PAGES) as usize;
+	let prp_list = pmm::alloc_pages(1) as usize;
+	for page in 1..BOUNCE_PAGES {
+		let entry = (prp_list as *mut u64).wrapping_add(page - 1);
+		// SAFETY: fresh from the PMM, and there's room for 512 entries
+		unsafe { entry.write((bounce + page * PAGE_SIZE) as u64) };
+	}
+	let mut setup = Setup {
+		function,
+		registers,
+		timeout_ms,
+		vector,
+		controller: Some(Controller {
+			admin,
+			io,
+			bounce,
+			prp_list,
+			max_transfer: BOUNCE_SIZE,
+		}),
+	};
+	let controller = setup.controller();
+	reset(registers, &controller.admin, timeout_ms)?;
+
+	let identify = controller.identify(IDENTIFY_CONTROLLER, 0)?;
+	let mut model = [0; IDENTIFY_MODEL.end - IDENTIFY_MODEL.start];
+	model.copy_from_slice(&identify[IDENTIFY_MODEL]);
+	let max_transfer = identify[IDENTIFY_MAX_TRANSFER];
+	if max_transfer != 0 {
+		controller.max_transfer =
+			BOUNCE_SIZE.min(PAGE_SIZE << u32::from(max_transfer));
+	}
+	kinfo!(
+		"NVMe: {} is a {}",
+		function.address,
+		str::from_utf8(&model).unwrap_or("?").trim()
+	);
+
+	// one I/O queue pair is all that's used, which is what 0 (it counts
+	// from 1) asks for
+	controller.admin.run(
+		Command::new(SET_FEATURES)
+			.dword(10, FEATURE_QUEUE_COUNT)
+			.dword(11, 0),
+	)?;
+	let io = &controller.io;
+	let queue = u32::from(io.entries() - 1) << 16 | u32::from(io.id());
+	let completion_flags = if interrupts {
+		// on vector 0, the only one `msi::enable` sets up
+		QUEUE_CONTIGUOUS | QUEUE_INTERRUPTS
+	} else {
+		QUEUE_CONTIGUOUS
+	};
+	let create_completions = Command::new(CREATE_IO_COMPLETION_QUEUE)
+		.prp(io.completions(), 0)
+		.dword(10, queue)
+		.dword(11, completion_flags);
+	let create_submissions = Command::new(CREATE_IO_SUBMISSION_QUEUE)
+		.prp(io.submissions(), 0)
+		.dword(10, queue)
+		.dword(11, u32::from(io.id()) << 16 | QUEUE_CONTIGUOUS);
+	controller.admin.run(create_completions)?;
+	controller.admin.run(create_submissions)?;
+
+	let mut ids = [0; MAX_NAMESPACES];
+	let list = controller.identify(IDENTIFY_ACTIVE_NAMESPACES, 0)?;
+	for (id, bytes) in ids.iter_mut().zip(list.chunks_exact(4)) {
+		*id = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+	}
+
+	let mut namespaces = [None; MAX_NAMESPACES];
+	for (&id, namespace) in ids.iter().zip(namespaces.iter_mut()) {
+		// the list ends at the first 0
+		if id == 0 {
+			break;
+		}
+		let identify = controller.identify(IDENTIFY_NAMESPACE, id)?;
+		*namespace = namespace::Info::parse(index, id, identify);
+		if namespace.is_none() {
+			kwarn!("NVMe: namespace {} has a sector size we can't use", id);
+		}
+	}
+
+	*CONTROLLERS[index].lock() = Some(setup.finish());
+	Ok(namespaces
+		.iter()
+		.flatten()
+		.filter(|&&info| namespace::register(info))
+		.count())
+}
+
+fn probe(function: &Function) -> bool {
+	let index = match CONTROLLERS.iter().position(|c| c.lock().is_none()) {
+		Some(index) => index,
+		None => {
+			kwarn!("NVMe: too many controllers, ignoring {}", function.address);
+			return false;
+		}
+	};
+
+	match setup(function, index) {
+		Ok(0) => {
+			kwarn!("NVMe: {} has no usable namespaces", function.address);
+			true
+		}
+		Ok(_) => true,
+		Err(e) => {
+			kwarn!("NVMe: couldn't set up {}: {:?}", function.address, e);
+			false
+		}
+	}
+}
+
+static DRIVER: pci::Driver = pci::Driver {
+	name: "nvme",
+	matches: &[Match::Class {
+		class: 0x01,
+		subclass: 0x08,
+		prog_if: Some(0x02),
+	}],
+	probe,
+};
+
+/// Start handling NVMe controllers, now and whenever PCI finds one
+pub fn init() {
+	if !pci::register_driver(&DRIVER) {
+		kwarn!("NVMe: couldn't register the PCI driver");
+	}
+}
@@ -0,0 +1,195 @@
+//! Namespaces, which are what NVMe calls the disks a controller presents.
+//! They're registered as block devices named `nvme0` to `nvme3`, in the
+//! order they're found.
+
+use super::{queue::Command, CONTROLLERS};
+use crate::{
+	block::{self, BlockDevice, BlockError},
+	kinfo, kwarn,
+	mm::PAGE_SIZE,
+};
+use spin::Mutex;
+
+pub const MAX_NAMESPACES: usize = 4;
+
+// I/O commands
+const FLUSH: u8 = 0x00;
+const WRITE: u8 = 0x01;
+const READ: u8 = 0x02;
+
+// identify namespace fields
+const IDENTIFY_SIZE: usize = 0;
+/// Which of the LBA formats the namespace is formatted with
+const IDENTIFY_FORMAT: usize = 26;
+const FORMAT_INDEX_MASK: u8 = 0xF;
+const IDENTIFY_FORMATS: usize = 128;
+const FORMAT_SIZE: usize = 4;
+/// Byte of an LBA format giving log2 of the sector size
+const FORMAT_SECTOR_SHIFT: usize = 2;
+
+const MIN_SECTOR_SIZE: usize = 512;
+
+/// Where a namespace is and what it looks like
+#[derive(Clone, Copy, Debug)]
+pub struct Info {
+	/// Index into `CONTROLLERS`
+	controller: usize,
+	id: u32,
+	sectors: u64,
+	sector_size: usize,
+}
+
+impl Info {
+	/// Pick apart namespace `id`'s identify data, or `None` if its sectors
+	/// aren't a power of two from 512 bytes to a page, like the block layer
+	/// wants
+	pub fn parse(controller: usize, id: u32, identify: &[u8]) -> Option<Self> {
+		let mut size = [0; 8];
+		size.copy_from_slice(&identify[IDENTIFY_SIZE..IDENTIFY_SIZE + 8]);
+
+		let format = (identify[IDENTIFY_FORMAT] & FORMAT_INDEX_MASK) as usize;
+		let shift = identify
+			[IDENTIFY_FORMATS + format * FORMAT_SIZE + FORMAT_SECTOR_SHIFT];
+		let sector_size = 1usize.checked_shl(shift.into())?;
+		if sector_size < MIN_SECTOR_SIZE || sector_size > PAGE_SIZE {
+			return None;
+		}
+
+		Some(Self {
+			controller,
+			id,
+			sectors: u64::from_le_bytes(size),
+			sector_size,
+		})
+	}
+}
+
+pub struct Namespace {
+	name: &'static str,
+	info: Mutex<Option<Info>>,
+}
+
+static NAMESPACES: [Namespace; MAX_NAMESPACES] = [
+	Namespace::new("nvme0"),
+	Namespace::new("nvme1"),
+	Namespace::new("nvme2"),
+	Namespace::new("nvme3"),
+];
+
+impl Namespace {
+	const fn new(name: &'static str) -> Self {
+		Self {
+			name,
+			info: Mutex::new(None),
+		}
+	}
+
+	fn info(&self) -> Result<Info, BlockError> {
+		self.info.lock().ok_or(BlockError::NoDevice)
+	}
+
+	/// Move `len` bytes at `sector`, a command's worth at a time. `copy` is
+	/// given each command's offset into the caller's buffer and the bounce
+	/// buffer, to fill before writing or empty after reading.
+	fn transfer(
+		&self,
+		sector: u64,
+		len: usize,
+		write: bool,
+		mut copy: impl FnMut(usize, &mut [u8]),
+	) -> Result<(), BlockError> {
+		block::check_range(self, sector, len)?;
+		let info = self.info()?;
+		let mut controller = CONTROLLERS[info.controller].lock();
+		let controller = controller.as_mut().ok_or(BlockError::NoDevice)?;
+		let opcode = if write { WRITE } else { READ };
+
+		let mut done = 0;
+		while done < len {
+			let count = (len - done).min(controller.max_transfer);
+			let lba = sector + (done / info.sector_size) as u64;
+			let sectors = (count / info.sector_size) as u32;
+
+			if write {
+				copy(done, controller.bounce(count));
+			}
+			let (first, second) = controller.prp(count);
+			// the sector count is 0 based
+			let command = Command::new(opcode)
+				.namespace(info.id)
+				.prp(first, second)
+				.dword(10, lba as u32)
+				.dword(11, (lba >> 32) as u32)
+				.dword(12, sectors - 1);
+			controller.run_io(command)?;
+			if !write {
+				copy(done, controller.bounce(count));
+			}
+
+			done += count;
+		}
+		Ok(())
+	}
+}
+
+impl BlockDevice for Namespace {
+	fn name(&self) -> &str {
+		self.name
+	}
+
+	fn sector_size(&self) -> usize {
+		self.info().map_or(MIN_SECTOR_SIZE, |info| info.sector_size)
+	}
+
+	fn sectors(&self) -> u64 {
+		self.info().map_or(0, |info| info.sectors)
+	}
+
+	fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+		self.transfer(sector, buf.len(), false, |offset, bounce| {
+			buf[offset..offset + bounce.len()].copy_from_slice(bounce)
+		})
+	}
+
+	fn write(&self, sector: u64, buf: &[u8]) -> Result<(), BlockError> {
+		self.transfer(sector, buf.len(), true, |offset, bounce| {
+			bounce.copy_from_slice(&buf[offset..offset + bounce.len()])
+		})
+	}
+
+	fn flush(&self) -> Result<(), BlockError> {
+		let info = self.info()?;
+		let mut controller = CONTROLLERS[info.controller].lock();
+		let controller = controller.as_mut().ok_or(BlockError::NoDevice)?;
+
+		controller.run_io(Command::new(FLUSH).namespace(info.id))?;
+		Ok(())
+	}
+}
+
+/// Make the namespace `info` describes a block device, returning false if
+/// there's no room for it
+pub fn register(info: Info) -> bool {
+	let namespace = match NAMESPACES.iter().find(|ns| ns.info.lock().is_none())
+	{
+		Some(namespace) => namespace,
+		None => {
+			kwarn!("NVMe: no room for namespace {}", info.id);
+			return false;
+		}
+	};
+	*namespace.info.lock() = Some(info);
+
+	kinfo!(
+		"NVMe: {} is namespace {}, {} MiB in {} byte sectors",
+		namespace.name,
+		info.id,
+		info.sectors * info.sector_size as u64 / (1024 * 1024),
+		info.sector_size
+	);
+	if block::register(namespace).is_none() {
+		kwarn!("NVMe: no room to register {}", namespace.name);
+		return false;
+	}
+	true
+}
@@ -0,0 +1,277 @@
+//! Submission and completion queues. Each queue here is a pair, with the
+//! submission queue and its completion queue sharing an ID, and only ever
+//! has one command in flight; whoever submits waits for the completion. A
+//! command that times out keeps the queue to itself until it does complete,
+//! since the controller may still be moving its data.
+
+use crate::{
+	arch::pit,
+	block::BlockError,
+	drivers::mmio::Mmio,
+	mm::{pmm, PAGE_SIZE},
+};
+use core::ptr;
+
+const SUBMISSION_SIZE: usize = 64;
+const COMPLETION_SIZE: usize = 16;
+/// Entries that fit in the single page each half of a queue gets
+pub const MAX_ENTRIES: u16 = (PAGE_SIZE / SUBMISSION_SIZE) as u16;
+
+const DOORBELLS: usize = 0x1000;
+
+/// How long a command gets before giving up on it, in milliseconds
+const TIMEOUT_MS: u64 = 5000;
+
+/// A submission queue entry
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct Command {
+	opcode: u8,
+	flags: u8,
+	id: u16,
+	namespace: u32,
+	reserved: u64,
+	metadata: u64,
+	prp: [u64; 2],
+	/// Command dwords 10 to 15
+	dwords: [u32; 6],
+}
+
+impl Command {
+	pub fn new(opcode: u8) -> Self {
+		Self {
+			opcode,
+			..Self::default()
+		}
+	}
+
+	pub fn namespace(mut self, namespace: u32) -> Self {
+		self.namespace = namespace;
+		self
+	}
+
+	/// Where the data is, as physical page addresses. `second` is either the
+	/// second page or a list of the rest, depending on the transfer's size.
+	pub fn prp(mut self, first: u64, second: u64) -> Self {
+		self.prp = [first, second];
+		self
+	}
+
+	/// Set command dword `index`, from 10 to 15
+	pub fn dword(mut self, index: usize, value: u32) -> Self {
+		self.dwords[index - 10] = value;
+		self
+	}
+}
+
+/// A completion queue entry
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct Completion {
+	/// Command specific
+	pub result: u32,
+	reserved: u32,
+	submission_head: u16,
+	submission_id: u16,
+	id: u16,
+	/// Phase tag in bit 0, status code and type above it
+	status: u16,
+}
+
+impl Completion {
+	fn phase(&self) -> bool {
+		self.status & 1 != 0
+	}
+
+	/// Status code and type, 0 if the command succeeded
+	pub fn status(&self) -> u16 {
+		self.status >> 1
+	}
+}
+
+/// Why `Queue::run` didn't get a successful completion
+#[derive(Clone, Copy, Debug)]
+pub enum RunError {
+	/// The command failed, or never went out because the queue is still
+	/// waiting on one that timed out earlier
+	Failed(BlockError),
+	/// The command with this ID just timed out. It should be aborted, and
+	/// the queue takes nothing else until it completes.
+	TimedOut(u16),
+}
+
+impl From<RunError> for BlockError {
+	fn from(e: RunError) -> Self {
+		match e {
+			RunError::Failed(e) => e,
+			RunError::TimedOut(_) => Self::Timeout,
+		}
+	}
+}
+
+pub struct Queue {
+	id: u16,
+	entries: u16,
+	submissions: usize,
+	completions: usize,
+	tail: u16,
+	head: u16,
+	/// Phase tag new completions have, which flips each time around
+	phase: bool,
+	next_command: u16,
+	submission_doorbell: usize,
+	completion_doorbell: usize,
+	/// Whether the completion queue raises interrupts to wake the waiter
+	interrupts: bool,
+	/// ID of a command that timed out and hasn't completed yet
+	stuck: Option<u16>,
+}
+
+impl Queue {
+	/// Queue `id` with `entries` entries, no more than `MAX_ENTRIES`.
+	/// `stride` is the doorbell stride the controller reports.
+	pub fn new(
+		registers: Mmio,
+		id: u16,
+		entries: u16,
+		stride: usize,
+		interrupts: bool,
+	) -> Self {
+		let submissions = pmm::alloc_pages(1) as usize;
+		let completions = pmm::alloc_pages(1) as usize;
+		// SAFETY: fresh from the PMM. Completions have to start with a phase
+		// tag of 0 so the first lap can tell new ones apart.
+		unsafe {
+			ptr::write_bytes(submissions as *mut u8, 0, PAGE_SIZE);
+			ptr::write_bytes(completions as *mut u8, 0, PAGE_SIZE);
+		}
+
+		let doorbell = registers.base() + DOORBELLS + 2 * id as usize * stride;
+		Self {
+			id,
+			entries,
+			submissions,
+			completions,
+			tail: 0,
+			head: 0,
+			phase: true,
+			next_command: 0,
+			submission_doorbell: doorbell,
+			completion_doorbell: doorbell + stride,
+			interrupts,
+			stuck: None,
+		}
+	}
+
+	pub fn id(&self) -> u16 {
+		self.id
+	}
+
+	pub fn entries(&self) -> u16 {
+		self.entries
+	}
+
+	pub fn submissions(&self) -> u64 {
+		self.submissions as u64
+	}
+
+	pub fn completions(&self) -> u64 {
+		self.completions as u64
+	}
+
+	/// The next completion, if the controller has posted one
+	fn poll(&mut self) -> Option<Completion> {
+		let entry = self.completions + self.head as usize * COMPLETION_SIZE;
+		// SAFETY: inside the queue's page
+		let completion =
+			unsafe { ptr::read_volatile(entry as *const Completion) };
+		if completion.phase() != self.phase {
+			return None;
+		}
+
+		self.head = (self.head + 1) % self.entries;
+		if self.head == 0 {
+			self.phase = !self.phase;
+		}
+		// SAFETY: the doorbell is one of the controller's registers
+		unsafe {
+			ptr::write_volatile(
+				self.completion_doorbell as *mut u32,
+				self.head.into(),
+			)
+		};
+		Some(completion)
+	}
+
+	/// Pick up whatever completions are left, returning whether the command
+	/// that timed out (if any) is done now
+	fn settled(&mut self) -> bool {
+		while let Some(completion) = self.poll() {
+			if Some(completion.id) == self.stuck {
+				self.stuck = None;
+			}
+		}
+		self.stuck.is_none()
+	}
+
+	/// Wait for the command that timed out to complete, returning false if
+	/// it still hasn't
+	pub fn settle(&mut self) -> bool {
+		let interrupts = self.interrupts;
+		pit::wait_until(interrupts, TIMEOUT_MS, || self.settled())
+	}
+
+	/// Submit `command` and wait for it to complete
+	pub fn run(
+		&mut self,
+		mut command: Command,
+	) -> Result<Completion, RunError> {
+		if !self.settled() {
+			return Err(RunError::Failed(BlockError::Timeout));
+		}
+
+		let id = self.next_command;
+		self.next_command = self.next_command.wrapping_add(1);
+		command.id = id;
+
+		let entry = self.submissions + self.tail as usize * SUBMISSION_SIZE;
+		self.tail = (self.tail + 1) % self.entries;
+		// SAFETY: the entry is inside the queue's page, and the doorbell is
+		// one of the controller's registers
+		unsafe {
+			ptr::write_volatile(entry as *mut Command, command);
+			ptr::write_volatile(
+				self.submission_doorbell as *mut u32,
+				self.tail.into(),
+			);
+		}
+
+		let mut completion = None;
+		pit::wait_until(self.interrupts, TIMEOUT_MS, || {
+			while let Some(c) = self.poll() {
+				if c.id == id {
+					completion = Some(c);
+					return true;
+				}
+			}
+			false
+		});
+		match completion {
+			Some(c) if c.status() != 0 => {
+				Err(RunError::Failed(BlockError::Device(c.status().into())))
+			}
+			Some(c) => Ok(c),
+			None => {
+				self.stuck = Some(id);
+				Err(RunError::TimedOut(id))
+			}
+		}
+	}
+
+	/// Give the queue's pages back. The controller has to be done with it,
+	/// either disabled or with the queue deleted.
+	pub unsafe fn free(self) {
+		pmm::free_pages(self.submissions as *mut u8, 1);
+		pmm::free_pages(self.completions as *mut u8, 1);
+	}
+}
@@ -0,0 +1,107 @@
+//! Base address registers, which say where a function's registers are in
+//! memory or I/O space
+
+use super::{Command, Function};
+
+const BAR0: u16 = 0x10;
+
+const IO_SPACE: u32 = 0b1;
+const IO_ADDRESS_MASK: u32 = !0b11;
+const MEMORY_TYPE: u32 = 0b110;
+const MEMORY_64_BIT: u32 = 0b100;
+const PREFETCHABLE: u32 = 0b1000;
+const MEMORY_ADDRESS_MASK: u32 = !0b1111;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bar {
+	Io {
+		port: u16,
+		size: u32,
+	},
+	Memory {
+		address: u64,
+		size: u64,
+		prefetchable: bool,
+		/// Takes up this BAR and the next one
+		wide: bool,
+	},
+}
+
+impl Bar {
+	pub fn address(&self) -> u64 {
+		match *self {
+			Self::Io { port, .. } => port.into(),
+			Self::Memory { address, .. } => address,
+		}
+	}
+
+	pub fn size(&self) -> u64 {
+		match *self {
+			Self::Io { size, .. } => size.into(),
+			Self::Memory { size, .. } => size,
+		}
+	}
+}
+
+const fn offset(index: usize) -> u16 {
+	BAR0 + index as u16 * 4
+}
+
+/// Write all ones to a BAR and see which bits stick, putting it back after
+fn size_mask(function: &Function, index: usize) -> u32 {
+	let original = function.read::<u32>(offset(index));
+	function.write(offset(index), u32::MAX);
+	let mask = function.read(offset(index));
+	function.write(offset(index), original);
+	mask
+}
+
+fn is_wide(raw: u32) -> bool {
+	raw & IO_SPACE == 0 && raw & MEMORY_TYPE == MEMORY_64_BIT
+}
+
+/// Decode BAR `index`, working out its size. The caller has to skip the
+/// top halves of 64 bit BARs, which aren't BARs of their own.
+pub fn probe(function: &Function, index: usize) -> Option<Bar> {
+	let raw: u32 = function.read(offset(index));
+
+	// the function would answer at whatever the BAR says while it's being
+	// sized, so decoding has to be off
+	let command = function.command();
+	function.set_command(command - (Command::IO_SPACE | Command::MEMORY_SPACE));
+
+	let bar = if raw & IO_SPACE != 0 {
+		let mask = size_mask(function, index) & IO_ADDRESS_MASK & 0xFFFF;
+		Bar::Io {
+			port: (raw & IO_ADDRESS_MASK) as u16,
+			size: (!mask).wrapping_add(1) & 0xFFFF,
+		}
+	} else {
+		let wide = is_wide(raw);
+		let mut address = u64::from(raw & MEMORY_ADDRESS_MASK);
+		let mask = size_mask(function, index) & MEMORY_ADDRESS_MASK;
+
+		let size = if wide {
+			address |= u64::from(function.read::<u32>(offset(index + 1))) << 32;
+			let high = size_mask(function, index + 1);
+			(!(u64::from(high) << 32 | u64::from(mask))).wrapping_add(1)
+		} else {
+			u64::from((!mask).wrapping_add(1))
+		};
+
+		Bar::Memory {
+			address,
+			size,
+			prefetchable: raw & PREFETCHABLE != 0,
+			wide,
+		}
+	};
+
+	function.set_command(command);
+
+	if bar.size() == 0 {
+		None
+	} else {
+		Some(bar)
+	}
+}
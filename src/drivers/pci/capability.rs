@@ -0,0 +1,217 @@
+//! The capabilities list, a linked list in configuration space describing
+//! optional features
+
+use super::{config, Address, Bar, Function};
+
+// capability IDs
+pub const POWER_MANAGEMENT: u8 = 0x01;
+pub const MSI: u8 = 0x05;
+pub const VENDOR_SPECIFIC: u8 = 0x09;
+pub const PCI_EXPRESS: u8 = 0x10;
+pub const MSI_X: u8 = 0x11;
+
+/// Capabilities start after the standard header
+const FIRST_OFFSET: u8 = 0x40;
+/// Most capabilities that fit in the legacy configuration space, so a list
+/// that loops back on itself still ends
+const MAX_CAPABILITIES: usize = 48;
+
+// MSI message control bits
+const MSI_64_BIT: u16 = 1 << 7;
+const MSI_PER_VECTOR_MASKING: u16 = 1 << 8;
+const MSI_MULTIPLE_MESSAGE_CAPABLE: u16 = 0b111 << 1;
+
+// MSI-X message control bits
+const MSI_X_TABLE_SIZE: u16 = 0x7FF;
+const MSI_X_BIR: u32 = 0b111;
+const MSI_X_ENTRY_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capability {
+	pub id: u8,
+	/// Where in configuration space it starts
+	pub offset: u16,
+}
+
+impl Capability {
+	pub const fn name(self) -> &'static str {
+		match self.id {
+			POWER_MANAGEMENT => "power management",
+			MSI => "MSI",
+			VENDOR_SPECIFIC => "vendor specific",
+			PCI_EXPRESS => "PCI Express",
+			MSI_X => "MSI-X",
+			_ => "unknown",
+		}
+	}
+}
+
+pub struct Capabilities {
+	address: Address,
+	next: u8,
+	remaining: usize,
+}
+
+impl Capabilities {
+	/// Walk the list starting at `start`, which is 0 for an empty list
+	pub const fn new(address: Address, start: u8) -> Self {
+		Self {
+			address,
+			next: start,
+			remaining: MAX_CAPABILITIES,
+		}
+	}
+}
+
+impl Iterator for Capabilities {
+	type Item = Capability;
+
+	fn next(&mut self) -> Option<Capability> {
+		// the bottom two bits of each pointer are reserved
+		let offset = self.next & !0b11;
+		if offset < FIRST_OFFSET || self.remaining == 0 {
+			return None;
+		}
+		self.remaining -= 1;
+
+		// the ID comes first, followed by the pointer to the next one
+		let [id, next] =
+			config::read::<u16>(self.address, offset.into()).to_le_bytes();
+		self.next = next;
+
+		Some(Capability {
+			id,
+			offset: offset.into(),
+		})
+	}
+}
+
+/// Message signaled interrupts, where the function writes to an address
+/// instead of asserting an interrupt pin
+#[derive(Clone, Copy, Debug)]
+pub struct Msi {
+	pub offset: u16,
+	pub control: u16,
+}
+
+impl Msi {
+	pub fn read(function: &Function, capability: Capability) -> Self {
+		Self {
+			offset: capability.offset,
+			control: function.read(capability.offset + 2),
+		}
+	}
+
+	/// Whether the message address can be above 4 GiB
+	pub const fn is_64bit(&self) -> bool {
+		self.control & MSI_64_BIT != 0
+	}
+
+	pub const fn per_vector_masking(&self) -> bool {
+		self.control & MSI_PER_VECTOR_MASKING != 0
+	}
+
+	/// How many vectors the function would like, a power of two up to 32
+	pub const fn vectors_capable(&self) -> usize {
+		1 << ((self.control & MSI_MULTIPLE_MESSAGE_CAPABLE) >> 1)
+	}
+}
+
+/// Like MSI, but with a table in one of the BARs that has an address and
+/// data for each vector
+#[derive(Clone, Copy, Debug)]
+pub struct MsiX {
+	pub offset: u16,
+	pub control: u16,
+	/// Entries in the table
+	pub table_size: usize,
+	pub table_bar: usize,
+	/// Where in the BAR the table is
+	pub table_offset: u32,
+	/// BAR and offset of the pending bit array
+	pub pba_bar: usize,
+	pub pba_offset: u32,
+	/// Physical address of the table, or `None` if it isn't inside a memory
+	/// BAR
+	pub table_address: Option<u64>,
+}
+
+impl MsiX {
+	pub fn read(function: &Function, capability: Capability) -> Self {
+		let control: u16 = function.read(capability.offset + 2);
+		let table: u32 = function.read(capability.offset + 4);
+		let pba: u32 = function.read(capability.offset + 8);
+
+		let table_size = usize::from(control & MSI_X_TABLE_SIZE) + 1;
+		let table_bar = (table & MSI_X_BIR) as usize;
+		let table_offset = table & !MSI_X_BIR;
+		let table_len = (table_size * MSI_X_ENTRY_SIZE) as u64;
+		let table_address = match function.bar(table_bar) {
+			Some(Bar::Memory { address, size, .. })
+				if u64::from(table_offset) + table_len <= size =>
+			{
+				Some(address + u64::from(table_offset))
+			}
+			_ => None,
+		};
+
+		Self {
+			offset: capability.offset,
+			control,
+			table_size,
+			table_bar,
+			table_offset,
+			table_address,
+			pba_bar: (pba & MSI_X_BIR) as usize,
+			pba_offset: pba & !MSI_X_BIR,
+		}
+	}
+}
+
+/// Where a PCI Express function sits in the topology
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortType {
+	Endpoint,
+	LegacyEndpoint,
+	RootComplexEndpoint,
+	RootComplexEventCollector,
+	RootPort,
+	SwitchUpstreamPort,
+	SwitchDownstreamPort,
+	PcieToPciBridge,
+	PciToPcieBridge,
+	Unknown(u8),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PciExpress {
+	pub offset: u16,
+	/// Version of the capability structure, not of the link
+	pub version: u8,
+	pub port_type: PortType,
+}
+
+impl PciExpress {
+	pub fn read(function: &Function, capability: Capability) -> Self {
+		let flags: u16 = function.read(capability.offset + 2);
+
+		let port_type = match (flags >> 4) as u8 & 0xF {
+			0b0000 => PortType::Endpoint,
+			0b0001 => PortType::LegacyEndpoint,
+			0b1001 => PortType::RootComplexEndpoint,
+			0b1010 => PortType::RootComplexEventCollector,
+			0b0100 => PortType::RootPort,
+			0b0101 => PortType::SwitchUpstreamPort,
+			0b0110 => PortType::SwitchDownstreamPort,
+			0b0111 => PortType::PcieToPciBridge,
+			0b1000 => PortType::PciToPcieBridge,
+			other => PortType::Unknown(other),
+		};
+
+		Self {
+			offset: capability.offset,
+			version: flags as u8 & 0xF,
+			port_type,
+		}
+	}
+}
@@ -0,0 +1,94 @@
+//! Human readable names for class codes and the vendors likely to show up
+//! in a VM or on a typical PC
+
+use super::Class;
+
+/// The most specific name there is for a class
+pub fn name(class: Class) -> &'static str {
+	match (class.class, class.subclass, class.prog_if) {
+		(0x00, 0x01, _) => "VGA compatible device",
+		(0x00, ..) => "unclassified device",
+
+		(0x01, 0x00, _) => "SCSI controller",
+		(0x01, 0x01, _) => "IDE controller",
+		(0x01, 0x02, _) => "floppy controller",
+		(0x01, 0x04, _) => "RAID controller",
+		(0x01, 0x05, _) => "ATA controller",
+		(0x01, 0x06, 0x01) => "SATA controller (AHCI)",
+		(0x01, 0x06, _) => "SATA controller",
+		(0x01, 0x07, _) => "SAS controller",
+		(0x01, 0x08, 0x02) => "NVMe controller",
+		(0x01, 0x08, _) => "non-volatile memory controller",
+		(0x01, ..) => "mass storage controller",
+
+		(0x02, 0x00, _) => "Ethernet controller",
+		(0x02, ..) => "network controller",
+
+		(0x03, 0x00, _) => "VGA compatible controller",
+		(0x03, 0x01, _) => "XGA controller",
+		(0x03, 0x02, _) => "3D controller",
+		(0x03, ..) => "display controller",
+
+		(0x04, 0x00, _) => "video device",
+		(0x04, 0x01, _) => "audio device",
+		(0x04, 0x03, _) => "audio device (HDA)",
+		(0x04, ..) => "multimedia controller",
+
+		(0x05, 0x00, _) => "RAM controller",
+		(0x05, ..) => "memory controller",
+
+		(0x06, 0x00, _) => "host bridge",
+		(0x06, 0x01, _) => "ISA bridge",
+		(0x06, 0x04, _) => "PCI bridge",
+		(0x06, 0x07, _) => "CardBus bridge",
+		(0x06, ..) => "bridge",
+
+		(0x07, 0x00, _) => "serial controller",
+		(0x07, 0x01, _) => "parallel controller",
+		(0x07, ..) => "communication controller",
+
+		(0x08, 0x00, _) => "interrupt controller",
+		(0x08, 0x01, _) => "DMA controller",
+		(0x08, 0x02, _) => "timer",
+		(0x08, 0x03, _) => "RTC controller",
+		(0x08, ..) => "system peripheral",
+
+		(0x09, 0x00, _) => "keyboard controller",
+		(0x09, 0x02, _) => "mouse controller",
+		(0x09, ..) => "input device controller",
+
+		(0x0C, 0x03, 0x00) => "USB controller (UHCI)",
+		(0x0C, 0x03, 0x10) => "USB controller (OHCI)",
+		(0x0C, 0x03, 0x20) => "USB controller (EHCI)",
+		(0x0C, 0x03, 0x30) => "USB controller (xHCI)",
+		(0x0C, 0x03, _) => "USB controller",
+		(0x0C, 0x05, _) => "SMBus controller",
+		(0x0C, ..) => "serial bus controller",
+
+		(0x0D, ..) => "wireless controller",
+		(0x0F, ..) => "satellite communication controller",
+		(0x10, ..) => "encryption controller",
+		(0x11, ..) => "signal processing controller",
+		(0x12, ..) => "processing accelerator",
+		(0xFF, ..) => "unassigned class",
+		_ => "unknown device",
+	}
+}
+
+pub fn vendor_name(vendor_id: u16) -> Option<&'static str> {
+	Some(match vendor_id {
+		0x1002 => "AMD/ATI",
+		0x1022 => "AMD",
+		0x10DE => "NVIDIA",
+		0x10EC => "Realtek",
+		0x1234 => "QEMU",
+		0x14E4 => "Broadcom",
+		0x15AD => "VMware",
+		0x1AF4 => "Red Hat (virtio)",
+		0x1B36 => "Red Hat",
+		0x144D => "Samsung",
+		0x8086 => "Intel",
+		0x80EE => "VirtualBox",
+		_ => return None,
+	})
+}
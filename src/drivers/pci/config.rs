@@ -0,0 +1,231 @@
+//! Configuration space access. ECAM (memory mapped, from the ACPI MCFG
+//! table) is used wherever it covers a bus, since it reaches every segment
+//! and the extended 4 KiB space. Everything else goes through the legacy
+//! 0xCF8/0xCFC ports, which only reach the first 256 bytes of segment 0.
+
+use crate::{
+	arch::{
+		acpi, cpu,
+		port::{inb, inl, inw, outb, outl, outw},
+	},
+	kwarn,
+	mm::IDENTITY_MAPPED,
+};
+use core::{fmt, mem::size_of, ptr};
+use spin::Mutex;
+
+const CONFIG_ADDRESS: u16 = 0xCF8;
+const CONFIG_DATA: u16 = 0xCFC;
+const CONFIG_ENABLE: u32 = 1 << 31;
+
+/// Size of the legacy configuration space
+pub const LEGACY_SIZE: u16 = 256;
+/// Size of the extended configuration space ECAM exposes
+pub const EXTENDED_SIZE: u16 = 4096;
+
+const MAX_ECAM_REGIONS: usize = 4;
+// MCFG has 8 reserved bytes after its header, then 16 byte allocations
+const MCFG_RESERVED: usize = 8;
+const MCFG_ENTRY_LEN: usize = 16;
+
+/// Where a function lives
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Address {
+	pub segment: u16,
+	pub bus: u8,
+	pub device: u8,
+	pub function: u8,
+}
+
+impl fmt::Display for Address {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"{:04x}:{:02x}:{:02x}.{}",
+			self.segment, self.bus, self.device, self.function
+		)
+	}
+}
+
+/// A range of buses whose configuration space is mapped into memory
+#[derive(Clone, Copy, Debug)]
+pub struct EcamRegion {
+	pub base: u64,
+	pub segment: u16,
+	pub start_bus: u8,
+	pub end_bus: u8,
+}
+
+impl EcamRegion {
+	fn covers(&self, address: Address) -> bool {
+		address.segment == self.segment
+			&& (self.start_bus..=self.end_bus).contains(&address.bus)
+	}
+
+	fn pointer(&self, address: Address, offset: u16) -> u64 {
+		self.base
+			+ (u64::from(address.bus - self.start_bus) << 20
+				| u64::from(address.device) << 15
+				| u64::from(address.function) << 12
+				| u64::from(offset))
+	}
+}
+
+static ECAM: Mutex<[Option<EcamRegion>; MAX_ECAM_REGIONS]> =
+	Mutex::new([None; MAX_ECAM_REGIONS]);
+
+/// Look for ECAM regions in the MCFG table, returning how many were found
+pub fn init() -> usize {
+	let mcfg = match acpi::find_table(b"MCFG") {
+		Some(mcfg) => mcfg,
+		None => return 0,
+	};
+	let entries = mcfg.data().get(MCFG_RESERVED..).unwrap_or(&[]);
+
+	let mut regions = [None; MAX_ECAM_REGIONS];
+	let mut count = 0;
+	for entry in entries.chunks_exact(MCFG_ENTRY_LEN) {
+		let mut base = [0; 8];
+		base.copy_from_slice(&entry[..8]);
+		let region = EcamRegion {
+			base: u64::from_le_bytes(base),
+			segment: u16::from_le_bytes([entry[8], entry[9]]),
+			start_bus: entry[10],
+			end_bus: entry[11],
+		};
+
+		if region.end_bus < region.start_bus {
+			continue;
+		}
+		let buses = u64::from(region.end_bus - region.start_bus) + 1;
+		let end = region.base.checked_add(buses << 20);
+		if end.map_or(true, |end| end > IDENTITY_MAPPED) {
+			kwarn!("PCI: ECAM at {:#x} isn't mapped, skipping it", region.base);
+			continue;
+		}
+		if count == MAX_ECAM_REGIONS {
+			kwarn!("PCI: too many ECAM regions, ignoring the rest");
+			break;
+		}
+
+		regions[count] = Some(region);
+		count += 1;
+	}
+
+	cpu::without_interrupts(|| *ECAM.lock() = regions);
+	count
+}
+
+/// Every ECAM region found by `init`
+pub fn ecam_regions() -> impl Iterator<Item = EcamRegion> {
+	let regions = cpu::without_interrupts(|| *ECAM.lock());
+	(0..MAX_ECAM_REGIONS).filter_map(move |index| regions[index])
+}
+
+fn ecam_region(address: Address) -> Option<EcamRegion> {
+	ecam_regions().find(|region| region.covers(address))
+}
+
+/// How much configuration space `address` has that can actually be reached
+pub fn space_size(address: Address) -> u16 {
+	if ecam_region(address).is_some() {
+		EXTENDED_SIZE
+	} else if address.segment == 0 {
+		LEGACY_SIZE
+	} else {
+		0
+	}
+}
+
+fn legacy_address(address: Address, offset: u16) -> u32 {
+	CONFIG_ENABLE
+		| u32::from(address.bus) << 16
+		| u32::from(address.device) << 11
+		| u32::from(address.function) << 8
+		| u32::from(offset & 0xFC)
+}
+
+/// A register width configuration space can be accessed with
+pub trait Access: Copy {
+	/// What reads from nothing give back
+	const ABSENT: Self;
+
+	unsafe fn read_legacy(port: u16) -> Self;
+	unsafe fn write_legacy(port: u16, value: Self);
+}
+
+impl Access for u8 {
+	const ABSENT: Self = 0xFF;
+
+	unsafe fn read_legacy(port: u16) -> Self {
+		inb(port)
+	}
+
+	unsafe fn write_legacy(port: u16, value: Self) {
+		outb(port, value)
+	}
+}
+
+impl Access for u16 {
+	const ABSENT: Self = 0xFFFF;
+
+	unsafe fn read_legacy(port: u16) -> Self {
+		inw(port)
+	}
+
+	unsafe fn write_legacy(port: u16, value: Self) {
+		outw(port, value)
+	}
+}
+
+impl Access for u32 {
+	const ABSENT: Self = 0xFFFF_FFFF;
+
+	unsafe fn read_legacy(port: u16) -> Self {
+		inl(port)
+	}
+
+	unsafe fn write_legacy(port: u16, value: Self) {
+		outl(port, value)
+	}
+}
+
+/// Read a register. `offset` has to be aligned to the register's size, and
+/// anything out of reach reads as all ones, like a missing device.
+pub fn read<T: Access>(address: Address, offset: u16) -> T {
+	debug_assert_eq!(offset as usize % size_of::<T>(), 0);
+
+	if let Some(region) = ecam_region(address) {
+		if offset < EXTENDED_SIZE {
+			let pointer = region.pointer(address, offset) as *const T;
+			// SAFETY: the region was checked to be identity mapped
+			return unsafe { ptr::read_volatile(pointer) };
+		}
+	} else if address.segment == 0 && offset < LEGACY_SIZE {
+		// the address and data ports have to be used as a pair
+		return cpu::without_interrupts(|| unsafe {
+			outl(CONFIG_ADDRESS, legacy_address(address, offset));
+			T::read_legacy(CONFIG_DATA + (offset & 3))
+		});
+	}
+	T::ABSENT
+}
+
+/// Write a register, with the same rules as `read`. Writes to anything out of
+/// reach are dropped.
+pub fn write<T: Access>(address: Address, offset: u16, value: T) {
+	debug_assert_eq!(offset as usize % size_of::<T>(), 0);
+
+	if let Some(region) = ecam_region(address) {
+		if offset < EXTENDED_SIZE {
+			let pointer = region.pointer(address, offset) as *mut T;
+			// SAFETY: the region was checked to be identity mapped
+			unsafe { ptr::write_volatile(pointer, value) };
+		}
+	} else if address.segment == 0 && offset < LEGACY_SIZE {
+		cpu::without_interrupts(|| unsafe {
+			outl(CONFIG_ADDRESS, legacy_address(address, offset));
+			T::write_legacy(CONFIG_DATA + (offset & 3), value);
+		});
+	}
+}
@@ -0,0 +1,476 @@
+//! PCI and PCI Express. Every bus is scanned once at boot and the functions
+//! found are kept in a table, which drivers bind to by registering what
+//! vendor/device IDs or classes they handle.
+
+pub mod bar;
+pub mod capability;
+pub mod class;
+pub mod config;
+pub mod msi;
+
+pub use bar::Bar;
+pub use capability::{Capabilities, Capability, Msi, MsiX, PciExpress};
+pub use config::Address;
+
+use crate::{kdebug, kinfo, kwarn};
+use bitflags::bitflags;
+use config::Access;
+use spin::Mutex;
+
+// registers in the header every function has
+const VENDOR_ID: u16 = 0x00;
+const DEVICE_ID: u16 = 0x02;
+const COMMAND: u16 = 0x04;
+const STATUS: u16 = 0x06;
+const REVISION: u16 = 0x08;
+const PROG_IF: u16 = 0x09;
+const SUBCLASS: u16 = 0x0A;
+const CLASS: u16 = 0x0B;
+const HEADER_TYPE: u16 = 0x0E;
+// registers in a general device (type 0) header
+const SUBSYSTEM_VENDOR_ID: u16 = 0x2C;
+const SUBSYSTEM_ID: u16 = 0x2E;
+const CAPABILITIES: u16 = 0x34;
+const INTERRUPT_LINE: u16 = 0x3C;
+const INTERRUPT_PIN: u16 = 0x3D;
+
+/// Vendor ID of a function that isn't there
+const NO_VENDOR: u16 = 0xFFFF;
+const MULTIFUNCTION: u8 = 0x80;
+
+const DEVICES_PER_BUS: u8 = 32;
+const FUNCTIONS_PER_DEVICE: u8 = 8;
+
+const MAX_FUNCTIONS: usize = 64;
+/// BARs in a general device's header, the most any header has
+const MAX_BARS: usize = HeaderType::General.bars();
+const MAX_DRIVERS: usize = 16;
+
+bitflags! {
+	pub struct Command: u16 {
+		const IO_SPACE = 1 << 0;
+		const MEMORY_SPACE = 1 << 1;
+		/// Lets the function start DMA on its own
+		const BUS_MASTER = 1 << 2;
+		const PARITY_ERROR_RESPONSE = 1 << 6;
+		const SERR = 1 << 8;
+		/// Stops the function from asserting legacy INTx interrupts
+		const INTERRUPT_DISABLE = 1 << 10;
+	}
+}
+
+bitflags! {
+	pub struct Status: u16 {
+		const INTERRUPT = 1 << 3;
+		const CAPABILITIES_LIST = 1 << 4;
+		const MASTER_DATA_PARITY_ERROR = 1 << 8;
+		const SIGNALED_TARGET_ABORT = 1 << 11;
+		const RECEIVED_TARGET_ABORT = 1 << 12;
+		const RECEIVED_MASTER_ABORT = 1 << 13;
+		const SIGNALED_SYSTEM_ERROR = 1 << 14;
+		const DETECTED_PARITY_ERROR = 1 << 15;
+	}
+}
+
+/// Layout of the rest of the configuration header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaderType {
+	General,
+	PciBridge,
+	CardBusBridge,
+	Unknown(u8),
+}
+
+impl HeaderType {
+	const fn from_u8(header_type: u8) -> Self {
+		match header_type & !MULTIFUNCTION {
+			0 => Self::General,
+			1 => Self::PciBridge,
+			2 => Self::CardBusBridge,
+			other => Self::Unknown(other),
+		}
+	}
+
+	/// How many BARs the header has
+	pub const fn bars(self) -> usize {
+		match self {
+			Self::General => 6,
+			Self::PciBridge => 2,
+			_ => 0,
+		}
+	}
+}
+
+/// What kind of function something is, e.g. 01:06:01 for an AHCI controller
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Class {
+	pub class: u8,
+	pub subclass: u8,
+	pub prog_if: u8,
+}
+
+impl Class {
+	pub fn name(self) -> &'static str {
+		class::name(self)
+	}
+}
+
+/// A function found on the bus, with the parts of its header that don't
+/// change
+#[derive(Clone, Copy, Debug)]
+pub struct Function {
+	pub address: Address,
+	pub vendor_id: u16,
+	pub device_id: u16,
+	pub class: Class,
+	pub revision: u8,
+	pub header_type: HeaderType,
+	/// 0 for anything but general devices
+	pub subsystem_vendor_id: u16,
+	pub subsystem_id: u16,
+	/// Legacy interrupt pin (1 to 4 for INTA to INTD), 0 if there's none
+	pub interrupt_pin: u8,
+	/// What firmware routed the pin to on the PIC, 0xFF if it didn't
+	pub interrupt_line: u8,
+	/// Sized once while scanning, since sizing them means turning decoding
+	/// off
+	bars: [Option<Bar>; MAX_BARS],
+}
+
+impl Function {
+	/// The function at `address`, if there is one
+	pub fn at(address: Address) -> Option<Self> {
+		let vendor_id: u16 = config::read(address, VENDOR_ID);
+		if vendor_id == NO_VENDOR {
+			return None;
+		}
+
+		let header_type =
+			HeaderType::from_u8(config::read(address, HEADER_TYPE));
+		let general = header_type == HeaderType::General;
+		let read_u8 = |offset| config::read::<u8>(address, offset);
+		let read_general = |offset| {
+			if general {
+				config::read::<u16>(address, offset)
+			} else {
+				0
+			}
+		};
+
+		Some(Self {
+			address,
+			vendor_id,
+			device_id: config::read(address, DEVICE_ID),
+			class: Class {
+				class: read_u8(CLASS),
+				subclass: read_u8(SUBCLASS),
+				prog_if: read_u8(PROG_IF),
+			},
+			revision: read_u8(REVISION),
+			header_type,
+			subsystem_vendor_id: read_general(SUBSYSTEM_VENDOR_ID),
+			subsystem_id: read_general(SUBSYSTEM_ID),
+			interrupt_pin: read_u8(INTERRUPT_PIN),
+			interrupt_line: read_u8(INTERRUPT_LINE),
+			bars: [None; MAX_BARS],
+		})
+	}
+
+	pub fn read<T: Access>(&self, offset: u16) -> T {
+		config::read(self.address, offset)
+	}
+
+	pub fn write<T: Access>(&self, offset: u16, value: T) {
+		config::write(self.address, offset, value)
+	}
+
+	pub fn command(&self) -> Command {
+		Command::from_bits_truncate(self.read(COMMAND))
+	}
+
+	pub fn set_command(&self, command: Command) {
+		self.write(COMMAND, command.bits());
+	}
+
+	/// Turn on some command bits, leaving the rest alone
+	pub fn enable(&self, flags: Command) {
+		self.set_command(self.command() | flags);
+	}
+
+	pub fn disable(&self, flags: Command) {
+		self.set_command(self.command() - flags);
+	}
+
+	pub fn status(&self) -> Status {
+		Status::from_bits_truncate(self.read(STATUS))
+	}
+
+	/// Decode and size every BAR. Only safe before a driver has the function
+	/// doing anything, so this happens once, when it's found.
+	fn size_bars(&mut self) {
+		let mut index = 0;
+		while index < self.header_type.bars() {
+			let bar = bar::probe(self, index);
+			self.bars[index] = bar;
+
+			// the top half of a 64 bit BAR stays `None`
+			index += match bar {
+				Some(Bar::Memory { wide: true, .. }) => 2,
+				_ => 1,
+			};
+		}
+	}
+
+	/// A BAR as it was when the function was found, or `None` if it's unused
+	/// (or is the top half of a 64 bit one)
+	pub fn bar(&self, index: usize) -> Option<Bar> {
+		self.bars.get(index).copied().flatten()
+	}
+
+	/// Every BAR in use, along with its index
+	pub fn bars(&self) -> impl Iterator<Item = (usize, Bar)> + '_ {
+		(0..self.header_type.bars())
+			.filter_map(move |index| Some((index, self.bar(index)?)))
+	}
+
+	pub fn capabilities(&self) -> Capabilities {
+		let start = if self.status().contains(Status::CAPABILITIES_LIST) {
+			self.read(CAPABILITIES)
+		} else {
+			0
+		};
+		Capabilities::new(self.address, start)
+	}
+
+	pub fn capability(&self, id: u8) -> Option<Capability> {
+		self.capabilities().find(|capability| capability.id == id)
+	}
+
+	pub fn msi(&self) -> Option<Msi> {
+		Some(Msi::read(self, self.capability(capability::MSI)?))
+	}
+
+	pub fn msix(&self) -> Option<MsiX> {
+		Some(MsiX::read(self, self.capability(capability::MSI_X)?))
+	}
+
+	pub fn pci_express(&self) -> Option<PciExpress> {
+		let capability = self.capability(capability::PCI_EXPRESS)?;
+		Some(PciExpress::read(self, capability))
+	}
+}
+
+/// What a driver can bind to
+#[derive(Clone, Copy, Debug)]
+pub enum Match {
+	Id {
+		vendor: u16,
+		device: u16,
+	},
+	/// A whole class of devices, with any programming interface if `prog_if`
+	/// is `None`
+	Class {
+		class: u8,
+		subclass: u8,
+		prog_if: Option<u8>,
+	},
+}
+
+impl Match {
+	pub fn matches(&self, function: &Function) -> bool {
+		match *self {
+			Self::Id { vendor, device } => {
+				function.vendor_id == vendor && function.device_id == device
+			}
+			Self::Class {
+				class,
+				subclass,
+				prog_if,
+			} => {
+				function.class.class == class
+					&& function.class.subclass == subclass
+					&& prog_if.map_or(true, |p| function.class.prog_if == p)
+			}
+		}
+	}
+}
+
+pub struct Driver {
+	pub name: &'static str,
+	pub matches: &'static [Match],
+	/// Set up a function that matched, returning false if the driver can't
+	/// handle it after all
+	pub probe: fn(&Function) -> bool,
+}
+
+#[derive(Clone, Copy)]
+struct Slot {
+	function: Function,
+	driver: Option<&'static Driver>,
+}
+
+static FUNCTIONS: Mutex<[Option<Slot>; MAX_FUNCTIONS]> =
+	Mutex::new([None; MAX_FUNCTIONS]);
+static DRIVERS: Mutex<[Option<&'static Driver>; MAX_DRIVERS]> =
+	Mutex::new([None; MAX_DRIVERS]);
+
+/// Every function found, in bus order
+pub fn functions() -> impl Iterator<Item = Function> {
+	let functions = *FUNCTIONS.lock();
+	(0..MAX_FUNCTIONS).filter_map(move |index| Some(functions[index]?.function))
+}
+
+/// Name of the driver bound to the function at `address`, if any
+pub fn driver(address: Address) -> Option<&'static str> {
+	FUNCTIONS
+		.lock()
+		.iter()
+		.flatten()
+		.find(|slot| slot.function.address == address)?
+		.driver
+		.map(|driver| driver.name)
+}
+
+/// Add a driver, binding it to anything it matches that doesn't have a
+/// driver yet. Returns false if there are already too many drivers.
+pub fn register_driver(driver: &'static Driver) -> bool {
+	{
+		let mut drivers = DRIVERS.lock();
+		match drivers.iter_mut().find(|slot| slot.is_none()) {
+			Some(slot) => *slot = Some(driver),
+			None => return false,
+		}
+	}
+
+	bind_all();
+	true
+}
+
+/// Offer every unbound function to the drivers that match it
+fn bind_all() {
+	for index in 0..MAX_FUNCTIONS {
+		// neither lock is held while probing, since drivers will want to look
+		// at other functions (or register more drivers)
+		let function = match FUNCTIONS.lock()[index] {
+			Some(slot) if slot.driver.is_none() => slot.function,
+			_ => continue,
+		};
+		let drivers = *DRIVERS.lock();
+
+		let bound = drivers.iter().flatten().find(|driver| {
+			driver.matches.iter().any(|m| m.matches(&function))
+				&& (driver.probe)(&function)
+		});
+
+		if let Some(&driver) = bound {
+			kinfo!("PCI: {} bound to {}", driver.name, function.address);
+			if let Some(slot) = &mut FUNCTIONS.lock()[index] {
+				slot.driver = Some(driver);
+			}
+		}
+	}
+}
+
+/// Add every function of a device to `functions`, returning false if it's
+/// full
+fn scan_device(
+	segment: u16,
+	bus: u8,
+	device: u8,
+	functions: &mut [Option<Slot>; MAX_FUNCTIONS],
+	count: &mut usize,
+) -> bool {
+	let first = Address {
+		segment,
+		bus,
+		device,
+		function: 0,
+	};
+	if config::read::<u16>(first, VENDOR_ID) == NO_VENDOR {
+		return true;
+	}
+
+	// functions past 0 only exist on multifunction devices
+	let multifunction =
+		config::read::<u8>(first, HEADER_TYPE) & MULTIFUNCTION != 0;
+	let last = if multifunction {
+		FUNCTIONS_PER_DEVICE
+	} else {
+		1
+	};
+
+	for function in 0..last {
+		let address = Address { function, ..first };
+		let mut function = match Function::at(address) {
+			Some(function) => function,
+			None => continue,
+		};
+		if *count == MAX_FUNCTIONS {
+			return false;
+		}
+		function.size_bars();
+
+		kdebug!(
+			"PCI: {} {:04x}:{:04x} {}",
+			address,
+			function.vendor_id,
+			function.device_id,
+			function.class.name()
+		);
+		functions[*count] = Some(Slot {
+			function,
+			driver: None,
+		});
+		*count += 1;
+	}
+	true
+}
+
+/// Find ECAM, scan every bus it covers (or every bus on segment 0 through
+/// the legacy ports without it), then bind any drivers already registered
+pub fn init() {
+	let ecam_regions = config::init();
+
+	let mut functions = [None; MAX_FUNCTIONS];
+	let mut count = 0;
+	let mut scan = |segment, buses: core::ops::RangeInclusive<u8>| {
+		for bus in buses {
+			for device in 0..DEVICES_PER_BUS {
+				if !scan_device(
+					segment,
+					bus,
+					device,
+					&mut functions,
+					&mut count,
+				) {
+					return false;
+				}
+			}
+		}
+		true
+	};
+
+	let complete = if ecam_regions == 0 {
+		scan(0, 0..=u8::MAX)
+	} else {
+		config::ecam_regions().all(|region| {
+			scan(region.segment, region.start_bus..=region.end_bus)
+		})
+	};
+	if !complete {
+		kwarn!("PCI: too many functions, only keeping {}", MAX_FUNCTIONS);
+	}
+
+	*FUNCTIONS.lock() = functions;
+	kinfo!(
+		"PCI: found {} functions using {}",
+		count,
+		if ecam_regions == 0 {
+			"port I/O"
+		} else {
+			"ECAM"
+		}
+	);
+
+	bind_all();
+}
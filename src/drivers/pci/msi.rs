@@ -0,0 +1,286 @@
+//! Programming MSI and MSI-X. Either way the function raises an interrupt by
+//! writing a message (a vector, really) to an address that picks which
+//! CPU's local APIC gets it, so no legacy INTx routing is involved. Handlers
+//! for these vectors have to finish with `apic::eoi`.
+
+use super::{Command, Function, Msi, MsiX};
+use crate::{
+	arch::{apic, idt},
+	mm::IDENTITY_MAPPED,
+};
+use core::ptr;
+
+/// Messages written anywhere in this range go to a local APIC
+const MESSAGE_ADDRESS: u64 = 0xFEE0_0000;
+const DESTINATION_SHIFT: u64 = 12;
+
+// MSI registers, as offsets from the capability
+const MSI_CONTROL: u16 = 0x02;
+const MSI_ADDRESS_LOW: u16 = 0x04;
+const MSI_ADDRESS_HIGH: u16 = 0x08;
+// these two move up by 4 when the address is 64 bit
+const MSI_DATA: u16 = 0x08;
+const MSI_MASK: u16 = 0x0C;
+
+const MSI_ENABLE: u16 = 1 << 0;
+const MSI_MULTIPLE_MESSAGE_ENABLE: u16 = 0b111 << 4;
+
+// MSI-X registers, as offsets from the capability
+const MSI_X_CONTROL: u16 = 0x02;
+const MSI_X_ENABLE: u16 = 1 << 15;
+/// Masks every vector at once, regardless of their own masks
+const MSI_X_FUNCTION_MASK: u16 = 1 << 14;
+
+// MSI-X table entries, as offsets in 32 bit words
+const ENTRY_WORDS: usize = 4;
+const ENTRY_ADDRESS_LOW: usize = 0;
+const ENTRY_ADDRESS_HIGH: usize = 1;
+const ENTRY_DATA: usize = 2;
+const ENTRY_CONTROL: usize = 3;
+const ENTRY_MASKED: u32 = 1 << 0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MsiError {
+	/// The function has neither capability (or not the one asked for)
+	Unsupported,
+	/// The local APIC isn't running, so there's nothing to send messages to
+	NoApic,
+	/// The function can't do that many vectors
+	TooManyVectors,
+	/// Every IDT vector is taken
+	NoVectors,
+	/// The MSI-X table isn't in a memory BAR inside the identity map
+	TableUnreachable,
+}
+
+/// What the function writes, and where, to raise an interrupt
+#[derive(Clone, Copy, Debug)]
+pub struct Message {
+	pub address: u64,
+	pub data: u32,
+}
+
+impl Message {
+	/// Fixed, edge triggered delivery of `vector` to the local APIC with ID
+	/// `apic_id`
+	pub fn new(vector: u8, apic_id: u8) -> Self {
+		Self {
+			address: MESSAGE_ADDRESS | u64::from(apic_id) << DESTINATION_SHIFT,
+			data: vector.into(),
+		}
+	}
+}
+
+impl Msi {
+	fn data_offset(&self) -> u16 {
+		if self.is_64bit() {
+			self.offset + MSI_DATA + 4
+		} else {
+			self.offset + MSI_DATA
+		}
+	}
+
+	/// Point the function at `message`, using `count` vectors starting at its
+	/// data (a power of two, with the data aligned to it), and turn MSI on
+	pub fn enable(&self, function: &Function, message: Message, count: usize) {
+		let address_high = (message.address >> 32) as u32;
+		function.write(self.offset + MSI_ADDRESS_LOW, message.address as u32);
+		if self.is_64bit() {
+			function.write(self.offset + MSI_ADDRESS_HIGH, address_high);
+		}
+		function.write(self.data_offset(), message.data as u16);
+
+		// the count is encoded as a power of two
+		let encoded = count.trailing_zeros() as u16;
+		let control = self.control & !MSI_MULTIPLE_MESSAGE_ENABLE
+			| encoded << 4
+			| MSI_ENABLE;
+		function.write(self.offset + MSI_CONTROL, control);
+	}
+
+	pub fn disable(&self, function: &Function) {
+		let control: u16 = function.read(self.offset + MSI_CONTROL);
+		function.write(self.offset + MSI_CONTROL, control & !MSI_ENABLE);
+	}
+
+	/// Mask or unmask one of the vectors, if the function supports that
+	pub fn set_masked(&self, function: &Function, index: usize, masked: bool) {
+		if !self.per_vector_masking() {
+			return;
+		}
+
+		let offset = self.data_offset() - MSI_DATA + MSI_MASK;
+		let mask: u32 = function.read(offset);
+		let mask = if masked {
+			mask | 1 << index
+		} else {
+			mask & !(1 << index)
+		};
+		function.write(offset, mask);
+	}
+}
+
+impl MsiX {
+	/// The table of addresses and data, mapped through the identity map
+	fn table(&self) -> Result<*mut u32, MsiError> {
+		let address = self.table_address.ok_or(MsiError::TableUnreachable)?;
+		let table_len = (self.table_size * ENTRY_WORDS * 4) as u64;
+		if address + table_len > IDENTITY_MAPPED {
+			return Err(MsiError::TableUnreachable);
+		}
+		Ok(address as *mut u32)
+	}
+
+	/// Point entry `index` at `message`. The entry is left unmasked.
+	pub fn set_entry(
+		&self,
+		index: usize,
+		message: Message,
+	) -> Result<(), MsiError> {
+		if index >= self.table_size {
+			return Err(MsiError::TooManyVectors);
+		}
+		let entry = self.table()?.wrapping_add(index * ENTRY_WORDS);
+
+		// SAFETY: `table` made sure the entry is inside a mapped BAR
+		unsafe {
+			let word = |word| entry.add(word);
+			ptr::write_volatile(word(ENTRY_CONTROL), ENTRY_MASKED);
+			ptr::write_volatile(
+				word(ENTRY_ADDRESS_LOW),
+				message.address as u32,
+			);
+			let address_high = (message.address >> 32) as u32;
+			ptr::write_volatile(word(ENTRY_ADDRESS_HIGH), address_high);
+			ptr::write_volatile(word(ENTRY_DATA), message.data);
+			ptr::write_volatile(word(ENTRY_CONTROL), 0);
+		}
+		Ok(())
+	}
+
+	pub fn set_masked(
+		&self,
+		index: usize,
+		masked: bool,
+	) -> Result<(), MsiError> {
+		if index >= self.table_size {
+			return Err(MsiError::TooManyVectors);
+		}
+		let entry = self.table()?.wrapping_add(index * ENTRY_WORDS);
+		let control = if masked { ENTRY_MASKED } else { 0 };
+
+		// SAFETY: `table` made sure the entry is inside a mapped BAR
+		unsafe { ptr::write_volatile(entry.add(ENTRY_CONTROL), control) };
+		Ok(())
+	}
+
+	/// Turn MSI-X on. Entries that haven't been set up should stay masked.
+	pub fn enable(&self, function: &Function) {
+		let control: u16 = function.read(self.offset + MSI_X_CONTROL);
+		let control = control & !MSI_X_FUNCTION_MASK | MSI_X_ENABLE;
+		function.write(self.offset + MSI_X_CONTROL, control);
+	}
+
+	pub fn disable(&self, function: &Function) {
+		let control: u16 = function.read(self.offset + MSI_X_CONTROL);
+		function.write(self.offset + MSI_X_CONTROL, control & !MSI_X_ENABLE);
+	}
+}
+
+/// Allocate vectors and install a handler on each
+fn install(handlers: &[idt::Handler]) -> Result<u8, MsiError> {
+	let first =
+		idt::allocate_vectors(handlers.len()).ok_or(MsiError::NoVectors)?;
+	for (vector, &handler) in (first..).zip(handlers) {
+		idt::set_handler(vector, handler);
+	}
+	Ok(first)
+}
+
+/// Deliver the function's interrupts with MSI, one vector per handler, to
+/// the local APIC with ID `apic_id`. Returns the first vector.
+pub fn enable_msi(
+	function: &Function,
+	handlers: &[idt::Handler],
+	apic_id: u8,
+) -> Result<u8, MsiError> {
+	let msi = function.msi().ok_or(MsiError::Unsupported)?;
+	if !apic::enabled() {
+		return Err(MsiError::NoApic);
+	}
+	// MSI can only do powers of two
+	let count = handlers.len();
+	if count == 0 || !count.is_power_of_two() || count > msi.vectors_capable() {
+		return Err(MsiError::TooManyVectors);
+	}
+
+	let first = install(handlers)?;
+	if let Some(msix) = function.msix() {
+		msix.disable(function);
+	}
+	msi.enable(function, Message::new(first, apic_id), count);
+	function.enable(Command::INTERRUPT_DISABLE);
+	Ok(first)
+}
+
+/// Deliver the function's interrupts with MSI-X, handler `i` handling table
+/// entry `i`, to the local APIC with ID `apic_id`. Returns the first vector.
+pub fn enable_msix(
+	function: &Function,
+	handlers: &[idt::Handler],
+	apic_id: u8,
+) -> Result<u8, MsiError> {
+	let msix = function.msix().ok_or(MsiError::Unsupported)?;
+	if !apic::enabled() {
+		return Err(MsiError::NoApic);
+	}
+	if handlers.is_empty() || handlers.len() > msix.table_size {
+		return Err(MsiError::TooManyVectors);
+	}
+	// the table is in memory space, which has to be decoded to write it
+	msix.table()?;
+	function.enable(Command::MEMORY_SPACE);
+
+	let first = install(handlers)?;
+	for (index, vector) in (first..).take(handlers.len()).enumerate() {
+		if let Err(e) = msix.set_entry(index, Message::new(vector, apic_id)) {
+			idt::free_vectors(first, handlers.len());
+			return Err(e);
+		}
+	}
+
+	if let Some(msi) = function.msi() {
+		msi.disable(function);
+	}
+	msix.enable(function);
+	function.enable(Command::INTERRUPT_DISABLE);
+	Ok(first)
+}
+
+/// Deliver the function's interrupts to `handler` on the boot CPU, with
+/// MSI-X if the function has it and MSI otherwise. Returns the vector.
+pub fn enable(
+	function: &Function,
+	handler: idt::Handler,
+) -> Result<u8, MsiError> {
+	let apic_id = apic::id().ok_or(MsiError::NoApic)?;
+
+	match enable_msix(function, &[handler], apic_id) {
+		Err(MsiError::Unsupported) | Err(MsiError::TableUnreachable) => {
+			enable_msi(function, &[handler], apic_id)
+		}
+		result => result,
+	}
+}
+
+/// Undo `enable` (or `enable_msi`/`enable_msix`): turn MSI and MSI-X off
+/// and free the `count` vectors starting at `first`
+pub fn disable(function: &Function, first: u8, count: usize) {
+	if let Some(msix) = function.msix() {
+		msix.disable(function);
+	}
+	if let Some(msi) = function.msi() {
+		msi.disable(function);
+	}
+	idt::free_vectors(first, count);
+}
@@ -0,0 +1,548 @@
+//! Keyboard on the first PS/2 port. Scancodes (set 1 or 2, whichever the
+//! keyboard speaks) are decoded into key codes as they arrive, modifier and
+//! lock state is tracked, and each press or release is published as an
+//! input event carrying the character the current keymap gives it.
+
+use super::{Port, Ps2Error, ACK, RESEND};
+use crate::{
+	arch::{
+		cpu,
+		idt::{self, InterruptStackFrame},
+		pic,
+	},
+	input::{
+		self, keymap, DeviceId, DeviceKind, EventKind, KeyCode, Modifiers,
+	},
+};
+use bitflags::bitflags;
+use spin::Mutex;
+
+const PORT: Port = Port::First;
+
+// device commands
+const SET_LEDS: u8 = 0xED;
+const SCANCODE_SET: u8 = 0xF0;
+const ENABLE_SCANNING: u8 = 0xF4;
+
+bitflags! {
+	struct Leds: u8 {
+		const SCROLL_LOCK = 0b001;
+		const NUM_LOCK = 0b010;
+		const CAPS_LOCK = 0b100;
+	}
+}
+
+impl Leds {
+	fn from_modifiers(modifiers: Modifiers) -> Self {
+		let mut leds = Self::empty();
+		leds.set(
+			Self::SCROLL_LOCK,
+			modifiers.contains(Modifiers::SCROLL_LOCK),
+		);
+		leds.set(Self::NUM_LOCK, modifiers.contains(Modifiers::NUM_LOCK));
+		leds.set(Self::CAPS_LOCK, modifiers.contains(Modifiers::CAPS_LOCK));
+		leds
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScancodeSet {
+	Set1,
+	Set2,
+}
+
+/// Turns scancode bytes into key presses and releases
+struct Decoder {
+	set: ScancodeSet,
+	// an 0xE0 prefix came before this byte
+	extended: bool,
+	// an 0xF0 prefix came before this byte (set 2 only)
+	released: bool,
+	// bytes of the pause sequence still to swallow
+	pause_left: u8,
+}
+
+impl Decoder {
+	const fn new(set: ScancodeSet) -> Self {
+		Self {
+			set,
+			extended: false,
+			released: false,
+			pause_left: 0,
+		}
+	}
+
+	fn feed(&mut self, byte: u8) -> Option<(KeyCode, bool)> {
+		// pause is the only key with an 0xE1 prefix, and it sends its whole
+		// make and break sequence at once when pressed
+		if self.pause_left > 0 {
+			self.pause_left -= 1;
+			return if self.pause_left == 0 {
+				Some((KeyCode::Pause, true))
+			} else {
+				None
+			};
+		}
+
+		match (byte, self.set) {
+			(0xE0, _) => {
+				self.extended = true;
+				return None;
+			}
+			(0xE1, ScancodeSet::Set1) => {
+				self.pause_left = 5;
+				return None;
+			}
+			(0xE1, ScancodeSet::Set2) => {
+				self.pause_left = 7;
+				return None;
+			}
+			(0xF0, ScancodeSet::Set2) => {
+				self.released = true;
+				return None;
+			}
+			_ => {}
+		}
+
+		let extended = core::mem::take(&mut self.extended);
+		match self.set {
+			ScancodeSet::Set1 => {
+				let code = if extended {
+					set1_extended(byte & 0x7F)
+				} else {
+					set1(byte & 0x7F)
+				}?;
+				Some((code, byte & 0x80 == 0))
+			}
+			ScancodeSet::Set2 => {
+				let pressed = !core::mem::take(&mut self.released);
+				let code = if extended {
+					set2_extended(byte)
+				} else {
+					set2(byte)
+				}?;
+				Some((code, pressed))
+			}
+		}
+	}
+}
+
+fn set1(byte: u8) -> Option<KeyCode> {
+	use KeyCode::*;
+
+	Some(match byte {
+		0x01 => Escape,
+		0x02 => Key1,
+		0x03 => Key2,
+		0x04 => Key3,
+		0x05 => Key4,
+		0x06 => Key5,
+		0x07 => Key6,
+		0x08 => Key7,
+		0x09 => Key8,
+		0x0A => Key9,
+		0x0B => Key0,
+		0x0C => Minus,
+		0x0D => Equals,
+		0x0E => Backspace,
+		0x0F => Tab,
+		0x10 => Q,
+		0x11 => W,
+		0x12 => E,
+		0x13 => R,
+		0x14 => T,
+		0x15 => Y,
+		0x16 => U,
+		0x17 => I,
+		0x18 => O,
+		0x19 => P,
+		0x1A => LeftBracket,
+		0x1B => RightBracket,
+		0x1C => Enter,
+		0x1D => LeftCtrl,
+		0x1E => A,
+		0x1F => S,
+		0x20 => D,
+		0x21 => F,
+		0x22 => G,
+		0x23 => H,
+		0x24 => J,
+		0x25 => K,
+		0x26 => L,
+		0x27 => Semicolon,
+		0x28 => Quote,
+		0x29 => Backtick,
+		0x2A => LeftShift,
+		0x2B => Backslash,
+		0x2C => Z,
+		0x2D => X,
+		0x2E => C,
+		0x2F => V,
+		0x30 => B,
+		0x31 => N,
+		0x32 => M,
+		0x33 => Comma,
+		0x34 => Period,
+		0x35 => Slash,
+		0x36 => RightShift,
+		0x37 => KeypadStar,
+		0x38 => LeftAlt,
+		0x39 => Space,
+		0x3A => CapsLock,
+		0x3B => F1,
+		0x3C => F2,
+		0x3D => F3,
+		0x3E => F4,
+		0x3F => F5,
+		0x40 => F6,
+		0x41 => F7,
+		0x42 => F8,
+		0x43 => F9,
+		0x44 => F10,
+		0x45 => NumLock,
+		0x46 => ScrollLock,
+		0x47 => Keypad7,
+		0x48 => Keypad8,
+		0x49 => Keypad9,
+		0x4A => KeypadMinus,
+		0x4B => Keypad4,
+		0x4C => Keypad5,
+		0x4D => Keypad6,
+		0x4E => KeypadPlus,
+		0x4F => Keypad1,
+		0x50 => Keypad2,
+		0x51 => Keypad3,
+		0x52 => Keypad0,
+		0x53 => KeypadPeriod,
+		0x56 => NonUsBackslash,
+		0x57 => F11,
+		0x58 => F12,
+		_ => return None,
+	})
+}
+
+fn set1_extended(byte: u8) -> Option<KeyCode> {
+	use KeyCode::*;
+
+	// print screen's fake shift presses (0x2A, 0x36) fall through to None
+	Some(match byte {
+		0x1C => KeypadEnter,
+		0x1D => RightCtrl,
+		0x35 => KeypadSlash,
+		0x37 => PrintScreen,
+		0x38 => RightAlt,
+		0x47 => Home,
+		0x48 => Up,
+		0x49 => PageUp,
+		0x4B => Left,
+		0x4D => Right,
+		0x4F => End,
+		0x50 => Down,
+		0x51 => PageDown,
+		0x52 => Insert,
+		0x53 => Delete,
+		0x5B => LeftMeta,
+		0x5C => RightMeta,
+		0x5D => Menu,
+		_ => return None,
+	})
+}
+
+fn set2(byte: u8) -> Option<KeyCode> {
+	use KeyCode::*;
+
+	Some(match byte {
+		0x01 => F9,
+		0x03 => F5,
+		0x04 => F3,
+		0x05 => F1,
+		0x06 => F2,
+		0x07 => F12,
+		0x09 => F10,
+		0x0A => F8,
+		0x0B => F6,
+		0x0C => F4,
+		0x0D => Tab,
+		0x0E => Backtick,
+		0x11 => LeftAlt,
+		0x12 => LeftShift,
+		0x14 => LeftCtrl,
+		0x15 => Q,
+		0x16 => Key1,
+		0x1A => Z,
+		0x1B => S,
+		0x1C => A,
+		0x1D => W,
+		0x1E => Key2,
+		0x21 => C,
+		0x22 => X,
+		0x23 => D,
+		0x24 => E,
+		0x25 => Key4,
+		0x26 => Key3,
+		0x29 => Space,
+		0x2A => V,
+		0x2B => F,
+		0x2C => T,
+		0x2D => R,
+		0x2E => Key5,
+		0x31 => N,
+		0x32 => B,
+		0x33 => H,
+		0x34 => G,
+		0x35 => Y,
+		0x36 => Key6,
+		0x3A => M,
+		0x3B => J,
+		0x3C => U,
+		0x3D => Key7,
+		0x3E => Key8,
+		0x41 => Comma,
+		0x42 => K,
+		0x43 => I,
+		0x44 => O,
+		0x45 => Key0,
+		0x46 => Key9,
+		0x49 => Period,
+		0x4A => Slash,
+		0x4B => L,
+		0x4C => Semicolon,
+		0x4D => P,
+		0x4E => Minus,
+		0x52 => Quote,
+		0x54 => LeftBracket,
+		0x55 => Equals,
+		0x58 => CapsLock,
+		0x59 => RightShift,
+		0x5A => Enter,
+		0x5B => RightBracket,
+		0x5D => Backslash,
+		0x61 => NonUsBackslash,
+		0x66 => Backspace,
+		0x69 => Keypad1,
+		0x6B => Keypad4,
+		0x6C => Keypad7,
+		0x70 => Keypad0,
+		0x71 => KeypadPeriod,
+		0x72 => Keypad2,
+		0x73 => Keypad5,
+		0x74 => Keypad6,
+		0x75 => Keypad8,
+		0x76 => Escape,
+		0x77 => NumLock,
+		0x78 => F11,
+		0x79 => KeypadPlus,
+		0x7A => Keypad3,
+		0x7B => KeypadMinus,
+		0x7C => KeypadStar,
+		0x7D => Keypad9,
+		0x7E => ScrollLock,
+		0x83 => F7,
+		_ => return None,
+	})
+}
+
+fn set2_extended(byte: u8) -> Option<KeyCode> {
+	use KeyCode::*;
+
+	// print screen's fake shift presses (0x12, 0x59) fall through to None
+	Some(match byte {
+		0x11 => RightAlt,
+		0x14 => RightCtrl,
+		0x1F => LeftMeta,
+		0x27 => RightMeta,
+		0x2F => Menu,
+		0x4A => KeypadSlash,
+		0x5A => KeypadEnter,
+		0x69 => End,
+		0x6B => Left,
+		0x6C => Home,
+		0x70 => Insert,
+		0x71 => Delete,
+		0x72 => Down,
+		0x74 => Right,
+		0x75 => Up,
+		0x7A => PageDown,
+		0x7C => PrintScreen,
+		0x7D => PageUp,
+		_ => return None,
+	})
+}
+
+/// Where the keyboard is in taking a new LED state. Setting the LEDs takes
+/// two bytes, each waiting for an ACK, and the interrupt handler moves this
+/// along as they arrive.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LedUpdate {
+	Idle,
+	SentCommand,
+	SentLeds(Leds),
+}
+
+struct Keyboard {
+	decoder: Decoder,
+	modifiers: Modifiers,
+	// lock keys being held down, so key repeat doesn't keep toggling them
+	locks_held: Modifiers,
+	led_update: LedUpdate,
+	// the locks changed again while the LEDs were being updated
+	leds_stale: bool,
+	device: Option<DeviceId>,
+}
+
+static KEYBOARD: Mutex<Keyboard> = Mutex::new(Keyboard {
+	decoder: Decoder::new(ScancodeSet::Set2),
+	modifiers: Modifiers::empty(),
+	locks_held: Modifiers::empty(),
+	led_update: LedUpdate::Idle,
+	leds_stale: false,
+	device: None,
+});
+
+impl Keyboard {
+	fn receive(&mut self, byte: u8) {
+		match (byte, self.led_update) {
+			(ACK, LedUpdate::SentCommand) => {
+				let leds = Leds::from_modifiers(self.modifiers);
+				self.led_update = LedUpdate::SentLeds(leds);
+				let _ = super::send(PORT, leds.bits());
+			}
+			(ACK, LedUpdate::SentLeds(_)) => {
+				self.led_update = LedUpdate::Idle;
+				if core::mem::take(&mut self.leds_stale) {
+					self.update_leds();
+				}
+			}
+			(RESEND, LedUpdate::SentCommand) => {
+				let _ = super::send(PORT, SET_LEDS);
+			}
+			(RESEND, LedUpdate::SentLeds(leds)) => {
+				let _ = super::send(PORT, leds.bits());
+			}
+			(ACK, LedUpdate::Idle) | (RESEND, LedUpdate::Idle) => {}
+			_ => {
+				if let Some((code, pressed)) = self.decoder.feed(byte) {
+					self.key(code, pressed);
+				}
+			}
+		}
+	}
+
+	fn key(&mut self, code: KeyCode, pressed: bool) {
+		let modifier = match code {
+			KeyCode::LeftShift => Modifiers::LEFT_SHIFT,
+			KeyCode::RightShift => Modifiers::RIGHT_SHIFT,
+			KeyCode::LeftCtrl => Modifiers::LEFT_CTRL,
+			KeyCode::RightCtrl => Modifiers::RIGHT_CTRL,
+			KeyCode::LeftAlt => Modifiers::ALT,
+			KeyCode::RightAlt => Modifiers::ALT_GR,
+			KeyCode::LeftMeta | KeyCode::RightMeta => Modifiers::META,
+			_ => Modifiers::empty(),
+		};
+		self.modifiers.set(modifier, pressed);
+
+		let lock = match code {
+			KeyCode::CapsLock => Modifiers::CAPS_LOCK,
+			KeyCode::NumLock => Modifiers::NUM_LOCK,
+			KeyCode::ScrollLock => Modifiers::SCROLL_LOCK,
+			_ => Modifiers::empty(),
+		};
+		if !lock.is_empty() {
+			if pressed && !self.locks_held.contains(lock) {
+				self.modifiers.toggle(lock);
+				self.update_leds();
+			}
+			self.locks_held.set(lock, pressed);
+		}
+
+		let device = match self.device {
+			Some(device) => device,
+			None => return,
+		};
+		let modifiers = self.modifiers;
+
+		input::publish(
+			device,
+			if pressed {
+				EventKind::KeyDown {
+					code,
+					modifiers,
+					c: keymap::current().translate(code, modifiers),
+				}
+			} else {
+				EventKind::KeyUp { code, modifiers }
+			},
+		);
+	}
+
+	/// Start sending the lock state to the keyboard's LEDs, or remember to
+	/// once the update in progress is done
+	fn update_leds(&mut self) {
+		if self.led_update == LedUpdate::Idle {
+			self.led_update = LedUpdate::SentCommand;
+			let _ = super::send(PORT, SET_LEDS);
+		} else {
+			self.leds_stale = true;
+		}
+	}
+}
+
+extern "x86-interrupt" fn interrupt(_frame: InterruptStackFrame) {
+	let byte = super::read_data_unchecked();
+	KEYBOARD.lock().receive(byte);
+
+	pic::eoi(PORT.irq());
+}
+
+/// Ask the keyboard which scancode set it's using
+fn scancode_set() -> Result<u8, Ps2Error> {
+	super::send_command(PORT, SCANCODE_SET)?;
+	super::send_command(PORT, 0)?;
+	super::read_data()
+}
+
+/// Reset the keyboard and get it sending scancodes in set 1 or 2, then
+/// start handling its interrupts. `ps2::init` has to have succeeded first.
+pub fn init() -> Result<ScancodeSet, Ps2Error> {
+	if !super::port_works(PORT) {
+		return Err(Ps2Error::NoDevice(PORT));
+	}
+
+	super::reset(PORT)?;
+
+	// set 2 is the default, but some keyboards come up in set 1 and not
+	// every one supports switching
+	let set = match scancode_set() {
+		Ok(1) => ScancodeSet::Set1,
+		Ok(2) => ScancodeSet::Set2,
+		_ => {
+			super::send_command(PORT, SCANCODE_SET)?;
+			super::send_command(PORT, 2)?;
+			ScancodeSet::Set2
+		}
+	};
+
+	let modifiers = Modifiers::empty();
+	super::send_command(PORT, SET_LEDS)?;
+	super::send_command(PORT, Leds::from_modifiers(modifiers).bits())?;
+	super::send_command(PORT, ENABLE_SCANNING)?;
+
+	let device = input::register("PS/2 keyboard", DeviceKind::Keyboard);
+	cpu::without_interrupts(|| {
+		let mut keyboard = KEYBOARD.lock();
+		keyboard.decoder = Decoder::new(set);
+		keyboard.modifiers = modifiers;
+		keyboard.device = device;
+	});
+
+	idt::set_handler(pic::vector(PORT.irq()), interrupt);
+	super::enable_interrupt(PORT)?;
+	pic::unmask(PORT.irq());
+
+	Ok(set)
+}
+
+/// Modifiers and locks as of the last key event
+pub fn modifiers() -> Modifiers {
+	cpu::without_interrupts(|| KEYBOARD.lock().modifiers)
+}
@@ -0,0 +1,312 @@
+//! 8042 PS/2 controller. Devices plugged into its two ports (normally a
+//! keyboard and a mouse) have their own drivers; this only sets up the
+//! controller and moves bytes between them and the CPU.
+
+pub mod keyboard;
+pub mod mouse;
+
+use crate::{
+	arch::{
+		cpu,
+		port::{inb, outb},
+	},
+	kwarn,
+};
+use bitflags::bitflags;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+const DATA: u16 = 0x60;
+// reads give the status register, writes send a controller command
+const STATUS: u16 = 0x64;
+const COMMAND: u16 = 0x64;
+
+const READ_CONFIG: u8 = 0x20;
+const WRITE_CONFIG: u8 = 0x60;
+const DISABLE_SECOND_PORT: u8 = 0xA7;
+const ENABLE_SECOND_PORT: u8 = 0xA8;
+const TEST_SECOND_PORT: u8 = 0xA9;
+const SELF_TEST: u8 = 0xAA;
+const TEST_FIRST_PORT: u8 = 0xAB;
+const DISABLE_FIRST_PORT: u8 = 0xAD;
+const ENABLE_FIRST_PORT: u8 = 0xAE;
+const WRITE_SECOND_PORT: u8 = 0xD4;
+/// Pulses the CPU's reset line, which reboots the machine
+const PULSE_RESET: u8 = 0xFE;
+
+const SELF_TEST_PASSED: u8 = 0x55;
+const PORT_TEST_PASSED: u8 = 0x00;
+
+/// Replies devices send to commands
+pub const ACK: u8 = 0xFA;
+/// The device didn't get the last byte right and wants it again
+pub const RESEND: u8 = 0xFE;
+
+// commands every device understands
+const RESET: u8 = 0xFF;
+/// Sent after a reset once the device has tested itself
+const DEVICE_SELF_TEST_PASSED: u8 = 0xAA;
+
+/// Status register polls before giving up on the controller
+const TIMEOUT: usize = 100_000;
+/// Times a device may ask for a command again before giving up on it
+const RETRIES: usize = 3;
+/// `read_data` timeouts to sit through while a device resets, which can take
+/// a good fraction of a second
+const RESET_ATTEMPTS: usize = 10;
+
+bitflags! {
+	struct Status: u8 {
+		const OUTPUT_FULL = 0b0000_0001;
+		const INPUT_FULL = 0b0000_0010;
+	}
+}
+
+bitflags! {
+	struct Config: u8 {
+		const FIRST_PORT_INTERRUPT = 0b0000_0001;
+		const SECOND_PORT_INTERRUPT = 0b0000_0010;
+		const FIRST_PORT_CLOCK_DISABLED = 0b0001_0000;
+		const SECOND_PORT_CLOCK_DISABLED = 0b0010_0000;
+		const FIRST_PORT_TRANSLATION = 0b0100_0000;
+	}
+}
+
+/// The controller's two device ports; the keyboard is usually on the first
+/// and the mouse on the second
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Port {
+	First,
+	Second,
+}
+
+impl Port {
+	/// IRQ line the device on this port raises
+	pub const fn irq(self) -> u8 {
+		match self {
+			Self::First => 1,
+			Self::Second => 12,
+		}
+	}
+}
+
+/// Why talking to the controller or a device failed
+#[derive(Debug)]
+pub enum Ps2Error {
+	/// The controller or a device didn't respond in time, which usually
+	/// means it isn't there
+	Timeout,
+	/// The controller's self-test returned this instead of 0x55
+	SelfTestFailed(u8),
+	/// There is nothing usable on this port
+	NoDevice(Port),
+	/// A device answered a command with this instead of ACK
+	UnexpectedReply(u8),
+}
+
+static PORT_WORKS: [AtomicBool; 2] =
+	[AtomicBool::new(false), AtomicBool::new(false)];
+
+fn status() -> Status {
+	Status::from_bits_truncate(unsafe { inb(STATUS) })
+}
+
+fn wait_until(f: impl Fn(Status) -> bool) -> Result<(), Ps2Error> {
+	for _ in 0..TIMEOUT {
+		if f(status()) {
+			return Ok(());
+		}
+		core::hint::spin_loop();
+	}
+
+	Err(Ps2Error::Timeout)
+}
+
+fn write_command(command: u8) -> Result<(), Ps2Error> {
+	wait_until(|status| !status.contains(Status::INPUT_FULL))?;
+	unsafe { outb(COMMAND, command) };
+	Ok(())
+}
+
+fn write_data(byte: u8) -> Result<(), Ps2Error> {
+	wait_until(|status| !status.contains(Status::INPUT_FULL))?;
+	unsafe { outb(DATA, byte) };
+	Ok(())
+}
+
+/// Wait for a byte from the controller or a device. Only for use while the
+/// device's interrupt is off, or the handler would race for it.
+pub fn read_data() -> Result<u8, Ps2Error> {
+	for _ in 0..TIMEOUT {
+		// checked and taken in one go, so another port's interrupt handler
+		// can't grab the byte in between
+		let byte = cpu::without_interrupts(|| {
+			if status().contains(Status::OUTPUT_FULL) {
+				Some(unsafe { inb(DATA) })
+			} else {
+				None
+			}
+		});
+		if let Some(byte) = byte {
+			return Ok(byte);
+		}
+		core::hint::spin_loop();
+	}
+
+	Err(Ps2Error::Timeout)
+}
+
+/// Take whatever byte is waiting, for interrupt handlers (which know there
+/// is one)
+pub fn read_data_unchecked() -> u8 {
+	unsafe { inb(DATA) }
+}
+
+fn read_config() -> Result<Config, Ps2Error> {
+	write_command(READ_CONFIG)?;
+	Ok(Config::from_bits_truncate(read_data()?))
+}
+
+fn write_config(config: Config) -> Result<(), Ps2Error> {
+	write_command(WRITE_CONFIG)?;
+	write_data(config.bits())
+}
+
+/// Send a byte to the device on `port` without waiting for a reply
+pub fn send(port: Port, byte: u8) -> Result<(), Ps2Error> {
+	// the keyboard's handler sends too, and mustn't get its byte in between
+	// the second port's prefix and the byte it's for
+	cpu::without_interrupts(|| {
+		if port == Port::Second {
+			write_command(WRITE_SECOND_PORT)?;
+		}
+		write_data(byte)
+	})
+}
+
+/// Send a command byte to the device on `port` and wait for it to be
+/// acknowledged, sending it again if the device asks
+pub fn send_command(port: Port, byte: u8) -> Result<(), Ps2Error> {
+	for _ in 0..RETRIES {
+		send(port, byte)?;
+
+		match read_data()? {
+			ACK => return Ok(()),
+			RESEND => continue,
+			reply => return Err(Ps2Error::UnexpectedReply(reply)),
+		}
+	}
+
+	Err(Ps2Error::UnexpectedReply(RESEND))
+}
+
+/// Reset the device on `port` and wait for it to pass its self-test. Mice
+/// send their ID right after, which is left for the caller to read.
+pub fn reset(port: Port) -> Result<(), Ps2Error> {
+	send_command(port, RESET)?;
+
+	let mut reply = Err(Ps2Error::Timeout);
+	for _ in 0..RESET_ATTEMPTS {
+		reply = read_data();
+		if reply.is_ok() {
+			break;
+		}
+	}
+
+	match reply? {
+		DEVICE_SELF_TEST_PASSED => Ok(()),
+		reply => Err(Ps2Error::UnexpectedReply(reply)),
+	}
+}
+
+/// Reboot the machine through the controller. Only returns if the
+/// controller never takes the command.
+pub fn pulse_reset() -> Result<(), Ps2Error> {
+	write_command(PULSE_RESET)
+}
+
+/// Whether the controller found a working port there during `init`
+pub fn port_works(port: Port) -> bool {
+	PORT_WORKS[port as usize].load(Ordering::SeqCst)
+}
+
+/// Have the controller raise the port's IRQ whenever its device sends
+/// something
+pub fn enable_interrupt(port: Port) -> Result<(), Ps2Error> {
+	let mut config = read_config()?;
+	config.insert(match port {
+		Port::First => Config::FIRST_PORT_INTERRUPT,
+		Port::Second => Config::SECOND_PORT_INTERRUPT,
+	});
+	write_config(config)
+}
+
+/// Reset and self-test the controller, then test and enable both ports.
+/// Interrupts stay off until each device's driver turns them on, and
+/// scancode translation is disabled so keyboards speak their native set.
+pub fn init() -> Result<(), Ps2Error> {
+	// nothing drives the bus at all without a controller
+	if unsafe { inb(STATUS) } == 0xFF {
+		return Err(Ps2Error::Timeout);
+	}
+
+	write_command(DISABLE_FIRST_PORT)?;
+	write_command(DISABLE_SECOND_PORT)?;
+
+	// throw away anything a device sent before now
+	while status().contains(Status::OUTPUT_FULL) {
+		read_data_unchecked();
+	}
+
+	let mut config = read_config()?;
+	config.remove(
+		Config::FIRST_PORT_INTERRUPT
+			| Config::SECOND_PORT_INTERRUPT
+			| Config::FIRST_PORT_TRANSLATION,
+	);
+	// with the second port disabled its clock should be off, unless there is
+	// no second port
+	let mut dual = config.contains(Config::SECOND_PORT_CLOCK_DISABLED);
+	write_config(config)?;
+
+	write_command(SELF_TEST)?;
+	match read_data()? {
+		SELF_TEST_PASSED => {}
+		result => return Err(Ps2Error::SelfTestFailed(result)),
+	}
+	// the self-test can reset the controller
+	write_config(config)?;
+
+	if dual {
+		write_command(ENABLE_SECOND_PORT)?;
+		dual = !read_config()?.contains(Config::SECOND_PORT_CLOCK_DISABLED);
+		write_command(DISABLE_SECOND_PORT)?;
+	}
+
+	let mut any = false;
+	for &(port, test, enable) in &[
+		(Port::First, TEST_FIRST_PORT, ENABLE_FIRST_PORT),
+		(Port::Second, TEST_SECOND_PORT, ENABLE_SECOND_PORT),
+	] {
+		if port == Port::Second && !dual {
+			continue;
+		}
+
+		write_command(test)?;
+		let result = read_data()?;
+		let works = result == PORT_TEST_PASSED;
+		if works {
+			write_command(enable)?;
+			any = true;
+		} else {
+			kwarn!("PS/2 {:?} port failed its test ({:#x})", port, result);
+		}
+
+		PORT_WORKS[port as usize].store(works, Ordering::SeqCst);
+	}
+
+	if any {
+		Ok(())
+	} else {
+		Err(Ps2Error::NoDevice(Port::First))
+	}
+}
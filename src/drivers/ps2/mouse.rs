@@ -0,0 +1,226 @@
+//! Mouse on the second PS/2 port. Wheel mice are switched into IntelliMouse
+//! mode, which makes their packets 4 bytes instead of 3. Every complete
+//! packet is published as motion, wheel and button input events.
+
+use super::{Port, Ps2Error};
+use crate::{
+	arch::{
+		cpu,
+		idt::{self, InterruptStackFrame},
+		pic,
+	},
+	input::{self, DeviceId, DeviceKind, EventKind, MouseButton},
+};
+use bitflags::bitflags;
+use spin::Mutex;
+
+const PORT: Port = Port::Second;
+
+// device commands
+const SET_SAMPLE_RATE: u8 = 0xF3;
+const GET_ID: u8 = 0xF2;
+const ENABLE_REPORTING: u8 = 0xF4;
+
+/// Sample rates that, set in this order, turn on the scroll wheel
+const INTELLIMOUSE_KNOCK: [u8; 3] = [200, 100, 80];
+/// ID a mouse reports once the scroll wheel is on
+const INTELLIMOUSE_ID: u8 = 3;
+
+bitflags! {
+	struct Buttons: u8 {
+		const LEFT = 0b001;
+		const RIGHT = 0b010;
+		const MIDDLE = 0b100;
+	}
+}
+
+bitflags! {
+	/// First byte of every packet
+	struct PacketFlags: u8 {
+		const BUTTONS = 0b0000_0111;
+		/// Always set, which is how the start of a packet is found again
+		const ALWAYS_ONE = 0b0000_1000;
+		const X_NEGATIVE = 0b0001_0000;
+		const Y_NEGATIVE = 0b0010_0000;
+		const X_OVERFLOW = 0b0100_0000;
+		const Y_OVERFLOW = 0b1000_0000;
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseType {
+	Standard,
+	/// IntelliMouse compatible, with a scroll wheel
+	Wheel,
+}
+
+struct Mouse {
+	packet: [u8; 4],
+	len: usize,
+	packet_size: usize,
+	// as of the last packet, to tell which ones changed
+	buttons: Buttons,
+	device: Option<DeviceId>,
+}
+
+static MOUSE: Mutex<Mouse> = Mutex::new(Mouse {
+	packet: [0; 4],
+	len: 0,
+	packet_size: 3,
+	buttons: Buttons::empty(),
+	device: None,
+});
+
+impl Mouse {
+	fn receive(&mut self, byte: u8) {
+		// a dropped byte would shift every packet after it, so wait for
+		// something that can start one
+		if self.len == 0 && byte & PacketFlags::ALWAYS_ONE.bits() == 0 {
+			return;
+		}
+
+		self.packet[self.len] = byte;
+		self.len += 1;
+
+		if self.len == self.packet_size {
+			self.len = 0;
+			self.decode();
+		}
+	}
+
+	/// Publish whatever changed according to the packet just received
+	fn decode(&mut self) {
+		let device = match self.device {
+			Some(device) => device,
+			None => return,
+		};
+
+		let flags = PacketFlags::from_bits_truncate(self.packet[0]);
+
+		// movement is 9 bit two's complement, with the sign bits in the flags.
+		// Overflowed movement is garbage, so it's thrown away.
+		let axis = |value: u8, negative, overflow| {
+			if flags.contains(overflow) {
+				0
+			} else if flags.contains(negative) {
+				value as i16 - 0x100
+			} else {
+				value as i16
+			}
+		};
+		let dx = axis(
+			self.packet[1],
+			PacketFlags::X_NEGATIVE,
+			PacketFlags::X_OVERFLOW,
+		);
+		let dy = axis(
+			self.packet[2],
+			PacketFlags::Y_NEGATIVE,
+			PacketFlags::Y_OVERFLOW,
+		);
+
+		// the wheel is a 4 bit signed value in the last byte
+		let wheel = if self.packet_size == 4 {
+			((self.packet[3] << 4) as i8) >> 4
+		} else {
+			0
+		};
+
+		if dx != 0 || dy != 0 {
+			// the mouse counts up as positive, the screen counts down
+			input::publish(
+				device,
+				EventKind::RelativeMotion {
+					dx: dx.into(),
+					dy: (-dy).into(),
+				},
+			);
+		}
+		if wheel != 0 {
+			input::publish(device, EventKind::Wheel(wheel.into()));
+		}
+
+		let buttons =
+			Buttons::from_bits_truncate((flags & PacketFlags::BUTTONS).bits());
+		for &(flag, button) in &[
+			(Buttons::LEFT, MouseButton::Left),
+			(Buttons::RIGHT, MouseButton::Right),
+			(Buttons::MIDDLE, MouseButton::Middle),
+		] {
+			if (buttons ^ self.buttons).contains(flag) {
+				input::publish(
+					device,
+					EventKind::Button {
+						button,
+						pressed: buttons.contains(flag),
+					},
+				);
+			}
+		}
+		self.buttons = buttons;
+	}
+}
+
+extern "x86-interrupt" fn interrupt(_frame: InterruptStackFrame) {
+	let byte = super::read_data_unchecked();
+	MOUSE.lock().receive(byte);
+
+	pic::eoi(PORT.irq());
+}
+
+fn set_sample_rate(rate: u8) -> Result<(), Ps2Error> {
+	super::send_command(PORT, SET_SAMPLE_RATE)?;
+	super::send_command(PORT, rate)
+}
+
+fn device_id() -> Result<u8, Ps2Error> {
+	super::send_command(PORT, GET_ID)?;
+	super::read_data()
+}
+
+/// Reset the mouse and get it reporting
+fn configure() -> Result<MouseType, Ps2Error> {
+	super::reset(PORT)?;
+	// the ID that follows the self-test result
+	super::read_data()?;
+
+	for &rate in &INTELLIMOUSE_KNOCK {
+		set_sample_rate(rate)?;
+	}
+	let kind = if device_id()? == INTELLIMOUSE_ID {
+		MouseType::Wheel
+	} else {
+		MouseType::Standard
+	};
+
+	let device = input::register("PS/2 mouse", DeviceKind::Mouse);
+	cpu::without_interrupts(|| {
+		let mut mouse = MOUSE.lock();
+		mouse.len = 0;
+		mouse.buttons = Buttons::empty();
+		mouse.device = device;
+		mouse.packet_size = match kind {
+			MouseType::Standard => 3,
+			MouseType::Wheel => 4,
+		};
+	});
+
+	super::send_command(PORT, ENABLE_REPORTING)?;
+	Ok(kind)
+}
+
+/// Reset the mouse, turn on its scroll wheel if it has one, and start
+/// handling its interrupts. `ps2::init` has to have succeeded first.
+pub fn init() -> Result<MouseType, Ps2Error> {
+	if !super::port_works(PORT) {
+		return Err(Ps2Error::NoDevice(PORT));
+	}
+
+	let kind = configure()?;
+
+	idt::set_handler(pic::vector(PORT.irq()), interrupt);
+	super::enable_interrupt(PORT)?;
+	pic::unmask(PORT.irq());
+
+	Ok(kind)
+}
@@ -0,0 +1,93 @@
+//! RAM disk backed by a boot module, so there's something to put a
+//! filesystem on without a real disk driver. Writes change the module in
+//! memory and are gone after a reboot.
+
+use crate::{
+	block::{self, BlockDevice, BlockError, DeviceId},
+	boot,
+};
+use spin::Mutex;
+
+/// String the module has to be given in limine.cfg
+pub const MODULE: &str = "ramdisk";
+const SECTOR_SIZE: usize = 512;
+
+#[derive(Debug)]
+pub enum RamDiskError {
+	/// The bootloader didn't load a module called `MODULE`
+	NoModule,
+	/// The module is smaller than a sector
+	TooSmall,
+	/// There's no room to register another block device
+	TooManyDevices,
+}
+
+pub struct RamDisk {
+	data: Mutex<Option<&'static mut [u8]>>,
+}
+
+static RAMDISK: RamDisk = RamDisk {
+	data: Mutex::new(None),
+};
+
+impl RamDisk {
+	/// Byte range `len` bytes at `sector` cover, after checking they exist
+	fn range(
+		&self,
+		sector: u64,
+		len: usize,
+	) -> Result<core::ops::Range<usize>, BlockError> {
+		block::check_range(self, sector, len)?;
+		let start = sector as usize * SECTOR_SIZE;
+		Ok(start..start + len)
+	}
+}
+
+impl BlockDevice for RamDisk {
+	fn name(&self) -> &str {
+		"ram0"
+	}
+
+	fn sector_size(&self) -> usize {
+		SECTOR_SIZE
+	}
+
+	fn sectors(&self) -> u64 {
+		self.data
+			.lock()
+			.as_ref()
+			.map_or(0, |data| (data.len() / SECTOR_SIZE) as u64)
+	}
+
+	fn read(&self, sector: u64, buf: &mut [u8]) -> Result<(), BlockError> {
+		let range = self.range(sector, buf.len())?;
+		let data = self.data.lock();
+		let data = data.as_ref().ok_or(BlockError::NoDevice)?;
+
+		buf.copy_from_slice(&data[range]);
+		Ok(())
+	}
+
+	fn write(&self, sector: u64, buf: &[u8]) -> Result<(), BlockError> {
+		let range = self.range(sector, buf.len())?;
+		let mut data = self.data.lock();
+		let data = data.as_mut().ok_or(BlockError::NoDevice)?;
+
+		data[range].copy_from_slice(buf);
+		Ok(())
+	}
+}
+
+/// Turn the `ramdisk` boot module into a block device. Any bytes past the
+/// last whole sector are ignored.
+pub fn init() -> Result<DeviceId, RamDiskError> {
+	// SAFETY: nothing else looks up this module
+	let data =
+		unsafe { boot::module_mut(MODULE) }.ok_or(RamDiskError::NoModule)?;
+	if data.len() < SECTOR_SIZE {
+		return Err(RamDiskError::TooSmall);
+	}
+
+	*RAMDISK.data.lock() = Some(data);
+	block::register(&RAMDISK).ok_or(RamDiskError::TooManyDevices)
+}
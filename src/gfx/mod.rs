@@ -0,0 +1,423 @@
+//! 2D drawing on the framebuffer, for anything that isn't text. Everything is
+//! clipped to the painter's clip rectangle (the whole screen unless told
+//! otherwise), so coordinates are signed and shapes can hang off the edges.
+
+pub mod pointer;
+pub mod qoi;
+pub mod splash;
+
+use crate::stdio::framebuffer::{Framebuffer, Pixel, Rect};
+
+/// Draws on a locked framebuffer, flushing when dropped
+pub struct Painter<'a> {
+	framebuffer: &'a mut Framebuffer,
+	clip: Rect,
+}
+
+/// Mix `over` into `under`, `alpha` being how much of `over` shows
+fn blend(under: Pixel, over: Pixel, alpha: u8) -> Pixel {
+	let mix = |under: u8, over: u8| {
+		let alpha = alpha as u32;
+		((over as u32 * alpha + under as u32 * (255 - alpha)) / 255) as u8
+	};
+	let (under, over) = (under.channels(), over.channels());
+
+	Pixel::new(
+		mix(under.0, over.0),
+		mix(under.1, over.1),
+		mix(under.2, over.2),
+	)
+}
+
+impl<'a> Painter<'a> {
+	pub fn new(framebuffer: &'a mut Framebuffer) -> Self {
+		let mut painter = Self {
+			framebuffer,
+			clip: Rect {
+				x0: 0,
+				y0: 0,
+				x1: 0,
+				y1: 0,
+			},
+		};
+
+		painter.reset_clip();
+		painter
+	}
+
+	/// Size of the screen in pixels, as (width, height)
+	pub fn resolution(&self) -> (i32, i32) {
+		let (width, height) = self.framebuffer.resolution();
+		(width as i32, height as i32)
+	}
+
+	/// Only draw inside the given rectangle from now on
+	pub fn set_clip(&mut self, x: i32, y: i32, width: i32, height: i32) {
+		self.reset_clip();
+		self.clip = self.clip_rect(x, y, width, height).unwrap_or(Rect {
+			x0: 0,
+			y0: 0,
+			x1: 0,
+			y1: 0,
+		});
+	}
+
+	/// Allow drawing anywhere on screen again
+	pub fn reset_clip(&mut self) {
+		let (width, height) = self.framebuffer.resolution();
+
+		self.clip = Rect {
+			x0: 0,
+			y0: 0,
+			x1: width as usize,
+			y1: height as usize,
+		};
+	}
+
+	/// The part of a rectangle inside the clip rectangle, if there's any
+	fn clip_rect(
+		&self,
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+	) -> Option<Rect> {
+		let clamp = |value: i32, min: usize, max: usize| {
+			(value.max(0) as usize).max(min).min(max)
+		};
+
+		let rect = Rect {
+			x0: clamp(x, self.clip.x0, self.clip.x1),
+			y0: clamp(y, self.clip.y0, self.clip.y1),
+			x1: clamp(x.saturating_add(width), self.clip.x0, self.clip.x1),
+			y1: clamp(y.saturating_add(height), self.clip.y0, self.clip.y1),
+		};
+
+		if rect.x0 < rect.x1 && rect.y0 < rect.y1 {
+			Some(rect)
+		} else {
+			None
+		}
+	}
+
+	pub fn pixel(&mut self, x: i32, y: i32, color: Pixel) {
+		if let Some(rect) = self.clip_rect(x, y, 1, 1) {
+			self.framebuffer.put_pixel(rect.x0, rect.y0, color);
+			self.framebuffer.mark_dirty(rect.x0, rect.y0, 1, 1);
+		}
+	}
+
+	pub fn fill_rect(
+		&mut self,
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+		color: Pixel,
+	) {
+		if let Some(rect) = self.clip_rect(x, y, width, height) {
+			self.framebuffer.fill_rect(
+				rect.x0,
+				rect.y0,
+				rect.x1 - rect.x0,
+				rect.y1 - rect.y0,
+				color,
+			);
+		}
+	}
+
+	/// One pixel wide outline of a rectangle
+	pub fn rect(
+		&mut self,
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+		color: Pixel,
+	) {
+		if width <= 0 || height <= 0 {
+			return;
+		}
+
+		self.fill_rect(x, y, width, 1, color);
+		self.fill_rect(x, y + height - 1, width, 1, color);
+		self.fill_rect(x, y + 1, 1, height - 2, color);
+		self.fill_rect(x + width - 1, y + 1, 1, height - 2, color);
+	}
+
+	/// The part of the line from (`x0`, `y0`) to (`x1`, `y1`) inside the clip
+	/// rectangle, if there's any, using Cohen-Sutherland
+	fn clip_line(
+		&self,
+		(mut x0, mut y0): (i64, i64),
+		(mut x1, mut y1): (i64, i64),
+	) -> Option<((i64, i64), (i64, i64))> {
+		const LEFT: u8 = 1;
+		const RIGHT: u8 = 2;
+		const TOP: u8 = 4;
+		const BOTTOM: u8 = 8;
+
+		if self.clip.x0 >= self.clip.x1 || self.clip.y0 >= self.clip.y1 {
+			return None;
+		}
+		let (left, right) = (self.clip.x0 as i64, self.clip.x1 as i64 - 1);
+		let (top, bottom) = (self.clip.y0 as i64, self.clip.y1 as i64 - 1);
+
+		let outcode = |x: i64, y: i64| {
+			let mut code = 0;
+			if x < left {
+				code |= LEFT;
+			} else if x > right {
+				code |= RIGHT;
+			}
+			if y < top {
+				code |= TOP;
+			} else if y > bottom {
+				code |= BOTTOM;
+			}
+			code
+		};
+		// where the line crosses `at` on one axis, given the other axis's
+		// coordinates of both ends. The product needs more than 64 bits.
+		let cross = |a0: i64, a1: i64, b0: i64, b1: i64, at: i64| {
+			a0 + ((a1 - a0) as i128 * (at - b0) as i128 / (b1 - b0) as i128)
+				as i64
+		};
+
+		// rounding can leave a moved end just outside, so give up on lines
+		// that keep bouncing rather than loop forever
+		for _ in 0..8 {
+			let (code0, code1) = (outcode(x0, y0), outcode(x1, y1));
+			if code0 | code1 == 0 {
+				return Some(((x0, y0), (x1, y1)));
+			}
+			if code0 & code1 != 0 {
+				return None;
+			}
+
+			let code = if code0 != 0 { code0 } else { code1 };
+			let (x, y) = if code & TOP != 0 {
+				(cross(x0, x1, y0, y1, top), top)
+			} else if code & BOTTOM != 0 {
+				(cross(x0, x1, y0, y1, bottom), bottom)
+			} else if code & LEFT != 0 {
+				(left, cross(y0, y1, x0, x1, left))
+			} else {
+				(right, cross(y0, y1, x0, x1, right))
+			};
+
+			if code == code0 {
+				x0 = x;
+				y0 = y;
+			} else {
+				x1 = x;
+				y1 = y;
+			}
+		}
+		None
+	}
+
+	/// Straight line from (`x0`, `y0`) to (`x1`, `y1`), both ends included
+	pub fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: Pixel) {
+		// clipping first means only pixels on screen are walked, and working
+		// in i64 keeps the deltas of far apart ends from overflowing
+		let (start, end) = ((x0 as i64, y0 as i64), (x1 as i64, y1 as i64));
+		let ((x0, y0), (x1, y1)) = match self.clip_line(start, end) {
+			Some(ends) => ends,
+			None => return,
+		};
+
+		// Bresenham, with the error term covering both directions so it works
+		// in every octant
+		let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+		let (step_x, step_y) = ((x1 - x0).signum(), (y1 - y0).signum());
+		let (mut x, mut y) = (x0, y0);
+		let mut error = dx + dy;
+
+		loop {
+			self.pixel(x as i32, y as i32, color);
+
+			if x == x1 && y == y1 {
+				break;
+			}
+
+			let doubled = 2 * error;
+			if doubled >= dy {
+				error += dy;
+				x += step_x;
+			}
+			if doubled <= dx {
+				error += dx;
+				y += step_y;
+			}
+		}
+	}
+
+	/// Walk one octant of a circle with the midpoint algorithm, handing every
+	/// (x, y) offset from the center to `f`
+	fn circle_points(radius: i32, mut f: impl FnMut(i32, i32)) {
+		let (mut x, mut y) = (radius, 0);
+		// the error grows with the square of the radius
+		let mut error = 1 - radius as i64;
+
+		while x >= y {
+			f(x, y);
+
+			y += 1;
+			if error < 0 {
+				error += 2 * y as i64 + 1;
+			} else {
+				x -= 1;
+				error += 2 * (y as i64 - x as i64) + 1;
+			}
+		}
+	}
+
+	/// One pixel wide outline of a circle
+	pub fn circle(&mut self, cx: i32, cy: i32, radius: i32, color: Pixel) {
+		if radius < 0 {
+			return;
+		}
+
+		Self::circle_points(radius, |x, y| {
+			for &(px, py) in &[
+				(x, y),
+				(y, x),
+				(-y, x),
+				(-x, y),
+				(-x, -y),
+				(-y, -x),
+				(y, -x),
+				(x, -y),
+			] {
+				self.pixel(cx.saturating_add(px), cy.saturating_add(py), color);
+			}
+		});
+	}
+
+	pub fn fill_circle(&mut self, cx: i32, cy: i32, radius: i32, color: Pixel) {
+		if radius < 0 {
+			return;
+		}
+
+		// every octant point gives the ends of a horizontal span. Big circles
+		// just get clipped, so the arithmetic saturates rather than wrapping
+		let span = |offset: i32| offset.saturating_mul(2).saturating_add(1);
+		Self::circle_points(radius, |x, y| {
+			let (left, width) = (cx.saturating_sub(x), span(x));
+			self.fill_rect(left, cy.saturating_add(y), width, 1, color);
+			self.fill_rect(left, cy.saturating_sub(y), width, 1, color);
+
+			let (left, width) = (cx.saturating_sub(y), span(y));
+			self.fill_rect(left, cy.saturating_add(x), width, 1, color);
+			self.fill_rect(left, cy.saturating_sub(x), width, 1, color);
+		});
+	}
+
+	/// Copy an image onto the screen with its top left corner at (`x`, `y`).
+	/// `rgba` holds `width` x `height` pixels, 4 bytes each, row by row; the
+	/// alpha byte blends it with what's already there.
+	pub fn blit(
+		&mut self,
+		x: i32,
+		y: i32,
+		width: i32,
+		height: i32,
+		rgba: &[u8],
+	) {
+		assert!(
+			rgba.len() >= width.max(0) as usize * height.max(0) as usize * 4,
+			"Image is smaller than its dimensions!"
+		);
+
+		let rect = match self.clip_rect(x, y, width, height) {
+			Some(rect) => rect,
+			None => return,
+		};
+
+		for screen_y in rect.y0..rect.y1 {
+			let image_y = (screen_y as i32 - y) as usize;
+
+			for screen_x in rect.x0..rect.x1 {
+				let image_x = (screen_x as i32 - x) as usize;
+				let offset = (image_y * width as usize + image_x) * 4;
+				self.put_rgba(
+					screen_x,
+					screen_y,
+					[
+						rgba[offset],
+						rgba[offset + 1],
+						rgba[offset + 2],
+						rgba[offset + 3],
+					],
+				);
+			}
+		}
+
+		self.framebuffer.mark_dirty(
+			rect.x0,
+			rect.y0,
+			rect.x1 - rect.x0,
+			rect.y1 - rect.y0,
+		);
+	}
+
+	/// Like `blit`, but for images that come one pixel at a time (left to
+	/// right, top to bottom), such as ones being decoded on the fly
+	pub fn blit_pixels(
+		&mut self,
+		x: i32,
+		y: i32,
+		width: i32,
+		pixels: impl IntoIterator<Item = [u8; 4]>,
+	) {
+		if width <= 0 {
+			return;
+		}
+
+		let mut bounds: Option<Rect> = None;
+
+		for (i, rgba) in pixels.into_iter().enumerate() {
+			let (image_x, image_y) = (i as i32 % width, i as i32 / width);
+
+			if let Some(rect) = self.clip_rect(x + image_x, y + image_y, 1, 1) {
+				self.put_rgba(rect.x0, rect.y0, rgba);
+				bounds = Some(match bounds {
+					Some(bounds) => bounds.union(rect),
+					None => rect,
+				});
+			}
+		}
+
+		if let Some(rect) = bounds {
+			self.framebuffer.mark_dirty(
+				rect.x0,
+				rect.y0,
+				rect.x1 - rect.x0,
+				rect.y1 - rect.y0,
+			);
+		}
+	}
+
+	/// Set an on-screen pixel, blending by the alpha byte. Marking it dirty is
+	/// up to the caller.
+	fn put_rgba(&mut self, x: usize, y: usize, [r, g, b, alpha]: [u8; 4]) {
+		let color = Pixel::new(r, g, b);
+
+		let color = match alpha {
+			0 => return,
+			255 => color,
+			_ => match self.framebuffer.get_pixel(x, y) {
+				Some(under) => blend(under, color, alpha),
+				None => return,
+			},
+		};
+		self.framebuffer.put_pixel(x, y, color);
+	}
+}
+
+impl Drop for Painter<'_> {
+	fn drop(&mut self) {
+		self.framebuffer.flush();
+	}
+}
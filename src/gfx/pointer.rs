@@ -0,0 +1,67 @@
+//! Mouse pointer, laid over the screen as a framebuffer sprite so it never
+//! has to know what's drawn under it
+
+use crate::{
+	arch::cpu,
+	stdio::framebuffer::{Pixel, Sprite, FRAMEBUFFER},
+};
+
+const T: Option<Pixel> = None;
+const B: Option<Pixel> = Some(Pixel::new(0, 0, 0));
+const W: Option<Pixel> = Some(Pixel::new(255, 255, 255));
+
+#[rustfmt::skip]
+static ARROW_PIXELS: [Option<Pixel>; 11 * 17] = [
+	B, T, T, T, T, T, T, T, T, T, T,
+	B, B, T, T, T, T, T, T, T, T, T,
+	B, W, B, T, T, T, T, T, T, T, T,
+	B, W, W, B, T, T, T, T, T, T, T,
+	B, W, W, W, B, T, T, T, T, T, T,
+	B, W, W, W, W, B, T, T, T, T, T,
+	B, W, W, W, W, W, B, T, T, T, T,
+	B, W, W, W, W, W, W, B, T, T, T,
+	B, W, W, W, W, W, W, W, B, T, T,
+	B, W, W, W, W, W, W, W, W, B, T,
+	B, W, W, W, W, W, B, B, B, B, B,
+	B, W, W, B, W, W, B, T, T, T, T,
+	B, W, B, T, B, W, W, B, T, T, T,
+	B, B, T, T, B, W, W, B, T, T, T,
+	B, T, T, T, T, B, W, W, B, T, T,
+	T, T, T, T, T, B, W, W, B, T, T,
+	T, T, T, T, T, T, B, B, T, T, T,
+];
+
+/// The usual arrow, with its tip in the top left corner
+pub static ARROW: Sprite = Sprite {
+	width: 11,
+	height: 17,
+	pixels: &ARROW_PIXELS,
+};
+
+/// Put the pointer in the middle of the screen
+pub fn show() {
+	cpu::without_interrupts(|| {
+		let mut framebuffer = FRAMEBUFFER.lock();
+		let (width, height) = framebuffer.resolution();
+
+		framebuffer.set_sprite(Some(&ARROW));
+		framebuffer.move_sprite(width as usize / 2, height as usize / 2);
+		framebuffer.flush();
+	});
+}
+
+/// Move the pointer by some mouse movement, keeping its tip on screen
+pub fn move_by(dx: i32, dy: i32) {
+	let clamp = |value: usize, delta: i32, max: u16| {
+		(value as i32 + delta).max(0).min(max as i32 - 1) as usize
+	};
+
+	cpu::without_interrupts(|| {
+		let mut framebuffer = FRAMEBUFFER.lock();
+		let (width, height) = framebuffer.resolution();
+		let (x, y) = framebuffer.sprite_position();
+
+		framebuffer.move_sprite(clamp(x, dx, width), clamp(y, dy, height));
+		framebuffer.flush();
+	});
+}
@@ -0,0 +1,175 @@
+//! Decoder for QOI ("Quite OK Image") files. Pixels come out one at a time
+//! as they're decoded, so nothing has to be allocated for the image.
+
+const MAGIC: &[u8; 4] = b"qoif";
+const HEADER_SIZE: usize = 14;
+const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+
+const OP_RGB: u8 = 0xFE;
+const OP_RGBA: u8 = 0xFF;
+const OP_INDEX: u8 = 0b00;
+const OP_DIFF: u8 = 0b01;
+const OP_LUMA: u8 = 0b10;
+const OP_RUN: u8 = 0b11;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error {
+	BadMagic,
+	Truncated,
+	/// Width or height is zero, or the image couldn't possibly fit in the
+	/// data given
+	BadDimensions,
+}
+
+/// A QOI file that's been checked enough to start decoding
+#[derive(Clone, Copy)]
+pub struct Image<'a> {
+	pub width: u32,
+	pub height: u32,
+	data: &'a [u8],
+}
+
+impl<'a> Image<'a> {
+	pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+		if data.len() < HEADER_SIZE + END_MARKER.len() {
+			return Err(Error::Truncated);
+		}
+		if &data[..4] != MAGIC {
+			return Err(Error::BadMagic);
+		}
+
+		let read_u32 = |offset: usize| {
+			u32::from_be_bytes([
+				data[offset],
+				data[offset + 1],
+				data[offset + 2],
+				data[offset + 3],
+			])
+		};
+		let (width, height) = (read_u32(4), read_u32(8));
+
+		// every op covers at most 62 pixels, which bounds how big the image
+		// can be without trusting the header
+		let max_pixels = (data.len() - HEADER_SIZE) as u64 * 62;
+		if width == 0
+			|| height == 0
+			|| width as u64 * height as u64 > max_pixels
+		{
+			return Err(Error::BadDimensions);
+		}
+
+		Ok(Self {
+			width,
+			height,
+			data: &data[HEADER_SIZE..],
+		})
+	}
+
+	/// Every pixel as RGBA, left to right, top to bottom. Corrupt data ends
+	/// the image early.
+	pub fn pixels(&self) -> Pixels<'a> {
+		Pixels {
+			data: self.data,
+			pos: 0,
+			index: [[0; 4]; 64],
+			pixel: [0, 0, 0, 255],
+			run: 0,
+			remaining: self.width as usize * self.height as usize,
+		}
+	}
+}
+
+/// Iterator over a QOI image's pixels, from `Image::pixels`
+pub struct Pixels<'a> {
+	data: &'a [u8],
+	pos: usize,
+	// previously seen pixels, by hash
+	index: [[u8; 4]; 64],
+	pixel: [u8; 4],
+	// repeats of `pixel` still to hand out
+	run: u8,
+	remaining: usize,
+}
+
+const fn hash([r, g, b, a]: [u8; 4]) -> usize {
+	(r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+impl Pixels<'_> {
+	fn byte(&mut self) -> Option<u8> {
+		let byte = *self.data.get(self.pos)?;
+		self.pos += 1;
+		Some(byte)
+	}
+
+	/// Work out the next pixel from the data
+	fn decode(&mut self) -> Option<[u8; 4]> {
+		let op = self.byte()?;
+		let [r, g, b, a] = self.pixel;
+
+		let pixel = match op {
+			OP_RGB => [self.byte()?, self.byte()?, self.byte()?, a],
+			OP_RGBA => [self.byte()?, self.byte()?, self.byte()?, self.byte()?],
+			_ => match op >> 6 {
+				OP_INDEX => self.index[op as usize & 0x3F],
+				OP_DIFF => [
+					r.wrapping_add((op >> 4) & 0x03).wrapping_sub(2),
+					g.wrapping_add((op >> 2) & 0x03).wrapping_sub(2),
+					b.wrapping_add(op & 0x03).wrapping_sub(2),
+					a,
+				],
+				OP_LUMA => {
+					let dg = (op & 0x3F).wrapping_sub(32);
+					let rest = self.byte()?;
+					let dr = dg.wrapping_add(rest >> 4).wrapping_sub(8);
+					let db = dg.wrapping_add(rest & 0x0F).wrapping_sub(8);
+
+					[
+						r.wrapping_add(dr),
+						g.wrapping_add(dg),
+						b.wrapping_add(db),
+						a,
+					]
+				}
+				OP_RUN => {
+					// this pixel is the first of the run
+					self.run = op & 0x3F;
+					self.pixel
+				}
+				_ => unreachable!(),
+			},
+		};
+
+		self.index[hash(pixel)] = pixel;
+		Some(pixel)
+	}
+}
+
+impl Iterator for Pixels<'_> {
+	type Item = [u8; 4];
+
+	fn next(&mut self) -> Option<[u8; 4]> {
+		if self.remaining == 0 {
+			return None;
+		}
+
+		if self.run > 0 {
+			self.run -= 1;
+		} else {
+			match self.decode() {
+				Some(pixel) => self.pixel = pixel,
+				None => {
+					self.remaining = 0;
+					return None;
+				}
+			}
+		}
+
+		self.remaining -= 1;
+		Some(self.pixel)
+	}
+
+	fn size_hint(&self) -> (usize, Option<usize>) {
+		(0, Some(self.remaining))
+	}
+}
@@ -0,0 +1,127 @@
+//! Boot splash: the logo centered on a black screen with a progress bar under
+//! it, shown instead of the kernel console while `kmain` sets things up.
+//! Turned on and off with the `SPLASH` build option.
+
+use super::{qoi, Painter};
+use crate::{
+	arch::cpu,
+	kwarn,
+	stdio::{
+		framebuffer::{CommonColors, FRAMEBUFFER},
+		terminal::{self, KERNEL_VT},
+	},
+};
+use core::sync::atomic::{AtomicBool, Ordering};
+use lazy_static::lazy_static;
+
+static LOGO: &[u8] = include_bytes!("../../res/logo.qoi");
+
+lazy_static! {
+	/// The logo's header, only parsed once. Decoding the pixels still
+	/// happens as they're drawn.
+	static ref LOGO_IMAGE: Result<qoi::Image<'static>, qoi::Error> =
+		qoi::Image::parse(LOGO);
+}
+
+const ENABLED: bool = cfg!(SPLASH = "ON");
+
+const BAR_WIDTH: i32 = 300;
+const BAR_HEIGHT: i32 = 12;
+/// Space between the logo and the progress bar
+const BAR_GAP: i32 = 32;
+/// Space between the progress bar's outline and its fill
+const BAR_PADDING: i32 = 2;
+
+static SHOWING: AtomicBool = AtomicBool::new(false);
+
+/// Top left corners of the logo and of the progress bar, with both of them
+/// centered on screen together
+fn layout(
+	painter: &Painter,
+	logo: Option<&qoi::Image>,
+) -> ((i32, i32), (i32, i32)) {
+	let (width, height) = painter.resolution();
+	let (logo_width, logo_height) =
+		logo.map_or((0, 0), |logo| (logo.width as i32, logo.height as i32));
+
+	let top = (height - logo_height - BAR_GAP - BAR_HEIGHT) / 2;
+
+	(
+		((width - logo_width) / 2, top),
+		((width - BAR_WIDTH) / 2, top + logo_height + BAR_GAP),
+	)
+}
+
+/// Take the screen away from the terminals and draw the logo with an empty
+/// progress bar
+pub fn show() {
+	if !ENABLED {
+		return;
+	}
+
+	terminal::detach();
+	SHOWING.store(true, Ordering::SeqCst);
+
+	let logo = *LOGO_IMAGE;
+	cpu::without_interrupts(|| {
+		let mut framebuffer = FRAMEBUFFER.lock();
+		framebuffer.clear(Default::default());
+
+		let mut painter = Painter::new(&mut framebuffer);
+		let ((logo_x, logo_y), (bar_x, bar_y)) =
+			layout(&painter, logo.as_ref().ok());
+
+		if let Ok(logo) = &logo {
+			painter.blit_pixels(
+				logo_x,
+				logo_y,
+				logo.width as i32,
+				logo.pixels(),
+			);
+		}
+		painter.rect(
+			bar_x,
+			bar_y,
+			BAR_WIDTH,
+			BAR_HEIGHT,
+			CommonColors::White.into(),
+		);
+	});
+
+	// logging draws on the terminal, so the framebuffer has to be free
+	if let Err(e) = logo {
+		kwarn!("Couldn't decode the boot logo: {:?}", e);
+	}
+}
+
+/// Fill the progress bar up to `done` out of `total` steps
+pub fn progress(done: usize, total: usize) {
+	if !SHOWING.load(Ordering::SeqCst) || total == 0 {
+		return;
+	}
+
+	let logo = LOGO_IMAGE.ok();
+	let inner_width = BAR_WIDTH - 2 * BAR_PADDING;
+	let filled = inner_width * done.min(total) as i32 / total as i32;
+
+	cpu::without_interrupts(|| {
+		let mut framebuffer = FRAMEBUFFER.lock();
+		let mut painter = Painter::new(&mut framebuffer);
+		let (_, (bar_x, bar_y)) = layout(&painter, logo.as_ref());
+
+		painter.fill_rect(
+			bar_x + BAR_PADDING,
+			bar_y + BAR_PADDING,
+			filled,
+			BAR_HEIGHT - 2 * BAR_PADDING,
+			CommonColors::White.into(),
+		);
+	});
+}
+
+/// Hand the screen back to the kernel console
+pub fn finish() {
+	if SHOWING.swap(false, Ordering::SeqCst) {
+		terminal::switch(KERNEL_VT);
+	}
+}
@@ -0,0 +1,216 @@
+//! Device independent names for keys, and the modifier state that goes with
+//! them. Keyboard drivers translate whatever their hardware sends into these.
+
+use bitflags::bitflags;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyCode {
+	Escape,
+	F1,
+	F2,
+	F3,
+	F4,
+	F5,
+	F6,
+	F7,
+	F8,
+	F9,
+	F10,
+	F11,
+	F12,
+	PrintScreen,
+	ScrollLock,
+	Pause,
+
+	Backtick,
+	Key1,
+	Key2,
+	Key3,
+	Key4,
+	Key5,
+	Key6,
+	Key7,
+	Key8,
+	Key9,
+	Key0,
+	Minus,
+	Equals,
+	Backspace,
+	Tab,
+	Q,
+	W,
+	E,
+	R,
+	T,
+	Y,
+	U,
+	I,
+	O,
+	P,
+	LeftBracket,
+	RightBracket,
+	Backslash,
+	CapsLock,
+	A,
+	S,
+	D,
+	F,
+	G,
+	H,
+	J,
+	K,
+	L,
+	Semicolon,
+	Quote,
+	Enter,
+	LeftShift,
+	/// The extra key between left shift and Z on ISO keyboards
+	NonUsBackslash,
+	Z,
+	X,
+	C,
+	V,
+	B,
+	N,
+	M,
+	Comma,
+	Period,
+	Slash,
+	RightShift,
+	LeftCtrl,
+	LeftMeta,
+	LeftAlt,
+	Space,
+	RightAlt,
+	RightMeta,
+	Menu,
+	RightCtrl,
+
+	Insert,
+	Delete,
+	Home,
+	End,
+	PageUp,
+	PageDown,
+	Up,
+	Down,
+	Left,
+	Right,
+
+	NumLock,
+	KeypadSlash,
+	KeypadStar,
+	KeypadMinus,
+	KeypadPlus,
+	KeypadEnter,
+	KeypadPeriod,
+	Keypad0,
+	Keypad1,
+	Keypad2,
+	Keypad3,
+	Keypad4,
+	Keypad5,
+	Keypad6,
+	Keypad7,
+	Keypad8,
+	Keypad9,
+}
+
+impl KeyCode {
+	/// The (lowercase, US) letter on a letter key
+	pub const fn letter(self) -> Option<char> {
+		Some(match self {
+			Self::A => 'a',
+			Self::B => 'b',
+			Self::C => 'c',
+			Self::D => 'd',
+			Self::E => 'e',
+			Self::F => 'f',
+			Self::G => 'g',
+			Self::H => 'h',
+			Self::I => 'i',
+			Self::J => 'j',
+			Self::K => 'k',
+			Self::L => 'l',
+			Self::M => 'm',
+			Self::N => 'n',
+			Self::O => 'o',
+			Self::P => 'p',
+			Self::Q => 'q',
+			Self::R => 'r',
+			Self::S => 's',
+			Self::T => 't',
+			Self::U => 'u',
+			Self::V => 'v',
+			Self::W => 'w',
+			Self::X => 'x',
+			Self::Y => 'y',
+			Self::Z => 'z',
+			_ => return None,
+		})
+	}
+
+	pub const fn keypad_digit(self) -> Option<char> {
+		Some(match self {
+			Self::Keypad0 => '0',
+			Self::Keypad1 => '1',
+			Self::Keypad2 => '2',
+			Self::Keypad3 => '3',
+			Self::Keypad4 => '4',
+			Self::Keypad5 => '5',
+			Self::Keypad6 => '6',
+			Self::Keypad7 => '7',
+			Self::Keypad8 => '8',
+			Self::Keypad9 => '9',
+			_ => return None,
+		})
+	}
+
+	/// Which function key this is, counting from 1
+	pub const fn function_number(self) -> Option<usize> {
+		Some(match self {
+			Self::F1 => 1,
+			Self::F2 => 2,
+			Self::F3 => 3,
+			Self::F4 => 4,
+			Self::F5 => 5,
+			Self::F6 => 6,
+			Self::F7 => 7,
+			Self::F8 => 8,
+			Self::F9 => 9,
+			Self::F10 => 10,
+			Self::F11 => 11,
+			Self::F12 => 12,
+			_ => return None,
+		})
+	}
+}
+
+bitflags! {
+	pub struct Modifiers: u16 {
+		const LEFT_SHIFT = 1 << 0;
+		const RIGHT_SHIFT = 1 << 1;
+		const LEFT_CTRL = 1 << 2;
+		const RIGHT_CTRL = 1 << 3;
+		const ALT = 1 << 4;
+		const ALT_GR = 1 << 5;
+		const META = 1 << 6;
+		const CAPS_LOCK = 1 << 7;
+		const NUM_LOCK = 1 << 8;
+		const SCROLL_LOCK = 1 << 9;
+	}
+}
+
+impl Modifiers {
+	pub const fn shift(self) -> bool {
+		self.contains(Self::LEFT_SHIFT) || self.contains(Self::RIGHT_SHIFT)
+	}
+
+	pub const fn ctrl(self) -> bool {
+		self.contains(Self::LEFT_CTRL) || self.contains(Self::RIGHT_CTRL)
+	}
+
+	pub const fn alt(self) -> bool {
+		self.contains(Self::ALT)
+	}
+}
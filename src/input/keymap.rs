@@ -0,0 +1,212 @@
+//! Keyboard layouts, turning key codes into the characters printed on the
+//! keys. Only keys whose characters differ between layouts are listed in
+//! each table; letters, the keypad and the like are shared.
+
+use super::keycode::{KeyCode, Modifiers};
+use crate::arch::cpu;
+use spin::Mutex;
+
+/// Characters a key types: on its own, with shift, and with AltGr
+type Entry = (KeyCode, [Option<char>; 3]);
+
+pub struct Keymap {
+	pub name: &'static str,
+	keys: &'static [Entry],
+}
+
+const fn key(code: KeyCode, normal: char, shift: char) -> Entry {
+	(code, [Some(normal), Some(shift), None])
+}
+
+const fn key_altgr(
+	code: KeyCode,
+	normal: char,
+	shift: char,
+	altgr: char,
+) -> Entry {
+	(code, [Some(normal), Some(shift), Some(altgr)])
+}
+
+pub static US: Keymap = Keymap {
+	name: "us",
+	keys: &[
+		key(KeyCode::Backtick, '`', '~'),
+		key(KeyCode::Key1, '1', '!'),
+		key(KeyCode::Key2, '2', '@'),
+		key(KeyCode::Key3, '3', '#'),
+		key(KeyCode::Key4, '4', '$'),
+		key(KeyCode::Key5, '5', '%'),
+		key(KeyCode::Key6, '6', '^'),
+		key(KeyCode::Key7, '7', '&'),
+		key(KeyCode::Key8, '8', '*'),
+		key(KeyCode::Key9, '9', '('),
+		key(KeyCode::Key0, '0', ')'),
+		key(KeyCode::Minus, '-', '_'),
+		key(KeyCode::Equals, '=', '+'),
+		key(KeyCode::LeftBracket, '[', '{'),
+		key(KeyCode::RightBracket, ']', '}'),
+		key(KeyCode::Backslash, '\\', '|'),
+		key(KeyCode::Semicolon, ';', ':'),
+		key(KeyCode::Quote, '\'', '"'),
+		key(KeyCode::Comma, ',', '<'),
+		key(KeyCode::Period, '.', '>'),
+		key(KeyCode::Slash, '/', '?'),
+		key(KeyCode::NonUsBackslash, '\\', '|'),
+	],
+};
+
+pub static UK: Keymap = Keymap {
+	name: "uk",
+	keys: &[
+		key_altgr(KeyCode::Backtick, '`', '¬', '¦'),
+		key(KeyCode::Key1, '1', '!'),
+		key(KeyCode::Key2, '2', '"'),
+		key(KeyCode::Key3, '3', '£'),
+		key_altgr(KeyCode::Key4, '4', '$', '€'),
+		key(KeyCode::Key5, '5', '%'),
+		key(KeyCode::Key6, '6', '^'),
+		key(KeyCode::Key7, '7', '&'),
+		key(KeyCode::Key8, '8', '*'),
+		key(KeyCode::Key9, '9', '('),
+		key(KeyCode::Key0, '0', ')'),
+		key(KeyCode::Minus, '-', '_'),
+		key(KeyCode::Equals, '=', '+'),
+		key(KeyCode::LeftBracket, '[', '{'),
+		key(KeyCode::RightBracket, ']', '}'),
+		// sits next to Enter, where US keyboards have backslash
+		key(KeyCode::Backslash, '#', '~'),
+		key(KeyCode::Semicolon, ';', ':'),
+		key(KeyCode::Quote, '\'', '@'),
+		key(KeyCode::Comma, ',', '<'),
+		key(KeyCode::Period, '.', '>'),
+		key(KeyCode::Slash, '/', '?'),
+		key(KeyCode::NonUsBackslash, '\\', '|'),
+	],
+};
+
+pub static DE: Keymap = Keymap {
+	name: "de",
+	keys: &[
+		key(KeyCode::Backtick, '^', '°'),
+		key(KeyCode::Key1, '1', '!'),
+		key_altgr(KeyCode::Key2, '2', '"', '²'),
+		key_altgr(KeyCode::Key3, '3', '§', '³'),
+		key(KeyCode::Key4, '4', '$'),
+		key(KeyCode::Key5, '5', '%'),
+		key(KeyCode::Key6, '6', '&'),
+		key_altgr(KeyCode::Key7, '7', '/', '{'),
+		key_altgr(KeyCode::Key8, '8', '(', '['),
+		key_altgr(KeyCode::Key9, '9', ')', ']'),
+		key_altgr(KeyCode::Key0, '0', '=', '}'),
+		key_altgr(KeyCode::Minus, 'ß', '?', '\\'),
+		key(KeyCode::Equals, '´', '`'),
+		key_altgr(KeyCode::Q, 'q', 'Q', '@'),
+		key_altgr(KeyCode::E, 'e', 'E', '€'),
+		// QWERTZ
+		key(KeyCode::Y, 'z', 'Z'),
+		key(KeyCode::Z, 'y', 'Y'),
+		key_altgr(KeyCode::M, 'm', 'M', 'µ'),
+		key(KeyCode::LeftBracket, 'ü', 'Ü'),
+		key_altgr(KeyCode::RightBracket, '+', '*', '~'),
+		key(KeyCode::Backslash, '#', '\''),
+		key(KeyCode::Semicolon, 'ö', 'Ö'),
+		key(KeyCode::Quote, 'ä', 'Ä'),
+		key(KeyCode::Comma, ',', ';'),
+		key(KeyCode::Period, '.', ':'),
+		key(KeyCode::Slash, '-', '_'),
+		key_altgr(KeyCode::NonUsBackslash, '<', '>', '|'),
+	],
+};
+
+pub static KEYMAPS: [&Keymap; 3] = [&US, &UK, &DE];
+
+#[cfg(not(any(KEYMAP = "UK", KEYMAP = "DE")))]
+static DEFAULT: &Keymap = &US;
+#[cfg(KEYMAP = "UK")]
+static DEFAULT: &Keymap = &UK;
+#[cfg(KEYMAP = "DE")]
+static DEFAULT: &Keymap = &DE;
+
+// the keyboard interrupt reads this, so only lock it with interrupts disabled
+static CURRENT: Mutex<&Keymap> = Mutex::new(DEFAULT);
+
+/// Find a keymap by its (lowercase) name
+pub fn by_name(name: &str) -> Option<&'static Keymap> {
+	KEYMAPS.iter().copied().find(|keymap| keymap.name == name)
+}
+
+pub fn current() -> &'static Keymap {
+	cpu::without_interrupts(|| *CURRENT.lock())
+}
+
+/// Switch layouts; affects keys pressed from now on
+pub fn set_keymap(keymap: &'static Keymap) {
+	cpu::without_interrupts(|| *CURRENT.lock() = keymap);
+}
+
+impl Keymap {
+	/// The character a key press types, if any
+	pub fn translate(
+		&self,
+		code: KeyCode,
+		modifiers: Modifiers,
+	) -> Option<char> {
+		let shift = modifiers.shift();
+		let c = match self.keys.iter().find(|(key, _)| *key == code) {
+			Some((_, chars)) => {
+				if modifiers.contains(Modifiers::ALT_GR) {
+					chars[2]?
+				} else {
+					// caps lock only works on letters, and shift undoes it
+					let caps = modifiers.contains(Modifiers::CAPS_LOCK)
+						&& chars[0].map_or(false, char::is_alphabetic);
+					chars[(shift ^ caps) as usize]?
+				}
+			}
+			None => {
+				if modifiers.contains(Modifiers::ALT_GR) {
+					return None;
+				}
+				shared(code, shift, modifiers)?
+			}
+		};
+
+		// Ctrl with a letter types the matching control character
+		if modifiers.ctrl() && c.is_ascii_alphabetic() {
+			return Some((c.to_ascii_uppercase() as u8 - b'@') as char);
+		}
+
+		Some(c)
+	}
+}
+
+/// Characters that are the same on every layout
+fn shared(code: KeyCode, shift: bool, modifiers: Modifiers) -> Option<char> {
+	if let Some(letter) = code.letter() {
+		let upper = shift ^ modifiers.contains(Modifiers::CAPS_LOCK);
+		return Some(
+			if upper {
+				letter.to_ascii_uppercase()
+			} else {
+				letter
+			},
+		);
+	}
+
+	let num_lock = modifiers.contains(Modifiers::NUM_LOCK);
+	Some(match code {
+		KeyCode::Space => ' ',
+		KeyCode::Tab => '\t',
+		KeyCode::Enter | KeyCode::KeypadEnter => '\n',
+		KeyCode::Backspace => '\x08',
+		KeyCode::Escape => '\x1b',
+		KeyCode::KeypadSlash => '/',
+		KeyCode::KeypadStar => '*',
+		KeyCode::KeypadMinus => '-',
+		KeyCode::KeypadPlus => '+',
+		// without num lock the keypad is arrows and such instead
+		KeyCode::KeypadPeriod if num_lock => '.',
+		_ if num_lock => code.keypad_digit()?,
+		_ => return None,
+	})
+}
@@ -0,0 +1,196 @@
+//! Input from every device in one place. Drivers register their devices and
+//! publish events as they happen; anyone interested subscribes and gets a
+//! queue of their own, holding everything published from then on.
+
+pub mod keycode;
+pub mod keymap;
+
+pub use keycode::{KeyCode, Modifiers};
+
+use crate::{
+	arch::{cpu, pit},
+	ring_buffer::RingBuffer,
+};
+use spin::Mutex;
+
+const MAX_DEVICES: usize = 8;
+const MAX_SUBSCRIBERS: usize = 4;
+/// Events each subscriber can fall behind by before the oldest are dropped
+const QUEUE_SIZE: usize = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeviceKind {
+	Keyboard,
+	Mouse,
+	Serial,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeviceId(usize);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Device {
+	pub id: DeviceId,
+	pub name: &'static str,
+	pub kind: DeviceKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+	Left,
+	Right,
+	Middle,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum EventKind {
+	KeyDown {
+		code: KeyCode,
+		/// Modifiers and locks, including any this key changed
+		modifiers: Modifiers,
+		/// What the key types under the current keymap
+		c: Option<char>,
+	},
+	KeyUp {
+		code: KeyCode,
+		modifiers: Modifiers,
+	},
+	/// Text from a device without keys, like a serial terminal
+	Char(char),
+	/// Movement since the last event, positive being right and down
+	RelativeMotion {
+		dx: i32,
+		dy: i32,
+	},
+	/// Position on a device that has its own idea of where it is (a tablet,
+	/// say), scaled so 0 to `u16::MAX` covers the whole screen
+	AbsoluteMotion {
+		x: u16,
+		y: u16,
+	},
+	Button {
+		button: MouseButton,
+		pressed: bool,
+	},
+	/// Scroll wheel clicks, positive being towards the user
+	Wheel(i32),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Event {
+	pub device: DeviceId,
+	/// Uptime when the event was published
+	pub time_ms: u64,
+	pub kind: EventKind,
+}
+
+struct Queue {
+	subscribed: bool,
+	events: RingBuffer<Event, QUEUE_SIZE>,
+}
+
+impl Queue {
+	const fn new() -> Self {
+		Self {
+			subscribed: false,
+			events: RingBuffer::new(Event {
+				device: DeviceId(0),
+				time_ms: 0,
+				kind: EventKind::Wheel(0),
+			}),
+		}
+	}
+}
+
+// events are published from interrupt handlers, so these are only locked with
+// interrupts disabled
+static DEVICES: Mutex<[Option<Device>; MAX_DEVICES]> =
+	Mutex::new([None; MAX_DEVICES]);
+static QUEUES: Mutex<[Queue; MAX_SUBSCRIBERS]> =
+	Mutex::new([Queue::new(), Queue::new(), Queue::new(), Queue::new()]);
+
+/// Add a device to publish events for, or `None` if there's no room left
+pub fn register(name: &'static str, kind: DeviceKind) -> Option<DeviceId> {
+	cpu::without_interrupts(|| {
+		let mut devices = DEVICES.lock();
+		let index = devices.iter().position(Option::is_none)?;
+		let id = DeviceId(index);
+
+		devices[index] = Some(Device { id, name, kind });
+		Some(id)
+	})
+}
+
+pub fn device(id: DeviceId) -> Option<Device> {
+	cpu::without_interrupts(|| DEVICES.lock()[id.0])
+}
+
+/// Every registered device, in the order they were registered
+pub fn devices() -> impl Iterator<Item = Device> {
+	let devices = cpu::without_interrupts(|| *DEVICES.lock());
+	(0..MAX_DEVICES).filter_map(move |index| devices[index])
+}
+
+/// Hand an event to every subscriber. Safe to call from interrupt handlers.
+pub fn publish(device: DeviceId, kind: EventKind) {
+	let event = Event {
+		device,
+		time_ms: pit::uptime_ms(),
+		kind,
+	};
+
+	cpu::without_interrupts(|| {
+		for queue in QUEUES.lock().iter_mut().filter(|queue| queue.subscribed) {
+			// a subscriber that isn't keeping up loses its oldest events
+			queue.events.push_overwrite(event);
+		}
+	});
+}
+
+/// Start receiving events, or `None` if there are already too many
+/// subscribers
+pub fn subscribe() -> Option<Subscriber> {
+	cpu::without_interrupts(|| {
+		let mut queues = QUEUES.lock();
+		let index = queues.iter().position(|queue| !queue.subscribed)?;
+
+		queues[index].subscribed = true;
+		queues[index].events.clear();
+		Some(Subscriber { index })
+	})
+}
+
+/// A queue of events, which goes away when this is dropped
+pub struct Subscriber {
+	index: usize,
+}
+
+impl Subscriber {
+	/// Take the oldest event off the queue, if there is one
+	pub fn read(&self) -> Option<Event> {
+		cpu::without_interrupts(|| QUEUES.lock()[self.index].events.pop())
+	}
+
+	/// Like `read`, but sleeps until there's an event
+	pub fn wait(&self) -> Event {
+		loop {
+			cpu::disable_interrupts();
+			let event = QUEUES.lock()[self.index].events.pop();
+
+			if let Some(event) = event {
+				cpu::enable_interrupts();
+				return event;
+			}
+
+			cpu::enable_interrupts_and_wait();
+		}
+	}
+}
+
+impl Drop for Subscriber {
+	fn drop(&mut self) {
+		cpu::without_interrupts(|| {
+			QUEUES.lock()[self.index].subscribed = false
+		});
+	}
+}
@@ -0,0 +1,185 @@
+use super::{label, Level, Record, Sink};
+use crate::{
+	arch::{cpu, pit},
+	ring_buffer::RingBuffer,
+};
+use core::{
+	fmt::{self, Arguments, Write},
+	str,
+};
+use spin::Mutex;
+
+/// Longer lines are split across several entries
+pub const LINE_LEN: usize = 120;
+const ENTRIES: usize = 512;
+
+/// One line of kernel log
+#[derive(Clone, Copy)]
+pub struct Entry {
+	/// Milliseconds since boot when the line was started
+	pub timestamp: u64,
+	/// `None` for plain `kprint!` output
+	pub level: Option<Level>,
+	pub success: bool,
+	len: usize,
+	text: [u8; LINE_LEN],
+}
+
+impl Entry {
+	const EMPTY: Self = Self {
+		timestamp: 0,
+		level: None,
+		success: false,
+		len: 0,
+		text: [0; LINE_LEN],
+	};
+
+	pub fn text(&self) -> &str {
+		let text = &self.text[..self.len];
+
+		// splitting a long line can cut a character in half
+		match str::from_utf8(text) {
+			Ok(text) => text,
+			Err(e) => str::from_utf8(&text[..e.valid_up_to()]).unwrap_or(""),
+		}
+	}
+}
+
+impl fmt::Display for Entry {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(
+			f,
+			"[{:>5}.{:03}] ",
+			self.timestamp / 1000,
+			self.timestamp % 1000
+		)?;
+
+		if let Some(level) = self.level {
+			write!(f, "[ {} ] ", label(level, self.success))?;
+		}
+
+		f.write_str(self.text())
+	}
+}
+
+struct DmesgInner {
+	entries: RingBuffer<Entry, ENTRIES>,
+	// plain kprint output doesn't have to come a line at a time, so the line
+	// in progress is kept around between calls
+	raw_line: Entry,
+}
+
+/// Appends text to `line`, moving it into `entries` whenever it's complete
+struct LineWriter<'a> {
+	entries: &'a mut RingBuffer<Entry, ENTRIES>,
+	line: &'a mut Entry,
+}
+
+impl LineWriter<'_> {
+	fn commit(&mut self) {
+		self.entries.push_overwrite(*self.line);
+		self.line.len = 0;
+	}
+}
+
+impl Write for LineWriter<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		for byte in s.bytes() {
+			if self.line.len == 0 {
+				self.line.timestamp = pit::uptime_ms();
+			}
+
+			match byte {
+				b'\n' => self.commit(),
+				_ => {
+					self.line.text[self.line.len] = byte;
+					self.line.len += 1;
+
+					if self.line.len == LINE_LEN {
+						self.commit();
+					}
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Ring buffer holding the last `ENTRIES` lines printed by the kernel, both
+/// log records and plain `kprint!` output
+pub struct Dmesg(Mutex<DmesgInner>);
+
+impl Dmesg {
+	const fn new() -> Self {
+		Self(Mutex::new(DmesgInner {
+			entries: RingBuffer::new(Entry::EMPTY),
+			raw_line: Entry::EMPTY,
+		}))
+	}
+}
+
+impl Sink for Dmesg {
+	fn log(&self, record: &Record) {
+		let mut line = Entry {
+			level: Some(record.level),
+			success: record.success,
+			..Entry::EMPTY
+		};
+
+		cpu::without_interrupts(|| {
+			let mut inner = self.0.lock();
+			let mut writer = LineWriter {
+				entries: &mut inner.entries,
+				line: &mut line,
+			};
+
+			let _ = writer.write_fmt(record.args);
+			if writer.line.len > 0 {
+				writer.commit();
+			}
+		});
+	}
+}
+
+pub static DMESG: Dmesg = Dmesg::new();
+
+/// Record plain `kprint!` output
+pub fn record_raw(args: Arguments) {
+	cpu::without_interrupts(|| {
+		let inner = &mut *DMESG.0.lock();
+		let _ = LineWriter {
+			entries: &mut inner.entries,
+			line: &mut inner.raw_line,
+		}
+		.write_fmt(args);
+	});
+}
+
+/// Call `f` on every recorded line, oldest first
+pub fn for_each(mut f: impl FnMut(&Entry)) {
+	cpu::without_interrupts(|| {
+		for entry in DMESG.0.lock().entries.iter() {
+			f(&entry);
+		}
+	});
+}
+
+/// Write out every recorded line, oldest first
+pub fn dump(out: &mut dyn Write) -> fmt::Result {
+	let mut result = Ok(());
+	for_each(|entry| {
+		if result.is_ok() {
+			result = writeln!(out, "{}", entry);
+		}
+	});
+	result
+}
+
+/// Let go of the log no matter who holds it, for the panic handler only
+pub unsafe fn force_unlock() {
+	DMESG.0.force_unlock();
+}
+
+pub fn clear() {
+	cpu::without_interrupts(|| DMESG.0.lock().entries.clear());
+}
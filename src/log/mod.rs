@@ -0,0 +1,273 @@
+pub mod dmesg;
+pub mod sink;
+
+use crate::{arch::cpu, stdio::framebuffer::CommonColors};
+use core::{
+	fmt,
+	sync::atomic::{AtomicU8, Ordering},
+};
+use spin::Mutex;
+
+/// How important a message is, from most to least
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Level {
+	Error = 1,
+	Warn,
+	Info,
+	Debug,
+	Trace,
+}
+
+impl Level {
+	const fn from_u8(level: u8) -> Self {
+		match level {
+			1 => Self::Error,
+			2 => Self::Warn,
+			3 => Self::Info,
+			4 => Self::Debug,
+			_ => Self::Trace,
+		}
+	}
+
+	pub const fn label(self) -> &'static str {
+		match self {
+			Self::Error => "fail",
+			Self::Warn => "warn",
+			Self::Info => "info",
+			Self::Debug => "dbug",
+			Self::Trace => "trce",
+		}
+	}
+
+	pub const fn color(self) -> CommonColors {
+		match self {
+			Self::Error => CommonColors::Red,
+			Self::Warn => CommonColors::Yellow,
+			Self::Info => CommonColors::Cyan,
+			Self::Debug => CommonColors::Magenta,
+			Self::Trace => CommonColors::Blue,
+		}
+	}
+}
+
+/// Anything above this level is compiled out entirely, set with the
+/// `LOG_LEVEL` build option
+#[cfg(LOG_LEVEL = "ERROR")]
+pub const STATIC_MAX_LEVEL: Level = Level::Error;
+#[cfg(LOG_LEVEL = "WARN")]
+pub const STATIC_MAX_LEVEL: Level = Level::Warn;
+#[cfg(LOG_LEVEL = "INFO")]
+pub const STATIC_MAX_LEVEL: Level = Level::Info;
+#[cfg(LOG_LEVEL = "DEBUG")]
+pub const STATIC_MAX_LEVEL: Level = Level::Debug;
+#[cfg(LOG_LEVEL = "TRACE")]
+pub const STATIC_MAX_LEVEL: Level = Level::Trace;
+
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(STATIC_MAX_LEVEL as u8);
+
+/// Most verbose level that currently gets through to the sinks
+pub fn max_level() -> Level {
+	Level::from_u8(MAX_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Change the runtime filter. It can't let through more than
+/// `STATIC_MAX_LEVEL`, since those messages aren't in the kernel at all.
+pub fn set_max_level(level: Level) {
+	MAX_LEVEL.store(level.min(STATIC_MAX_LEVEL) as u8, Ordering::Relaxed);
+}
+
+pub struct Record<'a> {
+	pub level: Level,
+	/// Module path of the call site, unless overridden with `target:`
+	pub target: &'a str,
+	pub args: fmt::Arguments<'a>,
+	/// Reports something finishing successfully (`ksprintln!`) rather than
+	/// just information
+	pub success: bool,
+}
+
+/// Label shown in front of a message, e.g. "info" in `[ info ] =>`
+pub const fn label(level: Level, success: bool) -> &'static str {
+	if success {
+		"scss"
+	} else {
+		level.label()
+	}
+}
+
+impl Record<'_> {
+	pub const fn label(&self) -> &'static str {
+		label(self.level, self.success)
+	}
+
+	pub const fn color(&self) -> CommonColors {
+		if self.success {
+			CommonColors::Green
+		} else {
+			self.level.color()
+		}
+	}
+}
+
+/// Somewhere log records end up
+pub trait Sink: Sync {
+	/// Lets a sink be pickier than the global filter
+	fn enabled(&self, _level: Level) -> bool {
+		true
+	}
+
+	fn log(&self, record: &Record);
+}
+
+const MAX_SINKS: usize = 8;
+
+/// Which slot a sink was registered in, for unregistering it later
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SinkId(usize);
+
+// the sinks that are there from the start
+pub const FRAMEBUFFER_SINK: SinkId = SinkId(0);
+pub const SERIAL_SINK: SinkId = SinkId(1);
+pub const DMESG_SINK: SinkId = SinkId(2);
+
+static SINKS: Mutex<[Option<&'static dyn Sink>; MAX_SINKS]> = Mutex::new([
+	Some(&sink::FRAMEBUFFER),
+	Some(&sink::SERIAL),
+	Some(&dmesg::DMESG),
+	None,
+	None,
+	None,
+	None,
+	None,
+]);
+
+/// Start sending records to `sink`, or `None` if there are already too many
+/// sinks
+pub fn register(sink: &'static dyn Sink) -> Option<SinkId> {
+	cpu::without_interrupts(|| {
+		let mut sinks = SINKS.lock();
+		let index = sinks.iter().position(Option::is_none)?;
+
+		sinks[index] = Some(sink);
+		Some(SinkId(index))
+	})
+}
+
+/// Stop sending records to the sink `register` gave `id` for. Sinks can be
+/// zero sized, so they're told apart by slot rather than by address.
+pub fn unregister(id: SinkId) {
+	cpu::without_interrupts(|| SINKS.lock()[id.0] = None);
+}
+
+/// Backend for the logging macros
+#[doc(hidden)]
+pub fn _log(level: Level, target: &str, args: fmt::Arguments, success: bool) {
+	if level > max_level() {
+		return;
+	}
+
+	let record = Record {
+		level,
+		target,
+		args,
+		success,
+	};
+
+	// interrupt handlers log too, so the list is only locked with interrupts
+	// off, and only long enough to copy it; sinks lock their own state the
+	// same way
+	let sinks = cpu::without_interrupts(|| *SINKS.lock());
+	for sink in sinks.iter().flatten() {
+		if sink.enabled(level) {
+			sink.log(&record);
+		}
+	}
+}
+
+/// Log a message at the given level, optionally with an explicit target
+#[macro_export]
+macro_rules! klog {
+	(target: $target:expr, $level:expr, $($arg:tt)+) => ({
+		let level = $level;
+		if level <= $crate::log::STATIC_MAX_LEVEL {
+			$crate::log::_log(level, $target, format_args!($($arg)+), false);
+		}
+	});
+	($level:expr, $($arg:tt)+) => ({
+		$crate::klog!(target: module_path!(), $level, $($arg)+);
+	});
+}
+
+/// Log an error message
+#[macro_export]
+macro_rules! kerror {
+	($($arg:tt)+) => ({
+		$crate::klog!($crate::log::Level::Error, $($arg)+);
+	});
+}
+
+/// Log a warning message
+#[macro_export]
+macro_rules! kwarn {
+	($($arg:tt)+) => ({
+		$crate::klog!($crate::log::Level::Warn, $($arg)+);
+	});
+}
+
+/// Log an informative message
+#[macro_export]
+macro_rules! kinfo {
+	($($arg:tt)+) => ({
+		$crate::klog!($crate::log::Level::Info, $($arg)+);
+	});
+}
+
+/// Log a debugging message
+#[macro_export]
+macro_rules! kdebug {
+	($($arg:tt)+) => ({
+		$crate::klog!($crate::log::Level::Debug, $($arg)+);
+	});
+}
+
+/// Log a very verbose tracing message
+#[macro_export]
+macro_rules! ktrace {
+	($($arg:tt)+) => ({
+		$crate::klog!($crate::log::Level::Trace, $($arg)+);
+	});
+}
+
+/// Render formatted informative text, with a newline & a colored "info" label
+#[macro_export]
+macro_rules! kiprintln {
+	($($arg:tt)+) => ({
+		$crate::kinfo!($($arg)+);
+	});
+}
+
+/// Render formatted error text, with a newline & a colored "fail" label
+#[macro_export]
+macro_rules! keprintln {
+	($($arg:tt)+) => ({
+		$crate::kerror!($($arg)+);
+	});
+}
+
+/// Render formatted success text, with a newline & a colored "scss" label
+#[macro_export]
+macro_rules! ksprintln {
+	($($arg:tt)+) => ({
+		use $crate::log::{Level, STATIC_MAX_LEVEL};
+
+		if Level::Info <= STATIC_MAX_LEVEL {
+			$crate::log::_log(
+				Level::Info,
+				module_path!(),
+				format_args!($($arg)+),
+				true,
+			);
+		}
+	});
+}
@@ -0,0 +1,65 @@
+use super::{Record, Sink};
+use crate::{
+	arch::cpu,
+	stdio::{
+		framebuffer::CommonColors,
+		serial::{self, SERIAL_WRITER},
+		terminal,
+	},
+};
+use core::fmt::Write;
+
+/// Renders records on the framebuffer console with a colored label
+pub struct FramebufferSink;
+
+impl Sink for FramebufferSink {
+	fn log(&self, record: &Record) {
+		terminal::with_console(|writer| {
+			writer.fg.set(record.color());
+			let _ = write!(writer, "[ {} ] => ", record.label());
+			writer.fg.reset();
+
+			let _ = writer.write_fmt(record.args);
+			let _ = writer.write_char('\n');
+		});
+	}
+}
+
+/// Sends records over COM1 while serial mirroring is on, colored with ANSI
+/// escapes like x.py's output
+pub struct SerialSink;
+
+const fn ansi_color(color: CommonColors) -> u8 {
+	match color {
+		CommonColors::Black => 30,
+		CommonColors::Red => 31,
+		CommonColors::Green => 32,
+		CommonColors::Yellow => 33,
+		CommonColors::Blue => 34,
+		CommonColors::Magenta => 35,
+		CommonColors::Cyan => 36,
+		CommonColors::White => 37,
+	}
+}
+
+impl Sink for SerialSink {
+	fn log(&self, record: &Record) {
+		if !serial::mirroring() {
+			return;
+		}
+
+		cpu::without_interrupts(|| {
+			let _ = writeln!(
+				SERIAL_WRITER.lock(),
+				"\x1b[1m\x1b[{}m[ {} ] {} =>\x1b[0m {}",
+				ansi_color(record.color()),
+				record.label(),
+				record.target,
+				record.args
+			);
+		});
+	}
+}
+
+pub static FRAMEBUFFER: FramebufferSink = FramebufferSink;
+pub static SERIAL: SerialSink = SerialSink;
@@ -1,5 +1,6 @@
 #![no_std]
 #![no_main]
+#![feature(abi_x86_interrupt)]
 #![feature(asm)]
 #![feature(const_panic)]
 #![feature(panic_info_message)]
@@ -13,16 +14,41 @@
 //! BruhOS is an x86_64 operating system
 
 mod arch;
+mod block;
 mod boot;
+mod drivers;
+mod gfx;
+mod input;
+mod log;
 mod mm;
 mod polyfill;
+mod ring_buffer;
+mod shell;
 mod stdio;
 
-use arch::cpu;
+use arch::{apic, cpu, idt, pic, pit};
 use boot::STIVALE_STRUCT;
-use core::panic::{Location, PanicInfo};
+use core::{
+	fmt::Write,
+	panic::{Location, PanicInfo},
+};
+use drivers::{
+	ahci, ata, nvme, pci,
+	ps2::{self, keyboard, mouse},
+	ramdisk::{self, RamDiskError},
+};
+use gfx::{pointer, splash};
 use mm::pmm;
-use stdio::framebuffer::{CommonColors, STDIO_WRITER};
+use log::dmesg;
+use stdio::{
+	framebuffer::{CommonColors, FRAMEBUFFER},
+	serial::{self, SERIAL_WRITER},
+	terminal::{self, KERNEL_VT},
+};
+use zap_font::Font;
+
+/// Steps of `kmain` the boot splash's progress bar is split into
+const BOOT_STAGES: usize = 5;
 
 /// Bootloader entrypoint (kernel main)
 #[no_mangle]
@@ -36,22 +62,101 @@ pub fn kmain(stivale_struct_ptr: usize) -> ! {
 		STIVALE_STRUCT.set(stivale::load(stivale_struct_ptr));
 	}
 
+	splash::show();
+	// the terminals' cells come from the PMM, so it goes before anything
+	// that prints
 	pmm::init();
+
+	idt::init();
+	pic::init();
+	pit::init();
+	terminal::init();
+	pit::on_tick(terminal::blink_cursor);
+	splash::progress(1, BOOT_STAGES);
+
+	if let Err(e) = serial::init() {
+		keprintln!("Serial console is unavailable: {:?}", e);
+	}
+	cpu::enable_interrupts();
+	splash::progress(2, BOOT_STAGES);
+
+	match ps2::init().and_then(|_| keyboard::init()) {
+		Ok(set) => kinfo!("PS/2 keyboard ready, using scancode {:?}", set),
+		Err(e) => kwarn!("PS/2 keyboard is unavailable: {:?}", e),
+	}
+	let mouse = mouse::init();
+	match &mouse {
+		Ok(kind) => kinfo!("PS/2 mouse ready ({:?})", kind),
+		Err(e) => kwarn!("PS/2 mouse is unavailable: {:?}", e),
+	}
+	splash::progress(3, BOOT_STAGES);
+
 	pmm::sanity_check();
+	if let Err(e) = apic::init() {
+		kwarn!(
+			"Local APIC is unavailable, PCI devices can't use MSI: {:?}",
+			e
+		);
+	}
+	pci::init();
+	block::cache::init();
+	match ramdisk::init() {
+		Ok(_) => kinfo!("RAM disk ready"),
+		Err(RamDiskError::NoModule) => {}
+		Err(e) => kwarn!("Couldn't set up the RAM disk: {:?}", e),
+	}
+	ata::init();
+	ahci::init();
+	nvme::init();
+	// the framebuffer has to be unlocked again before logging
+	let double_buffered = cpu::without_interrupts(|| {
+		FRAMEBUFFER.lock().enable_double_buffering()
+	});
+	if !double_buffered {
+		kwarn!("Not enough memory to double buffer the framebuffer");
+	}
+	splash::progress(4, BOOT_STAGES);
+
+	// limine can hand over a replacement console font as a boot module
+	if let Some(data) = boot::module("font") {
+		match Font::parse(data) {
+			Ok(font) => terminal::set_font(font),
+			Err(e) => kwarn!("Couldn't load the font module: {:?}", e),
+		}
+	}
+	splash::progress(5, BOOT_STAGES);
+	splash::finish();
+	if mouse.is_ok() {
+		pointer::show();
+	}
 
 	kprintln!(include_str!("../res/ascii.txt"));
 	ksprintln!("Everything works!");
 
-	loop {
-		cpu::wait_for_interrupt();
-	}
+	shell::run()
 }
 
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
-	let mut writer = STDIO_WRITER.lock();
-	writer.fg.set(CommonColors::White);
-	writer.bg.set(CommonColors::Black);
+	cpu::disable_interrupts();
+	// the panic may have hit with any of these held, and nothing is ever
+	// going to run again to let go of them. Forcing them open is only sound
+	// because whoever held them never gets to touch them again: interrupts
+	// are off for good, and this never returns
+	unsafe {
+		serial::force_unlock();
+		dmesg::force_unlock();
+		terminal::force_unlock();
+	}
+
+	// make sure the panic actually shows up on screen. The guard has to be
+	// gone before keprintln locks the terminal again
+	terminal::switch(KERNEL_VT);
+	{
+		let mut writer = terminal::console().lock();
+		writer.fg.set(CommonColors::White);
+		writer.bg.set(CommonColors::Black);
+	}
 
 	static DEFAULT_LOCATION: Location =
 		Location::internal_constructor("UNKNOWN", 0, 0);
@@ -65,6 +170,12 @@ fn panic(info: &PanicInfo) -> ! {
 		info.message().unwrap_or(&format_args!("UNKNOWN")),
 	);
 
+	// the framebuffer only shows what's still on screen, so hand the whole
+	// history (ending with the panic itself) to whoever is listening on COM1
+	let mut serial = SERIAL_WRITER.lock();
+	let _ = serial.write_str("\n---- kernel log ----\n");
+	let _ = dmesg::dump(&mut *serial);
+
 	loop {
 		cpu::wait_for_interrupt();
 	}
@@ -1,4 +1,9 @@
 // pub const HIGH_HALF_OFFSET: usize = 0xffff800000000000;
 pub const PAGE_SIZE: usize = 4096;
+/// Physical memory below this is mapped at the same virtual address, so
+/// anything in it (device registers, ACPI tables, pages from the PMM) can be
+/// used as is. The kernel itself is linked in the higher half, which is why
+/// drivers bounce DMA through PMM pages rather than using callers' buffers.
+pub const IDENTITY_MAPPED: u64 = 4 * 1024 * 1024 * 1024;
 
 pub mod pmm;
@@ -3,6 +3,7 @@ use crate::{kiprintln, ksprintln, polyfill, STIVALE_STRUCT};
 use core::{
 	alloc::{GlobalAlloc, Layout},
 	cell::UnsafeCell,
+	slice,
 };
 use spin::Mutex;
 use stivale::memory::MemoryMapEntryType;
@@ -61,15 +62,15 @@ impl Pmm {
 	// offset = page-aligned address / page size
 	fn bitmap_reset_bit(&self, offset: usize) {
 		unsafe {
-			*self.get_bitmap_ptr().add(polyfill::div_up(offset, 8)) &=
-				0 << (8 - (offset % 8) - 1);
+			*self.get_bitmap_ptr().add(offset / 8) &=
+				!(1 << (8 - (offset % 8) - 1));
 		}
 	}
 
 	// offset = page-aligned address / page size
 	fn bitmap_set_bit(&self, offset: usize) {
 		unsafe {
-			*self.get_bitmap_ptr().add(polyfill::div_up(offset, 8)) |=
+			*self.get_bitmap_ptr().add(offset / 8) |=
 				1 << (8 - (offset % 8) - 1);
 		}
 	}
@@ -77,8 +78,7 @@ impl Pmm {
 	// offset = page-aligned address / page size
 	fn bitmap_test_bit(&self, offset: usize) -> bool {
 		unsafe {
-			(*self.get_bitmap_ptr().add(polyfill::div_up(offset, 8))
-				>> (8 - (offset % 8) - 1))
+			(*self.get_bitmap_ptr().add(offset / 8) >> (8 - (offset % 8) - 1))
 				& 1 == 1
 		}
 	}
@@ -97,10 +97,10 @@ pub fn init() {
 	let highest_page = mmap_usable
 		.clone()
 		.fold(0, |acc, cur| cur.end_address().max(acc)) as usize;
-	kiprintln!("Addressing: {} MiB of memory", highest_page / 1024 / 1024);
-
 	let highest_bit = polyfill::div_up(highest_page, super::PAGE_SIZE);
-	let bitmap_size = highest_bit / 8;
+	// the bits past `highest_bit` in the last byte stay set, so they never
+	// look free
+	let bitmap_size = polyfill::div_up(highest_bit, 8);
 
 	unsafe {
 		PMM.set_highest_bit(highest_bit);
@@ -134,6 +134,8 @@ pub fn init() {
 		}
 	}
 
+	// printing sets up the terminals, which allocate, so only now
+	kiprintln!("Addressing: {} MiB of memory", highest_page / 1024 / 1024);
 	kiprintln!(
 		"Initialized {} KiB PMM bitmap at: {:p}",
 		bitmap_size / 1024,
@@ -141,9 +143,10 @@ pub fn init() {
 	);
 }
 
-unsafe impl GlobalAlloc for Pmm {
-	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-		let pages = polyfill::div_up(layout.size(), PAGE_SIZE);
+impl Pmm {
+	/// First run of `pages` free pages, marked as used, or `None` if there's
+	/// no run that long
+	unsafe fn find_pages(&self, pages: usize) -> Option<*mut u8> {
 		let mut contiguous = 0;
 
 		for offset in self.get_last_used_page()..self.get_highest_bit() {
@@ -158,25 +161,89 @@ unsafe impl GlobalAlloc for Pmm {
 						self.bitmap_set_bit(p);
 					}
 
-					return (page * PAGE_SIZE) as *mut u8;
+					return Some((page * PAGE_SIZE) as *mut u8);
 				}
 			} else {
 				contiguous = 0;
 			}
 		}
+		None
+	}
+
+	/// Pages not handed out, counted a byte of the bitmap at a time
+	fn count_free(&self) -> usize {
+		let inner = unsafe { self.0.get().as_ref().unwrap().lock() };
+		let (bitmap, bits) = match (inner.bitmap_ptr, inner.highest_bit) {
+			(Some(bitmap), Some(bits)) => (bitmap, bits),
+			_ => return 0,
+		};
+
+		let len = polyfill::div_up(bits, 8);
+		let bitmap = unsafe { slice::from_raw_parts(bitmap as *const u8, len) };
+		bitmap.iter().map(|byte| byte.count_zeros() as usize).sum()
+	}
+}
+
+unsafe impl GlobalAlloc for Pmm {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		let pages = polyfill::div_up(layout.size(), PAGE_SIZE);
 
 		// oom i think? until paging is set up i guess
-		panic!("PMM: um");
+		self.find_pages(pages).expect("PMM: um")
 	}
 
 	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
 		let pages = polyfill::div_up(layout.size(), PAGE_SIZE);
 		for page in 0..pages {
-			self.bitmap_reset_bit((ptr as usize + page) / PAGE_SIZE)
+			self.bitmap_reset_bit((ptr as usize + page * PAGE_SIZE) / PAGE_SIZE)
 		}
 	}
 }
 
+/// Allocate `count` physically contiguous, page-aligned pages
+pub fn alloc_pages(count: usize) -> *mut u8 {
+	unsafe { PMM.alloc(page_layout(count)) }
+}
+
+/// Like `alloc_pages`, but `None` rather than a panic if there isn't a run
+/// of `count` free pages
+pub fn try_alloc_pages(count: usize) -> Option<*mut u8> {
+	unsafe { PMM.find_pages(count) }
+}
+
+/// Give back pages from `alloc_pages` or `try_alloc_pages`
+pub unsafe fn free_pages(ptr: *mut u8, count: usize) {
+	PMM.dealloc(ptr, page_layout(count));
+}
+
+fn page_layout(count: usize) -> Layout {
+	Layout::from_size_align(count * PAGE_SIZE, PAGE_SIZE)
+		.expect("PMM: page count overflows a layout")
+}
+
+/// Page counts, for showing how much memory is in use
+pub struct Stats {
+	/// Pages the bootloader said are usable
+	pub usable: usize,
+	/// Usable pages not handed out. The bitmap's own pages count as
+	/// handed out.
+	pub free: usize,
+}
+
+pub fn stats() -> Stats {
+	let usable = STIVALE_STRUCT.inner().memory_map().map_or(0, |mmap| {
+		mmap.iter()
+			.filter(|e| matches!(e.entry_type(), MemoryMapEntryType::Usable))
+			.map(|e| e.size() as usize / PAGE_SIZE)
+			.sum()
+	});
+
+	Stats {
+		usable,
+		free: PMM.count_free(),
+	}
+}
+
 pub fn sanity_check() {
 	assert!(
 		PMM.bitmap_test_bit(
@@ -0,0 +1,72 @@
+/// Fixed-capacity FIFO queue that needs no allocator, for buffering data
+/// between interrupt handlers and the rest of the kernel
+pub struct RingBuffer<T: Copy, const N: usize> {
+	buf: [T; N],
+	head: usize,
+	len: usize,
+}
+
+impl<T: Copy, const N: usize> RingBuffer<T, N> {
+	/// `empty` only fills the unused slots; it's never handed out
+	pub const fn new(empty: T) -> Self {
+		Self {
+			buf: [empty; N],
+			head: 0,
+			len: 0,
+		}
+	}
+
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	pub const fn is_full(&self) -> bool {
+		self.len == N
+	}
+
+	/// Append to the back, handing the value back if there's no room for it
+	pub fn push(&mut self, value: T) -> Result<(), T> {
+		if self.is_full() {
+			return Err(value);
+		}
+
+		self.buf[(self.head + self.len) % N] = value;
+		self.len += 1;
+		Ok(())
+	}
+
+	/// Append to the back, dropping the value at the front if there's no room
+	pub fn push_overwrite(&mut self, value: T) {
+		if self.is_full() {
+			self.pop();
+		}
+
+		let _ = self.push(value);
+	}
+
+	/// Pop from the front
+	pub fn pop(&mut self) -> Option<T> {
+		if self.is_empty() {
+			return None;
+		}
+
+		let value = self.buf[self.head];
+		self.head = (self.head + 1) % N;
+		self.len -= 1;
+		Some(value)
+	}
+
+	pub fn clear(&mut self) {
+		self.head = 0;
+		self.len = 0;
+	}
+
+	/// Iterate from front to back without removing anything
+	pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+		(0..self.len).map(move |i| self.buf[(self.head + i) % N])
+	}
+}
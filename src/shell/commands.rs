@@ -0,0 +1,634 @@
+//! Everything the shell can run
+
+use super::{Console, HISTORY};
+use crate::{
+	arch::cpu,
+	block::{self, cache, DeviceId},
+	boot::STIVALE_STRUCT,
+	drivers::pci::{self, Bar},
+	input::keymap,
+	log::dmesg,
+	mm::{pmm, IDENTITY_MAPPED, PAGE_SIZE},
+};
+use core::{fmt::Write, ptr, str};
+use stivale::memory::MemoryMapEntryType;
+
+const PEEK_DEFAULT_LEN: u64 = 64;
+const PEEK_MAX_LEN: u64 = 4096;
+
+const MAX_ARGS: usize = 16;
+
+pub enum Error {
+	/// Arguments didn't match the usage
+	Usage,
+	Invalid(&'static str),
+}
+
+struct Command {
+	name: &'static str,
+	usage: &'static str,
+	help: &'static str,
+	run: fn(&mut Console, &[&str]) -> Result<(), Error>,
+}
+
+const COMMANDS: &[Command] = &[
+	Command {
+		name: "help",
+		usage: "",
+		help: "List commands",
+		run: help,
+	},
+	Command {
+		name: "clear",
+		usage: "",
+		help: "Clear the screen",
+		run: clear,
+	},
+	Command {
+		name: "meminfo",
+		usage: "",
+		help: "Show how much physical memory is in use",
+		run: meminfo,
+	},
+	Command {
+		name: "mmap",
+		usage: "",
+		help: "Show the bootloader's memory map",
+		run: mmap,
+	},
+	Command {
+		name: "cpuinfo",
+		usage: "",
+		help: "Show what the CPU says about itself",
+		run: cpuinfo,
+	},
+	Command {
+		name: "lspci",
+		usage: "[-v]",
+		help: "List PCI devices, -v for BARs and capabilities",
+		run: lspci,
+	},
+	Command {
+		name: "lsblk",
+		usage: "",
+		help: "List block devices",
+		run: lsblk,
+	},
+	Command {
+		name: "sync",
+		usage: "[dev]",
+		help: "Write back cached blocks, or drop a device's",
+		run: sync,
+	},
+	Command {
+		name: "blkread",
+		usage: "<dev> <off> [len]",
+		help: "Dump part of a block device",
+		run: blkread,
+	},
+	Command {
+		name: "blkwrite",
+		usage: "<dev> <off> <byte>...",
+		help: "Write bytes to a block device",
+		run: blkwrite,
+	},
+	Command {
+		name: "dmesg",
+		usage: "[-c]",
+		help: "Show the kernel log, or clear it with -c",
+		run: dmesg,
+	},
+	Command {
+		name: "peek",
+		usage: "<addr> [len]",
+		help: "Dump physical memory",
+		run: peek,
+	},
+	Command {
+		name: "poke",
+		usage: "<addr> <byte>...",
+		help: "Write bytes to physical memory",
+		run: poke,
+	},
+	Command {
+		name: "history",
+		usage: "",
+		help: "Show previous commands",
+		run: history,
+	},
+	Command {
+		name: "keymap",
+		usage: "[name]",
+		help: "List keyboard layouts, or switch to one",
+		run: keymap,
+	},
+	Command {
+		name: "reboot",
+		usage: "",
+		help: "Restart the machine",
+		run: reboot,
+	},
+];
+
+/// Split a line into words and run the command it names
+pub fn run(out: &mut Console, line: &str) {
+	let mut args = [""; MAX_ARGS];
+	let mut count = 0;
+	for word in line.split_whitespace() {
+		if count == MAX_ARGS {
+			let _ = writeln!(out, "Too many arguments");
+			return;
+		}
+		args[count] = word;
+		count += 1;
+	}
+
+	let (name, args) = match args[..count].split_first() {
+		Some(split) => split,
+		None => return,
+	};
+	let command = match COMMANDS.iter().find(|c| c.name == *name) {
+		Some(command) => command,
+		None => {
+			let _ = writeln!(out, "{}: command not found", name);
+			return;
+		}
+	};
+
+	let _ = match (command.run)(out, args) {
+		Ok(()) => Ok(()),
+		Err(Error::Usage) => {
+			writeln!(out, "usage: {} {}", command.name, command.usage)
+		}
+		Err(Error::Invalid(why)) => writeln!(out, "{}: {}", command.name, why),
+	};
+}
+
+/// A number in hex with a leading `0x`, or in decimal
+fn parse_number(s: &str) -> Result<u64, Error> {
+	let parsed = match s.strip_prefix("0x") {
+		Some(hex) => u64::from_str_radix(hex, 16),
+		None => s.parse(),
+	};
+	parsed.map_err(|_| Error::Invalid("not a number"))
+}
+
+fn no_args(args: &[&str]) -> Result<(), Error> {
+	if args.is_empty() {
+		Ok(())
+	} else {
+		Err(Error::Usage)
+	}
+}
+
+fn help(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	for command in COMMANDS {
+		let _ = writeln!(
+			out,
+			"  {:<8} {:<21} {}",
+			command.name, command.usage, command.help
+		);
+	}
+	let _ = writeln!(
+		out,
+		"Lines can be edited with the arrow keys, Home, End and Delete. Up \
+		 and Down go through the history, Ctrl-U clears the line and Ctrl-L \
+		 clears the screen."
+	);
+	Ok(())
+}
+
+fn clear(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	let _ = out.write_str("\x1b[2J\x1b[H");
+	Ok(())
+}
+
+fn meminfo(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	let stats = pmm::stats();
+	let kib = |pages: usize| pages * PAGE_SIZE / 1024;
+	let used = stats.usable.saturating_sub(stats.free);
+
+	let _ = writeln!(out, "usable: {:>10} KiB", kib(stats.usable));
+	let _ = writeln!(out, "used:   {:>10} KiB", kib(used));
+	let _ = writeln!(out, "free:   {:>10} KiB", kib(stats.free));
+	Ok(())
+}
+
+fn mmap(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	let map = match STIVALE_STRUCT.inner().memory_map() {
+		Some(map) => map,
+		None => {
+			return Err(Error::Invalid("no memory map from the bootloader"))
+		}
+	};
+
+	for entry in map.iter() {
+		// the crate may know about more types than are listed here
+		#[allow(unreachable_patterns)]
+		let kind = match entry.entry_type() {
+			MemoryMapEntryType::Usable => "usable",
+			MemoryMapEntryType::Reserved => "reserved",
+			MemoryMapEntryType::AcpiReclaimable => "ACPI reclaimable",
+			MemoryMapEntryType::AcpiNvs => "ACPI NVS",
+			MemoryMapEntryType::BadMemory => "bad memory",
+			MemoryMapEntryType::BootloaderReclaimable => "bootloader",
+			MemoryMapEntryType::Kernel => "kernel",
+			_ => "unknown",
+		};
+
+		let _ = writeln!(
+			out,
+			"{:#018x}-{:#018x} {:>10} KiB  {}",
+			entry.start_address(),
+			entry.end_address(),
+			entry.size() / 1024,
+			kind
+		);
+	}
+	Ok(())
+}
+
+fn cpuinfo(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	let leaf0 = cpu::cpuid(0, 0);
+	let mut vendor = [0; 12];
+	vendor[..4].copy_from_slice(&leaf0.ebx.to_le_bytes());
+	vendor[4..8].copy_from_slice(&leaf0.edx.to_le_bytes());
+	vendor[8..].copy_from_slice(&leaf0.ecx.to_le_bytes());
+	let _ =
+		writeln!(out, "vendor:   {}", str::from_utf8(&vendor).unwrap_or("?"));
+
+	// the brand string is spread over three extended leaves
+	if cpu::cpuid(0x8000_0000, 0).eax >= 0x8000_0004 {
+		let mut brand = [0; 48];
+		for (i, leaf) in (0x8000_0002..=0x8000_0004).enumerate() {
+			let regs = cpu::cpuid(leaf, 0);
+			for (j, reg) in
+				[regs.eax, regs.ebx, regs.ecx, regs.edx].iter().enumerate()
+			{
+				let at = i * 16 + j * 4;
+				brand[at..at + 4].copy_from_slice(&reg.to_le_bytes());
+			}
+		}
+		let brand = str::from_utf8(&brand).unwrap_or("?");
+		let _ =
+			writeln!(out, "brand:    {}", brand.trim_end_matches('\0').trim());
+	}
+
+	let leaf1 = cpu::cpuid(1, 0);
+	let base_family = (leaf1.eax >> 8) & 0xF;
+	let mut family = base_family;
+	let mut model = (leaf1.eax >> 4) & 0xF;
+	if base_family == 0xF {
+		family += (leaf1.eax >> 20) & 0xFF;
+	}
+	if base_family == 0x6 || base_family == 0xF {
+		model |= ((leaf1.eax >> 16) & 0xF) << 4;
+	}
+	let _ = writeln!(
+		out,
+		"family:   {:#x}, model {:#x}, stepping {}",
+		family,
+		model,
+		leaf1.eax & 0xF
+	);
+
+	let _ = out.write_str("features:");
+	for &(name, reg, bit) in &[
+		("fpu", leaf1.edx, 0),
+		("tsc", leaf1.edx, 4),
+		("msr", leaf1.edx, 5),
+		("pae", leaf1.edx, 6),
+		("apic", leaf1.edx, 9),
+		("mtrr", leaf1.edx, 12),
+		("pat", leaf1.edx, 16),
+		("mmx", leaf1.edx, 23),
+		("sse", leaf1.edx, 25),
+		("sse2", leaf1.edx, 26),
+		("htt", leaf1.edx, 28),
+		("sse3", leaf1.ecx, 0),
+		("ssse3", leaf1.ecx, 9),
+		("sse4.1", leaf1.ecx, 19),
+		("sse4.2", leaf1.ecx, 20),
+		("x2apic", leaf1.ecx, 21),
+		("popcnt", leaf1.ecx, 23),
+		("aes", leaf1.ecx, 25),
+		("xsave", leaf1.ecx, 26),
+		("avx", leaf1.ecx, 28),
+		("rdrand", leaf1.ecx, 30),
+		("hypervisor", leaf1.ecx, 31),
+	] {
+		if reg & (1 << bit) != 0 {
+			let _ = write!(out, " {}", name);
+		}
+	}
+	let _ = writeln!(out);
+	Ok(())
+}
+
+fn lspci(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let verbose = match args {
+		[] => false,
+		["-v"] => true,
+		_ => return Err(Error::Usage),
+	};
+
+	for function in pci::functions() {
+		let _ = write!(
+			out,
+			"{} {:04x}:{:04x} {}",
+			function.address,
+			function.vendor_id,
+			function.device_id,
+			function.class.name()
+		);
+		if let Some(vendor) = pci::class::vendor_name(function.vendor_id) {
+			let _ = write!(out, " ({})", vendor);
+		}
+		let _ = writeln!(out);
+
+		if verbose {
+			lspci_details(out, &function);
+		}
+	}
+	Ok(())
+}
+
+fn lspci_details(out: &mut Console, function: &pci::Function) {
+	let class = function.class;
+	let _ = writeln!(
+		out,
+		"    class {:02x}:{:02x}:{:02x}, revision {:#x}",
+		class.class, class.subclass, class.prog_if, function.revision
+	);
+	if let Some(driver) = pci::driver(function.address) {
+		let _ = writeln!(out, "    driver: {}", driver);
+	}
+
+	for (index, bar) in function.bars() {
+		let _ = match bar {
+			Bar::Io { port, size } => {
+				writeln!(
+					out,
+					"    BAR{}: I/O at {:#x} ({} bytes)",
+					index, port, size
+				)
+			}
+			Bar::Memory {
+				address,
+				size,
+				prefetchable,
+				wide,
+			} => writeln!(
+				out,
+				"    BAR{}: memory at {:#x} ({} KiB, {} bit{})",
+				index,
+				address,
+				size / 1024,
+				if wide { 64 } else { 32 },
+				if prefetchable { ", prefetchable" } else { "" }
+			),
+		};
+	}
+
+	for capability in function.capabilities() {
+		let _ = writeln!(
+			out,
+			"    capability {:#04x} at {:#x}: {}",
+			capability.id,
+			capability.offset,
+			capability.name()
+		);
+	}
+}
+
+fn lsblk(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	for (_, device) in block::devices() {
+		let bytes = device.sectors() * device.sector_size() as u64;
+		let _ = writeln!(
+			out,
+			"{:<8} {:>10} sectors of {:>4} bytes {:>8} KiB{}",
+			device.name(),
+			device.sectors(),
+			device.sector_size(),
+			bytes / 1024,
+			if device.read_only() {
+				"  read only"
+			} else {
+				""
+			}
+		);
+	}
+
+	let (cached, dirty) = cache::usage();
+	let _ = writeln!(out, "cache: {} blocks, {} dirty", cached, dirty);
+	Ok(())
+}
+
+fn sync(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let result = match args {
+		[] => cache::sync(),
+		[name] => cache::invalidate(parse_device(name)?),
+		_ => return Err(Error::Usage),
+	};
+
+	if let Err(e) = result {
+		let _ = writeln!(out, "sync: {:?}", e);
+	}
+	Ok(())
+}
+
+fn parse_device(name: &str) -> Result<DeviceId, Error> {
+	block::by_name(name).ok_or(Error::Invalid("no such device"))
+}
+
+fn blkread(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let (id, offset, len) = match args {
+		[name, offset] => {
+			(parse_device(name)?, parse_number(offset)?, PEEK_DEFAULT_LEN)
+		}
+		[name, offset, len] => (
+			parse_device(name)?,
+			parse_number(offset)?,
+			parse_number(len)?,
+		),
+		_ => return Err(Error::Usage),
+	};
+	if len > PEEK_MAX_LEN {
+		return Err(Error::Invalid("can only dump 4096 bytes at a time"));
+	}
+	let end = offset.checked_add(len).ok_or(Error::Invalid("too far"))?;
+
+	for line in (offset..end).step_by(16) {
+		let mut bytes = [0; 16];
+		let bytes = &mut bytes[..(end - line).min(16) as usize];
+
+		if let Err(e) = cache::read(id, line, bytes) {
+			let _ = writeln!(out, "blkread: {:?}", e);
+			break;
+		}
+		dump_line(out, line, bytes);
+	}
+	Ok(())
+}
+
+fn blkwrite(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let (id, offset, bytes) = match args {
+		[name, offset, bytes @ ..] if !bytes.is_empty() => {
+			(parse_device(name)?, parse_number(offset)?, bytes)
+		}
+		_ => return Err(Error::Usage),
+	};
+
+	let mut values = [0; MAX_ARGS];
+	let values = parse_bytes(bytes, &mut values)?;
+
+	if let Err(e) = cache::write(id, offset, values) {
+		let _ = writeln!(out, "blkwrite: {:?}", e);
+	}
+	Ok(())
+}
+
+fn dmesg(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	match args {
+		[] => {
+			let _ = dmesg::dump(out);
+		}
+		["-c"] => dmesg::clear(),
+		_ => return Err(Error::Usage),
+	}
+	Ok(())
+}
+
+fn check_range(addr: u64, len: u64) -> Result<(), Error> {
+	match addr.checked_add(len) {
+		Some(end) if end <= IDENTITY_MAPPED => Ok(()),
+		_ => Err(Error::Invalid("only the first 4 GiB is mapped")),
+	}
+}
+
+fn peek(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let (addr, len) = match args {
+		[addr] => (parse_number(addr)?, PEEK_DEFAULT_LEN),
+		[addr, len] => (parse_number(addr)?, parse_number(len)?),
+		_ => return Err(Error::Usage),
+	};
+	if len > PEEK_MAX_LEN {
+		return Err(Error::Invalid("can only dump 4096 bytes at a time"));
+	}
+	check_range(addr, len)?;
+
+	for line in (addr..addr + len).step_by(16) {
+		let count = (addr + len - line).min(16);
+		let mut bytes = [0; 16];
+		for (i, byte) in bytes.iter_mut().take(count as usize).enumerate() {
+			// SAFETY: checked to be inside the identity map. Device memory
+			// could still mind being read, which is the user's problem.
+			*byte =
+				unsafe { ptr::read_volatile((line as usize + i) as *const u8) };
+		}
+
+		dump_line(out, line, &bytes[..count as usize]);
+	}
+	Ok(())
+}
+
+/// Up to 16 bytes starting at `address`, in hex and then as text
+fn dump_line(out: &mut Console, address: u64, bytes: &[u8]) {
+	let _ = write!(out, "{:#010x}: ", address);
+	for i in 0..16 {
+		let _ = match bytes.get(i) {
+			Some(byte) => write!(out, "{:02x} ", byte),
+			None => out.write_str("   "),
+		};
+	}
+	for &byte in bytes {
+		let c = if byte.is_ascii_graphic() {
+			byte as char
+		} else {
+			'.'
+		};
+		let _ = out.write_char(c);
+	}
+	let _ = writeln!(out);
+}
+
+/// Parse each argument as a byte into `values`, returning the used part
+fn parse_bytes<'a>(
+	args: &[&str],
+	values: &'a mut [u8; MAX_ARGS],
+) -> Result<&'a [u8], Error> {
+	for (value, arg) in values.iter_mut().zip(args) {
+		let n = parse_number(arg)?;
+		if n > 0xFF {
+			return Err(Error::Invalid("bytes go up to 0xff"));
+		}
+		*value = n as u8;
+	}
+	Ok(&values[..args.len()])
+}
+
+fn poke(_out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	let (addr, bytes) = match args.split_first() {
+		Some((addr, bytes)) if !bytes.is_empty() => {
+			(parse_number(addr)?, bytes)
+		}
+		_ => return Err(Error::Usage),
+	};
+	check_range(addr, bytes.len() as u64)?;
+
+	let mut values = [0; MAX_ARGS];
+	let values = parse_bytes(bytes, &mut values)?;
+
+	for (i, &value) in values.iter().enumerate() {
+		// SAFETY: not at all, that's the point of poke. It's at least inside
+		// the identity map.
+		unsafe { ptr::write_volatile((addr as usize + i) as *mut u8, value) };
+	}
+	Ok(())
+}
+
+fn history(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	for (i, line) in HISTORY.lock().iter().enumerate() {
+		let _ = writeln!(out, "{:>4}  {}", i + 1, line.as_str());
+	}
+	Ok(())
+}
+
+fn keymap(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	match args {
+		[] => {
+			let current = keymap::current();
+			for keymap in keymap::KEYMAPS.iter() {
+				let marker = if ptr::eq(*keymap, current) { '*' } else { ' ' };
+				let _ = writeln!(out, "{} {}", marker, keymap.name);
+			}
+		}
+		[name] => match keymap::by_name(name) {
+			Some(keymap) => keymap::set_keymap(keymap),
+			None => return Err(Error::Invalid("no such keymap")),
+		},
+		_ => return Err(Error::Usage),
+	}
+	Ok(())
+}
+
+fn reboot(out: &mut Console, args: &[&str]) -> Result<(), Error> {
+	no_args(args)?;
+
+	let _ = writeln!(out, "Rebooting...");
+	cpu::reboot()
+}
@@ -0,0 +1,198 @@
+//! Editing the line being typed at the prompt. Everything is redrawn with
+//! ANSI escapes, which the framebuffer terminal and serial terminals both
+//! understand. Only ASCII is accepted, so every character is one column.
+
+use crate::ring_buffer::RingBuffer;
+use core::{fmt::Write, str};
+
+pub const LINE_MAX: usize = 128;
+pub const HISTORY_SIZE: usize = 32;
+
+#[derive(Clone, Copy)]
+pub struct Line {
+	buf: [u8; LINE_MAX],
+	len: usize,
+}
+
+impl Line {
+	pub const EMPTY: Self = Self {
+		buf: [0; LINE_MAX],
+		len: 0,
+	};
+
+	pub fn as_str(&self) -> &str {
+		str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+	}
+}
+
+pub type History = RingBuffer<Line, HISTORY_SIZE>;
+
+pub enum Edit {
+	Insert(char),
+	Backspace,
+	Delete,
+	Left,
+	Right,
+	Home,
+	End,
+	/// Go back a line in the history
+	Previous,
+	/// Go forward a line in the history, ending up at what was being typed
+	Next,
+	/// Throw away the whole line
+	Kill,
+}
+
+pub struct Editor {
+	line: Line,
+	cursor: usize,
+	// how far back in the history the line came from, newest being 0
+	browsing: Option<usize>,
+	// what was typed before browsing the history
+	draft: Line,
+}
+
+impl Editor {
+	pub const fn new() -> Self {
+		Self {
+			line: Line::EMPTY,
+			cursor: 0,
+			browsing: None,
+			draft: Line::EMPTY,
+		}
+	}
+
+	pub const fn line(&self) -> Line {
+		self.line
+	}
+
+	pub fn apply(
+		&mut self,
+		edit: Edit,
+		history: &History,
+		out: &mut dyn Write,
+	) {
+		let _ = match edit {
+			Edit::Insert(c) => self.insert(c, out),
+			Edit::Backspace if self.cursor > 0 => {
+				self.cursor -= 1;
+				let _ = out.write_char('\x08');
+				self.remove(out)
+			}
+			Edit::Delete if self.cursor < self.line.len => self.remove(out),
+			Edit::Left if self.cursor > 0 => {
+				self.cursor -= 1;
+				out.write_str("\x1b[D")
+			}
+			Edit::Right if self.cursor < self.line.len => {
+				self.cursor += 1;
+				out.write_str("\x1b[C")
+			}
+			Edit::Home => self.move_to(0, out),
+			Edit::End => self.move_to(self.line.len, out),
+			Edit::Previous => {
+				let next = self.browsing.map_or(0, |back| back + 1);
+				if next < history.len() {
+					if self.browsing.is_none() {
+						self.draft = self.line;
+					}
+					self.browsing = Some(next);
+					self.replace(from_history(history, next), out)
+				} else {
+					Ok(())
+				}
+			}
+			Edit::Next => match self.browsing {
+				Some(0) => {
+					self.browsing = None;
+					self.replace(self.draft, out)
+				}
+				Some(back) => {
+					self.browsing = Some(back - 1);
+					self.replace(from_history(history, back - 1), out)
+				}
+				None => Ok(()),
+			},
+			Edit::Kill => self.replace(Line::EMPTY, out),
+			_ => Ok(()),
+		};
+	}
+
+	/// Write out the whole line again, after the prompt has been
+	pub fn redraw(&self, out: &mut dyn Write) {
+		let _ = out.write_str(self.line.as_str());
+		let _ = self.move_back(self.line.len - self.cursor, out);
+	}
+
+	fn insert(&mut self, c: char, out: &mut dyn Write) -> core::fmt::Result {
+		if !c.is_ascii() || self.line.len == LINE_MAX {
+			return Ok(());
+		}
+
+		let Line { buf, len } = &mut self.line;
+		buf.copy_within(self.cursor..*len, self.cursor + 1);
+		buf[self.cursor] = c as u8;
+		*len += 1;
+
+		// everything after the cursor shifts right
+		out.write_str(self.tail(self.cursor))?;
+		self.cursor += 1;
+		self.move_back(self.line.len - self.cursor, out)
+	}
+
+	/// Take out the character under the cursor
+	fn remove(&mut self, out: &mut dyn Write) -> core::fmt::Result {
+		let Line { buf, len } = &mut self.line;
+		buf.copy_within(self.cursor + 1..*len, self.cursor);
+		*len -= 1;
+
+		out.write_str(self.tail(self.cursor))?;
+		out.write_char(' ')?;
+		self.move_back(self.line.len - self.cursor + 1, out)
+	}
+
+	fn replace(
+		&mut self,
+		line: Line,
+		out: &mut dyn Write,
+	) -> core::fmt::Result {
+		self.move_to(0, out)?;
+		self.line = line;
+		self.cursor = line.len;
+
+		out.write_str(line.as_str())?;
+		out.write_str("\x1b[K")
+	}
+
+	fn move_to(&mut self, to: usize, out: &mut dyn Write) -> core::fmt::Result {
+		let from = core::mem::replace(&mut self.cursor, to);
+
+		if to < from {
+			self.move_back(from - to, out)
+		} else if to > from {
+			write!(out, "\x1b[{}C", to - from)
+		} else {
+			Ok(())
+		}
+	}
+
+	fn move_back(&self, by: usize, out: &mut dyn Write) -> core::fmt::Result {
+		if by > 0 {
+			write!(out, "\x1b[{}D", by)
+		} else {
+			Ok(())
+		}
+	}
+
+	fn tail(&self, from: usize) -> &str {
+		str::from_utf8(&self.line.buf[from..self.line.len]).unwrap_or("")
+	}
+}
+
+/// The line `back` entries before the newest
+fn from_history(history: &History, back: usize) -> Line {
+	history
+		.iter()
+		.nth(history.len() - 1 - back)
+		.unwrap_or(Line::EMPTY)
+}
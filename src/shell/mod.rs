@@ -0,0 +1,216 @@
+//! Kernel debug shell on the kernel console. It reads keyboard and serial
+//! input through the input subsystem, and its output goes to the console and
+//! (when mirroring) to serial, but not to dmesg.
+
+mod commands;
+mod editor;
+
+use crate::{
+	arch::cpu,
+	gfx::pointer,
+	input::{self, EventKind, KeyCode, Subscriber},
+	stdio::{
+		serial::{self, SERIAL_WRITER},
+		terminal,
+	},
+};
+use core::fmt::{self, Write};
+use editor::{Edit, Editor, History, Line};
+use spin::Mutex;
+
+const PROMPT: &str = "\x1b[1;32m>\x1b[0m ";
+/// Lines of scrollback one click of the mouse wheel moves through
+const WHEEL_LINES: isize = 3;
+
+const CTRL_C: char = '\x03';
+const CTRL_L: char = '\x0c';
+const CTRL_U: char = '\x15';
+
+static HISTORY: Mutex<History> = Mutex::new(History::new(Line::EMPTY));
+
+/// Writes to the kernel console, and to serial if console output is
+/// mirrored there
+pub struct Console;
+
+impl Write for Console {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		terminal::with_console(|console| {
+			let _ = console.write_str(s);
+		});
+
+		if serial::mirroring() {
+			cpu::without_interrupts(|| {
+				SERIAL_WRITER.lock().send_bytes(s.as_bytes());
+			});
+		}
+		Ok(())
+	}
+}
+
+/// A key press the shell cares about
+#[derive(Clone, Copy)]
+enum Input {
+	Char(char),
+	Key(KeyCode),
+}
+
+/// Turns the escape sequences serial terminals send for arrows and such
+/// back into keys
+#[derive(Clone, Copy)]
+enum Escape {
+	None,
+	Escape,
+	// after "ESC [", with the number read so far
+	Csi(u16),
+}
+
+impl Escape {
+	fn feed(&mut self, c: char) -> Option<Input> {
+		match (*self, c) {
+			(Self::None, '\x1b') => {
+				*self = Self::Escape;
+				None
+			}
+			(Self::None, c) => Some(Input::Char(c)),
+			(Self::Escape, '[') => {
+				*self = Self::Csi(0);
+				None
+			}
+			(Self::Escape, c) => {
+				*self = Self::None;
+				Some(Input::Char(c))
+			}
+			(Self::Csi(n), '0'..='9') => {
+				let digit = c as u16 - '0' as u16;
+				*self = Self::Csi(n.saturating_mul(10).saturating_add(digit));
+				None
+			}
+			(Self::Csi(n), c) => {
+				*self = Self::None;
+				let key = match (c, n) {
+					('A', _) => KeyCode::Up,
+					('B', _) => KeyCode::Down,
+					('C', _) => KeyCode::Right,
+					('D', _) => KeyCode::Left,
+					('H', _) | ('~', 1) | ('~', 7) => KeyCode::Home,
+					('F', _) | ('~', 4) | ('~', 8) => KeyCode::End,
+					('~', 3) => KeyCode::Delete,
+					_ => return None,
+				};
+				Some(Input::Key(key))
+			}
+		}
+	}
+}
+
+struct Shell {
+	events: Subscriber,
+	escape: Escape,
+	out: Console,
+}
+
+impl Shell {
+	/// Wait for the next key press, taking care of everything else the
+	/// console does with input in the meantime
+	fn next_input(&mut self) -> Input {
+		loop {
+			match self.events.wait().kind {
+				EventKind::KeyDown { code, modifiers, c } => {
+					if terminal::handle_hotkey(code, modifiers) {
+						continue;
+					}
+					return match c {
+						Some(c) => Input::Char(c),
+						None => Input::Key(code),
+					};
+				}
+				EventKind::Char(c) => {
+					if let Some(input) = self.escape.feed(c) {
+						return input;
+					}
+				}
+				EventKind::RelativeMotion { dx, dy } => {
+					pointer::move_by(dx, dy)
+				}
+				EventKind::Wheel(clicks) => {
+					terminal::scroll_active(-(clicks as isize) * WHEEL_LINES)
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn read_line(&mut self) -> Line {
+		let _ = self.out.write_str(PROMPT);
+		let mut editor = Editor::new();
+
+		loop {
+			let edit = match self.next_input() {
+				Input::Char('\n') => {
+					let _ = self.out.write_char('\n');
+					return editor.line();
+				}
+				Input::Char(CTRL_C) => {
+					let _ = self.out.write_str("^C\n");
+					return Line::EMPTY;
+				}
+				Input::Char(CTRL_L) => {
+					let _ = write!(self.out, "\x1b[2J\x1b[H{}", PROMPT);
+					editor.redraw(&mut self.out);
+					continue;
+				}
+				Input::Char(CTRL_U) => Edit::Kill,
+				Input::Char('\x08') => Edit::Backspace,
+				Input::Char(c) if c == ' ' || c.is_ascii_graphic() => {
+					Edit::Insert(c)
+				}
+				Input::Key(KeyCode::Left) => Edit::Left,
+				Input::Key(KeyCode::Right) => Edit::Right,
+				Input::Key(KeyCode::Up) => Edit::Previous,
+				Input::Key(KeyCode::Down) => Edit::Next,
+				Input::Key(KeyCode::Home) => Edit::Home,
+				Input::Key(KeyCode::End) => Edit::End,
+				Input::Key(KeyCode::Delete) => Edit::Delete,
+				_ => continue,
+			};
+
+			editor.apply(edit, &HISTORY.lock(), &mut self.out);
+		}
+	}
+}
+
+/// Run the shell forever
+pub fn run() -> ! {
+	let events =
+		input::subscribe().expect("No room for the shell to read input!");
+	// the shell does its own echoing
+	serial::set_echo(false);
+
+	let mut shell = Shell {
+		events,
+		escape: Escape::None,
+		out: Console,
+	};
+	let _ = writeln!(shell.out, "Type `help` to see what the shell can do.");
+
+	loop {
+		let line = shell.read_line();
+		let text = line.as_str().trim();
+		if text.is_empty() {
+			continue;
+		}
+
+		{
+			let mut history = HISTORY.lock();
+			let repeated = history
+				.iter()
+				.last()
+				.map_or(false, |last| last.as_str().trim() == text);
+			if !repeated {
+				history.push_overwrite(line);
+			}
+		}
+
+		commands::run(&mut shell.out, text);
+	}
+}
@@ -0,0 +1,303 @@
+use super::framebuffer::Pixel;
+
+const ESC: char = '\x1b';
+const MAX_PARAMS: usize = 16;
+
+/// A color as given by an SGR sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Color {
+	/// Whatever the terminal's default foreground/background is
+	Default,
+	/// One of the 256 xterm palette entries; 0-15 are the classic colors
+	Indexed(u8),
+	Rgb(u8, u8, u8),
+}
+
+// xterm's take on the 16 classic colors
+const PALETTE: [(u8, u8, u8); 16] = [
+	(0, 0, 0),
+	(205, 0, 0),
+	(0, 205, 0),
+	(205, 205, 0),
+	(0, 0, 238),
+	(205, 0, 205),
+	(0, 205, 205),
+	(229, 229, 229),
+	(127, 127, 127),
+	(255, 0, 0),
+	(0, 255, 0),
+	(255, 255, 0),
+	(92, 92, 255),
+	(255, 0, 255),
+	(0, 255, 255),
+	(255, 255, 255),
+];
+
+impl Color {
+	/// Resolve to a pixel, using `default` for `Color::Default`
+	pub fn to_pixel(self, default: Pixel) -> Pixel {
+		match self {
+			Self::Default => default,
+			Self::Rgb(r, g, b) => Pixel::new(r, g, b),
+			Self::Indexed(i @ 0..=15) => {
+				let (r, g, b) = PALETTE[i as usize];
+				Pixel::new(r, g, b)
+			}
+			// 6x6x6 color cube
+			Self::Indexed(i @ 16..=231) => {
+				let i = i - 16;
+				let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+				Pixel::new(level(i / 36), level(i / 6 % 6), level(i % 6))
+			}
+			// grayscale ramp
+			Self::Indexed(i) => {
+				let v = 8 + (i - 232) * 10;
+				Pixel::new(v, v, v)
+			}
+		}
+	}
+}
+
+/// One attribute out of an SGR ("select graphic rendition") sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Graphic {
+	Reset,
+	Bold(bool),
+	Foreground(Color),
+	Background(Color),
+}
+
+/// Which part of a line or the screen to erase, relative to the cursor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Erase {
+	ToEnd,
+	ToStart,
+	All,
+}
+
+/// Numeric parameters of a control sequence
+#[derive(Clone, Copy, Debug)]
+pub struct Params {
+	values: [u16; MAX_PARAMS],
+	len: usize,
+}
+
+impl Params {
+	const fn new() -> Self {
+		Self {
+			values: [0; MAX_PARAMS],
+			len: 0,
+		}
+	}
+
+	/// Parameter `i`, with omitted and zero parameters meaning `default`
+	fn get(&self, i: usize, default: u16) -> u16 {
+		match self.values[..self.len].get(i) {
+			Some(&v) if v != 0 => v,
+			_ => default,
+		}
+	}
+
+	/// Decode the parameters of an SGR sequence into attributes
+	pub fn graphics(&self) -> Graphics {
+		Graphics {
+			params: self.values,
+			len: self.len.max(1),
+			i: 0,
+		}
+	}
+}
+
+pub struct Graphics {
+	params: [u16; MAX_PARAMS],
+	len: usize,
+	i: usize,
+}
+
+impl Graphics {
+	fn next_param(&mut self) -> Option<u16> {
+		let param = self.params[..self.len].get(self.i).copied();
+		self.i += 1;
+		param
+	}
+
+	/// Parse the rest of a `38;...` or `48;...` extended color
+	fn extended_color(&mut self) -> Option<Color> {
+		match self.next_param()? {
+			5 => Some(Color::Indexed(self.next_param()? as u8)),
+			2 => {
+				let r = self.next_param()? as u8;
+				let g = self.next_param()? as u8;
+				let b = self.next_param()? as u8;
+				Some(Color::Rgb(r, g, b))
+			}
+			_ => None,
+		}
+	}
+}
+
+impl Iterator for Graphics {
+	type Item = Graphic;
+
+	fn next(&mut self) -> Option<Graphic> {
+		loop {
+			let graphic = match self.next_param()? {
+				0 => Graphic::Reset,
+				1 => Graphic::Bold(true),
+				22 => Graphic::Bold(false),
+				p @ 30..=37 => {
+					Graphic::Foreground(Color::Indexed(p as u8 - 30))
+				}
+				38 => Graphic::Foreground(self.extended_color()?),
+				39 => Graphic::Foreground(Color::Default),
+				p @ 40..=47 => {
+					Graphic::Background(Color::Indexed(p as u8 - 40))
+				}
+				48 => Graphic::Background(self.extended_color()?),
+				49 => Graphic::Background(Color::Default),
+				p @ 90..=97 => {
+					Graphic::Foreground(Color::Indexed(p as u8 - 90 + 8))
+				}
+				p @ 100..=107 => {
+					Graphic::Background(Color::Indexed(p as u8 - 100 + 8))
+				}
+				// unsupported attributes are skipped
+				_ => continue,
+			};
+
+			return Some(graphic);
+		}
+	}
+}
+
+/// Something the terminal should do in response to its input
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+	/// A printable character or a C0 control like `\n`
+	Print(char),
+	Sgr(Params),
+	CursorUp(u16),
+	CursorDown(u16),
+	CursorForward(u16),
+	CursorBack(u16),
+	/// Zero-based position to move the cursor to
+	CursorPosition {
+		row: u16,
+		col: u16,
+	},
+	EraseInDisplay(Erase),
+	EraseInLine(Erase),
+	SaveCursor,
+	RestoreCursor,
+}
+
+enum State {
+	Ground,
+	Escape,
+	Csi,
+}
+
+/// Incremental VT100/ANSI escape sequence parser. Feed it one character at a
+/// time and act on whatever it returns.
+pub struct Parser {
+	state: State,
+	params: Params,
+	// whether the current parameter has any digits yet
+	has_digits: bool,
+}
+
+impl Parser {
+	pub const fn new() -> Self {
+		Self {
+			state: State::Ground,
+			params: Params::new(),
+			has_digits: false,
+		}
+	}
+
+	pub fn advance(&mut self, c: char) -> Option<Action> {
+		match self.state {
+			State::Ground => match c {
+				ESC => {
+					self.state = State::Escape;
+					None
+				}
+				c => Some(Action::Print(c)),
+			},
+			State::Escape => {
+				self.state = State::Ground;
+
+				match c {
+					'[' => {
+						self.state = State::Csi;
+						self.params = Params::new();
+						self.has_digits = false;
+						None
+					}
+					'7' => Some(Action::SaveCursor),
+					'8' => Some(Action::RestoreCursor),
+					_ => None,
+				}
+			}
+			State::Csi => match c {
+				'0'..='9' => {
+					if !self.has_digits && self.params.len < MAX_PARAMS {
+						self.params.len += 1;
+					}
+					self.has_digits = true;
+
+					let param = &mut self.params.values[self.params.len - 1];
+					*param = param
+						.saturating_mul(10)
+						.saturating_add(c as u16 - '0' as u16);
+					None
+				}
+				';' => {
+					// an empty parameter still takes up a slot
+					if !self.has_digits && self.params.len < MAX_PARAMS {
+						self.params.len += 1;
+					}
+					self.has_digits = false;
+					None
+				}
+				// private markers and intermediates, e.g. `ESC[?25l`
+				'?' | ' '..='/' => None,
+				'@'..='~' => {
+					self.state = State::Ground;
+					self.dispatch(c)
+				}
+				// anything else aborts the sequence
+				_ => {
+					self.state = State::Ground;
+					None
+				}
+			},
+		}
+	}
+
+	fn dispatch(&self, c: char) -> Option<Action> {
+		let params = &self.params;
+		let erase = match params.get(0, 0) {
+			1 => Erase::ToStart,
+			2 | 3 => Erase::All,
+			_ => Erase::ToEnd,
+		};
+
+		match c {
+			'm' => Some(Action::Sgr(*params)),
+			'A' => Some(Action::CursorUp(params.get(0, 1))),
+			'B' => Some(Action::CursorDown(params.get(0, 1))),
+			'C' => Some(Action::CursorForward(params.get(0, 1))),
+			'D' => Some(Action::CursorBack(params.get(0, 1))),
+			'H' | 'f' => Some(Action::CursorPosition {
+				row: params.get(0, 1) - 1,
+				col: params.get(1, 1) - 1,
+			}),
+			'J' => Some(Action::EraseInDisplay(erase)),
+			'K' => Some(Action::EraseInLine(erase)),
+			's' => Some(Action::SaveCursor),
+			'u' => Some(Action::RestoreCursor),
+			_ => None,
+		}
+	}
+}
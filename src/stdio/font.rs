@@ -0,0 +1,89 @@
+//! The console font. A PSF font is parsed at runtime (the bundled Zap font
+//! unless a boot module replaces it) and its unicode table is flattened into
+//! a sorted array so drawing doesn't have to walk it for every character.
+
+use zap_font::{Font, Glyph};
+
+/// Drawn for characters the font has no glyph for
+const REPLACEMENT: char = '\u{FFFD}';
+/// Most unicode table entries kept around for fast lookup; anything past
+/// this falls back to walking the table
+const MAX_MAPPINGS: usize = 1024;
+
+pub struct ConsoleFont {
+	font: Font<'static>,
+	// (code point, glyph index), sorted by code point
+	map: [(u32, u32); MAX_MAPPINGS],
+	len: usize,
+	overflowed: bool,
+	fallback: usize,
+}
+
+impl ConsoleFont {
+	pub fn new(font: Font<'static>) -> Self {
+		let mut this = Self {
+			font,
+			map: [(0, 0); MAX_MAPPINGS],
+			len: 0,
+			overflowed: false,
+			fallback: 0,
+		};
+
+		if font.has_unicode_table() {
+			for (c, index) in font.mappings() {
+				if this.len == MAX_MAPPINGS {
+					this.overflowed = true;
+					break;
+				}
+
+				this.map[this.len] = (c as u32, index as u32);
+				this.len += 1;
+			}
+
+			this.map[..this.len].sort_unstable_by_key(|&(c, _)| c);
+		}
+
+		this.fallback = this
+			.lookup(REPLACEMENT)
+			.or_else(|| this.lookup('?'))
+			.unwrap_or(0);
+		this
+	}
+
+	/// The bundled Zap Light 8x16 font
+	pub fn builtin() -> Self {
+		Self::new(
+			Font::parse(zap_font::ZAP_LIGHT_16)
+				.expect("Builtin console font is broken!"),
+		)
+	}
+
+	/// Width and height of a glyph in pixels
+	pub const fn dimensions(&self) -> (usize, usize) {
+		(self.font.width(), self.font.height())
+	}
+
+	fn lookup(&self, c: char) -> Option<usize> {
+		if !self.font.has_unicode_table() {
+			return self.font.lookup(c);
+		}
+
+		let map = &self.map[..self.len];
+		match map.binary_search_by_key(&(c as u32), |&(c, _)| c) {
+			Ok(i) => Some(map[i].1 as usize),
+			Err(_) if self.overflowed => self.font.lookup(c),
+			Err(_) => None,
+		}
+	}
+
+	/// Bitmap for `c`, falling back to a replacement glyph if the font
+	/// doesn't have it
+	pub fn glyph(&self, c: char) -> Glyph<'static> {
+		let index = self.lookup(c).unwrap_or(self.fallback);
+
+		self.font
+			.glyph(index)
+			.or_else(|| self.font.glyph(0))
+			.expect("Console font has no glyphs!")
+	}
+}
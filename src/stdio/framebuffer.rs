@@ -1,17 +1,20 @@
-use crate::{polyfill, STIVALE_STRUCT};
-use core::fmt::{self, Write};
+use super::{font::ConsoleFont, terminal::Cell};
+use crate::{
+	mm::{pmm, PAGE_SIZE},
+	polyfill, STIVALE_STRUCT,
+};
 use lazy_static::lazy_static;
 use spin::Mutex;
 use stivale::framebuffer::FramebufferTag;
+use zap_font::Font;
 
-#[cfg(FONT = "LINUX")]
-use linux_console_font::{FONT, FONT_DIMENSIONS};
-#[cfg(FONT = "ZAP")]
-use zap_font::{FONT, FONT_DIMENSIONS};
-
+#[derive(Clone, Copy)]
 pub enum CommonColors {
 	Red,
 	Green,
+	Yellow,
+	Blue,
+	Magenta,
 	Cyan,
 	White,
 	Black,
@@ -22,6 +25,9 @@ impl From<CommonColors> for Pixel {
 		match c {
 			CommonColors::Red => Self::new(255, 0, 0),
 			CommonColors::Green => Self::new(0, 255, 0),
+			CommonColors::Yellow => Self::new(255, 255, 0),
+			CommonColors::Blue => Self::new(0, 0, 255),
+			CommonColors::Magenta => Self::new(255, 0, 255),
 			CommonColors::Cyan => Self::new(0, 255, 255),
 			CommonColors::White => Self::new(255, 255, 255),
 			CommonColors::Black => Self::new(0, 0, 0),
@@ -29,6 +35,7 @@ impl From<CommonColors> for Pixel {
 	}
 }
 
+#[derive(Clone, Copy)]
 pub struct Pixel {
 	r: u8,
 	g: u8,
@@ -36,12 +43,13 @@ pub struct Pixel {
 }
 
 impl Pixel {
-	fn as_bits(&self) -> u32 {
-		(self.r as u32) << 16 | (self.g as u32) << 8 | self.b as u32
+	pub const fn new(r: u8, g: u8, b: u8) -> Self {
+		Self { r, g, b }
 	}
 
-	pub fn new(r: u8, g: u8, b: u8) -> Self {
-		Self { r, g, b }
+	/// The color as (red, green, blue)
+	pub const fn channels(self) -> (u8, u8, u8) {
+		(self.r, self.g, self.b)
 	}
 
 	pub fn set(&mut self, to: impl Into<Pixel>) {
@@ -64,108 +72,550 @@ impl Default for Pixel {
 	}
 }
 
-pub struct FramebufferWriter {
+/// Where one color channel lives inside a pixel
+#[derive(Clone, Copy)]
+struct Channel {
+	size: u8,
+	shift: u8,
+}
+
+impl Channel {
+	/// Scale an 8-bit intensity down (or up) to the channel's width and move
+	/// it into place
+	fn encode(self, value: u8) -> u32 {
+		let value = value as u32;
+		let scaled = if self.size <= 8 {
+			value >> (8 - self.size)
+		} else {
+			value << (self.size - 8)
+		};
+
+		scaled << self.shift
+	}
+
+	/// Pull the channel back out of a pixel as an 8-bit intensity
+	fn decode(self, raw: u32) -> u8 {
+		let value = (raw >> self.shift) & ((1u64 << self.size) - 1) as u32;
+
+		if self.size <= 8 {
+			// repeat the top bits in the bottom ones so full intensity is
+			// still 255
+			let scaled = value << (8 - self.size);
+			(scaled | scaled >> self.size) as u8
+		} else {
+			(value >> (self.size - 8)) as u8
+		}
+	}
+}
+
+/// How pixels are laid out in video memory, as reported by the bootloader
+#[derive(Clone, Copy)]
+struct PixelFormat {
+	bytes_per_pixel: usize,
+	red: Channel,
+	green: Channel,
+	blue: Channel,
+}
+
+impl PixelFormat {
+	fn from_tag(tag: &FramebufferTag) -> Self {
+		let channel = |size, shift| Channel { size, shift };
+
+		// some bootloaders leave the masks empty; assume xRGB in that case
+		if tag.red_mask_size() == 0 {
+			return Self {
+				bytes_per_pixel: (tag.bpp() / 8) as usize,
+				red: channel(8, 16),
+				green: channel(8, 8),
+				blue: channel(8, 0),
+			};
+		}
+
+		Self {
+			bytes_per_pixel: (tag.bpp() as usize + 7) / 8,
+			red: channel(tag.red_mask_size(), tag.red_mask_shift()),
+			green: channel(tag.green_mask_size(), tag.green_mask_shift()),
+			blue: channel(tag.blue_mask_size(), tag.blue_mask_shift()),
+		}
+	}
+
+	fn encode(&self, pixel: Pixel) -> u32 {
+		self.red.encode(pixel.r)
+			| self.green.encode(pixel.g)
+			| self.blue.encode(pixel.b)
+	}
+
+	fn decode(&self, raw: u32) -> Pixel {
+		Pixel::new(
+			self.red.decode(raw),
+			self.green.decode(raw),
+			self.blue.decode(raw),
+		)
+	}
+}
+
+/// Smallest grid automatic scaling will shrink the console to
+const MIN_COLS: usize = 80;
+const MIN_ROWS: usize = 25;
+/// Biggest factor glyphs are ever blown up by
+const MAX_SCALE: usize = 4;
+
+/// Largest integer scale that still fits a `MIN_COLS` x `MIN_ROWS` grid of
+/// `font_width` x `font_height` glyphs on a `width` x `height` screen
+fn auto_scale(
+	(width, height): (usize, usize),
+	(font_width, font_height): (usize, usize),
+) -> usize {
+	let scale = (width / (font_width * MIN_COLS))
+		.min(height / (font_height * MIN_ROWS));
+
+	scale.max(1).min(MAX_SCALE)
+}
+
+/// Region from (`x0`, `y0`) up to but not including (`x1`, `y1`), in pixels
+/// or character cells depending on who's asking
+#[derive(Clone, Copy)]
+pub struct Rect {
+	pub x0: usize,
+	pub y0: usize,
+	pub x1: usize,
+	pub y1: usize,
+}
+
+impl Rect {
+	pub fn union(self, other: Self) -> Self {
+		Self {
+			x0: self.x0.min(other.x0),
+			y0: self.y0.min(other.y0),
+			x1: self.x1.max(other.x1),
+			y1: self.y1.max(other.y1),
+		}
+	}
+
+	/// The part both rectangles cover, if there is any
+	pub fn intersection(self, other: Self) -> Option<Self> {
+		let rect = Self {
+			x0: self.x0.max(other.x0),
+			y0: self.y0.max(other.y0),
+			x1: self.x1.min(other.x1),
+			y1: self.y1.min(other.y1),
+		};
+
+		if rect.x0 < rect.x1 && rect.y0 < rect.y1 {
+			Some(rect)
+		} else {
+			None
+		}
+	}
+}
+
+/// Small image (like the mouse pointer) laid over the screen when flushing.
+/// It's only ever drawn into video memory, so nothing under it has to be
+/// saved or redrawn when it moves.
+pub struct Sprite {
+	pub width: usize,
+	pub height: usize,
+	/// `width` x `height` pixels, row by row; `None` is see-through
+	pub pixels: &'static [Option<Pixel>],
+}
+
+/// The screen, split into character cells of the current font (times the
+/// current scale). Terminals decide what goes in the cells; this only knows
+/// how to get pixels on screen.
+///
+/// Once double buffering is enabled everything is drawn into a copy of the
+/// framebuffer in RAM, and only the region touched since the last `flush` is
+/// copied out to video memory.
+pub struct Framebuffer {
 	ptr: usize,
+	// where drawing goes: `ptr` itself, or the back buffer
+	buffer: usize,
+	dirty: Option<Rect>,
 	pitch: u16,
+	width: u16,
 	height: u16,
-	size: usize,
-	bpp: u16,
-	row: u16,
-	col: u16,
-	pub fg: Pixel,
-	pub bg: Pixel,
+	format: PixelFormat,
+	font: ConsoleFont,
+	// every glyph pixel is drawn as a `scale` x `scale` square
+	scale: usize,
+	// what the scale was set to, `None` picking one from the screen size
+	scale_setting: Option<usize>,
+	sprite: Option<&'static Sprite>,
+	// top left corner of the sprite
+	sprite_x: usize,
+	sprite_y: usize,
 }
 
-impl FramebufferWriter {
+impl Framebuffer {
 	pub fn new(tag: &FramebufferTag) -> Self {
-		Self {
+		let mut framebuffer = Self {
 			ptr: tag.start_address(),
+			buffer: tag.start_address(),
+			dirty: None,
 			pitch: tag.pitch(),
+			width: tag.width(),
 			height: tag.height(),
-			bpp: tag.bpp(),
-			size: tag.size(),
-			row: 0,
-			col: 0,
-			fg: Default::default(),
-			bg: Default::default(),
+			format: PixelFormat::from_tag(tag),
+			font: ConsoleFont::builtin(),
+			scale: 1,
+			scale_setting: None,
+			sprite: None,
+			sprite_x: 0,
+			sprite_y: 0,
+		};
+
+		framebuffer.update_scale();
+		framebuffer
+	}
+
+	/// Size of the screen in pixels, as (width, height)
+	pub const fn resolution(&self) -> (u16, u16) {
+		(self.width, self.height)
+	}
+
+	/// How many character cells fit on screen, as (columns, rows)
+	pub const fn grid_size(&self) -> (u16, u16) {
+		let (cell_width, cell_height) = self.cell_size();
+
+		(
+			self.width / cell_width as u16,
+			self.height / cell_height as u16,
+		)
+	}
+
+	/// Switch to a different font. This changes the grid size, so whoever
+	/// owns the cells has to redraw them all.
+	pub fn set_font(&mut self, font: Font<'static>) {
+		self.font = ConsoleFont::new(font);
+		self.update_scale();
+	}
+
+	/// Draw glyphs blown up by an integer factor, or pick one that suits the
+	/// screen size with `None`. Like `set_font`, this changes the grid size.
+	pub fn set_scale(&mut self, scale: Option<usize>) {
+		self.scale_setting = scale.map(|scale| scale.max(1).min(MAX_SCALE));
+		self.update_scale();
+	}
+
+	/// Factor glyphs are currently blown up by
+	pub const fn scale(&self) -> usize {
+		self.scale
+	}
+
+	/// Size of a character cell in pixels
+	const fn cell_size(&self) -> (usize, usize) {
+		let (font_width, font_height) = self.font.dimensions();
+		(font_width * self.scale, font_height * self.scale)
+	}
+
+	fn update_scale(&mut self) {
+		let screen = (self.width as usize, self.height as usize);
+		let scale = self
+			.scale_setting
+			.unwrap_or_else(|| auto_scale(screen, self.font.dimensions()));
+
+		// a huge font on a small screen still gets at least one cell
+		let (font_width, font_height) = self.font.dimensions();
+		let fits = |scale: usize| {
+			font_width * scale <= screen.0 && font_height * scale <= screen.1
+		};
+		self.scale = (1..=scale).rev().find(|&scale| fits(scale)).unwrap_or(1);
+	}
+
+	const fn buffer_size(&self) -> usize {
+		self.pitch as usize * self.height as usize
+	}
+
+	/// Start drawing into a back buffer allocated from the PMM. Until this is
+	/// called (the PMM has to be up first) drawing goes straight to video
+	/// memory. Returns false, staying single buffered, if there isn't enough
+	/// contiguous memory for the buffer.
+	pub fn enable_double_buffering(&mut self) -> bool {
+		if self.buffer != self.ptr {
+			return true;
 		}
+
+		let size = self.buffer_size();
+		let back =
+			match pmm::try_alloc_pages(polyfill::div_up(size, PAGE_SIZE)) {
+				Some(back) => back,
+				None => return false,
+			};
+
+		// reading video memory is slow, but this only happens once
+		unsafe {
+			polyfill::copy_forward(back, self.ptr as *const u8, size);
+		}
+
+		self.buffer = back as usize;
+		true
 	}
 
-	pub fn draw(&mut self, c: char) {
-		match c {
-			'\n' => {
-				self.row += FONT_DIMENSIONS.1 as u16;
-				self.col = 0;
-			}
-			'\t' => {
-				for _ in 0..12 {
-					self.draw(' ');
+	/// Lay `sprite` over the screen from now on, or take it away with `None`.
+	/// The sprite is only shown with double buffering enabled.
+	pub fn set_sprite(&mut self, sprite: Option<&'static Sprite>) {
+		self.mark_sprite_dirty();
+		self.sprite = sprite;
+		self.mark_sprite_dirty();
+	}
+
+	/// Move the sprite's top left corner, which may hang off the screen
+	pub fn move_sprite(&mut self, x: usize, y: usize) {
+		self.mark_sprite_dirty();
+		self.sprite_x = x;
+		self.sprite_y = y;
+		self.mark_sprite_dirty();
+	}
+
+	pub const fn sprite_position(&self) -> (usize, usize) {
+		(self.sprite_x, self.sprite_y)
+	}
+
+	fn mark_sprite_dirty(&mut self) {
+		if let Some(sprite) = self.sprite {
+			self.mark_dirty(
+				self.sprite_x,
+				self.sprite_y,
+				sprite.width,
+				sprite.height,
+			);
+		}
+	}
+
+	/// Draw the part of the sprite inside `dirty` into video memory, after
+	/// the back buffer has been copied there
+	fn draw_sprite(&self, dirty: Rect) {
+		let sprite = match self.sprite {
+			Some(sprite) => sprite,
+			None => return,
+		};
+		let bounds = Rect {
+			x0: self.sprite_x,
+			y0: self.sprite_y,
+			x1: self.sprite_x + sprite.width,
+			y1: self.sprite_y + sprite.height,
+		};
+		let rect = match bounds.intersection(dirty) {
+			Some(rect) => rect,
+			None => return,
+		};
+
+		for y in rect.y0..rect.y1 {
+			for x in rect.x0..rect.x1 {
+				let index =
+					(y - self.sprite_y) * sprite.width + (x - self.sprite_x);
+
+				if let Some(color) = sprite.pixels[index] {
+					let value = self.format.encode(color);
+					unsafe { self.write_raw(self.ptr, x, y, value) }
 				}
 			}
-			_ => {
-				let offset = (c as u8 - 32) as usize * 16;
-				for y in 0..16 {
-					for x in 0..8 {
-						let cur_x = self.col as usize + (8 - x);
-						let cur_y = self.row as usize + y;
-
-						let ptr = (self.ptr
-							+ (cur_x * (self.bpp / 8) as usize
-								+ cur_y * self.pitch as usize) as usize)
-							as *mut u32;
-
-						if FONT[y + offset as usize] >> x & 1 == 1 {
-							unsafe { *ptr = self.fg.as_bits() }
-						} else {
-							unsafe { *ptr = self.bg.as_bits() }
-						}
-					}
-				}
+		}
+	}
 
-				if self.pitch == self.col {
-					self.draw('\n');
-				} else {
-					self.col += FONT_DIMENSIONS.0 as u16;
-				}
+	/// Copy everything drawn since the last flush out to video memory
+	pub fn flush(&mut self) {
+		let dirty = match self.dirty.take() {
+			Some(dirty) => dirty,
+			None => return,
+		};
 
-				if self.row == self.height {
-					let top_row_bytes =
-						self.pitch as usize * FONT_DIMENSIONS.1 as usize;
+		if self.buffer == self.ptr {
+			return;
+		}
 
-					for offset in 0..top_row_bytes {
-						unsafe { *(self.ptr as *mut u8).add(offset) = 0 }
-					}
+		let bytes_per_pixel = self.format.bytes_per_pixel;
+		let row_bytes = (dirty.x1 - dirty.x0) * bytes_per_pixel;
 
-					unsafe {
-						polyfill::memmove(
-							self.ptr as *mut u8,
-							(self.ptr + top_row_bytes) as *const u8,
-							self.size - top_row_bytes,
-						);
-					}
+		for y in dirty.y0..dirty.y1 {
+			let offset = y * self.pitch as usize + dirty.x0 * bytes_per_pixel;
 
-					self.row -= 1;
-				}
+			unsafe {
+				polyfill::copy_forward(
+					(self.ptr + offset) as *mut u8,
+					(self.buffer + offset) as *const u8,
+					row_bytes,
+				);
 			}
 		}
+
+		self.draw_sprite(dirty);
+	}
+
+	/// Remember that a region has to be flushed, clipped to the screen
+	pub fn mark_dirty(
+		&mut self,
+		x: usize,
+		y: usize,
+		width: usize,
+		height: usize,
+	) {
+		let rect = Rect {
+			x0: x.min(self.width as usize),
+			y0: y.min(self.height as usize),
+			x1: (x + width).min(self.width as usize),
+			y1: (y + height).min(self.height as usize),
+		};
+
+		if rect.x0 == rect.x1 || rect.y0 == rect.y1 {
+			return;
+		}
+
+		self.dirty = Some(match self.dirty {
+			Some(dirty) => dirty.union(rect),
+			None => rect,
+		});
 	}
-}
 
-impl Write for FramebufferWriter {
-	fn write_str(&mut self, s: &str) -> fmt::Result {
-		for c in s.chars() {
-			match c {
-				c if c.is_ascii() => self.draw(c),
-				_ => return Err(fmt::Error),
+	/// Address of a pixel in `base`, which is either video memory or the
+	/// back buffer
+	fn pixel_ptr(&self, base: usize, x: usize, y: usize) -> *mut u8 {
+		(base
+			+ x * self.format.bytes_per_pixel
+			+ y * self.pitch as usize) as *mut u8
+	}
+
+	/// Read the raw value of a pixel, whatever its size
+	unsafe fn read_raw(&self, base: usize, x: usize, y: usize) -> u32 {
+		let ptr = self.pixel_ptr(base, x, y);
+
+		match self.format.bytes_per_pixel {
+			2 => (ptr as *const u16).read_unaligned() as u32,
+			3 => {
+				*ptr as u32
+					| (*ptr.add(1) as u32) << 8
+					| (*ptr.add(2) as u32) << 16
+			}
+			_ => (ptr as *const u32).read_unaligned(),
+		}
+	}
+
+	/// Write the raw value of a pixel, whatever its size
+	unsafe fn write_raw(&self, base: usize, x: usize, y: usize, value: u32) {
+		let ptr = self.pixel_ptr(base, x, y);
+
+		match self.format.bytes_per_pixel {
+			2 => (ptr as *mut u16).write_unaligned(value as u16),
+			3 => {
+				*ptr = value as u8;
+				*ptr.add(1) = (value >> 8) as u8;
+				*ptr.add(2) = (value >> 16) as u8;
+			}
+			_ => (ptr as *mut u32).write_unaligned(value),
+		}
+	}
+
+	/// Color of a pixel, or `None` if it's off screen
+	pub fn get_pixel(&self, x: usize, y: usize) -> Option<Pixel> {
+		if x >= self.width as usize || y >= self.height as usize {
+			return None;
+		}
+
+		let raw = unsafe { self.read_raw(self.buffer, x, y) };
+		Some(self.format.decode(raw))
+	}
+
+	/// Set a pixel, ignoring ones off screen. This doesn't mark anything
+	/// dirty, so it's up to the caller to do that before flushing.
+	pub fn put_pixel(&self, x: usize, y: usize, color: Pixel) {
+		if x >= self.width as usize || y >= self.height as usize {
+			return;
+		}
+
+		unsafe { self.write_raw(self.buffer, x, y, self.format.encode(color)) }
+	}
+
+	pub fn fill_rect(
+		&mut self,
+		x: usize,
+		y: usize,
+		width: usize,
+		height: usize,
+		color: Pixel,
+	) {
+		for cur_y in y..y + height {
+			for cur_x in x..x + width {
+				self.put_pixel(cur_x, cur_y, color);
 			}
 		}
-		Ok(())
+
+		self.mark_dirty(x, y, width, height);
+	}
+
+	/// Fill the whole screen with one color
+	pub fn clear(&mut self, color: Pixel) {
+		self.fill_rect(0, 0, self.width as usize, self.height as usize, color);
+	}
+
+	/// Draw one character cell, swapping its colors if `inverted` (which is
+	/// how the cursor is shown)
+	pub fn draw_cell(
+		&mut self,
+		col: u16,
+		row: u16,
+		cell: Cell,
+		inverted: bool,
+	) {
+		let (cell_width, cell_height) = self.cell_size();
+		let (x, y) = (col as usize * cell_width, row as usize * cell_height);
+		let (fg, bg) = if inverted {
+			(cell.bg, cell.fg)
+		} else {
+			(cell.fg, cell.bg)
+		};
+
+		if cell.c == ' ' {
+			self.fill_rect(x, y, cell_width, cell_height, bg);
+			return;
+		}
+
+		let glyph = self.font.glyph(cell.c);
+
+		for cell_y in 0..cell_height {
+			let glyph_y = cell_y / self.scale;
+
+			for cell_x in 0..cell_width {
+				let glyph_x = cell_x / self.scale;
+				// bold also lights up the pixel right of every lit one
+				let lit = glyph.is_set(glyph_x, glyph_y)
+					|| cell.bold
+						&& glyph_x > 0 && glyph.is_set(glyph_x - 1, glyph_y);
+
+				let color = if lit { fg } else { bg };
+				self.put_pixel(x + cell_x, y + cell_y, color);
+			}
+		}
+
+		self.mark_dirty(x, y, cell_width, cell_height);
+	}
+
+	/// Move the top `rows` rows of cells up by `by` rows. Whatever ends up in
+	/// the bottom `by` rows is stale and has to be redrawn.
+	pub fn scroll_up(&mut self, by: u16, rows: u16) {
+		if by == 0 || by >= rows {
+			return;
+		}
+
+		let row_bytes = self.pitch as usize * self.cell_size().1;
+		let offset = row_bytes * by as usize;
+		let text_bytes = row_bytes * rows as usize;
+
+		unsafe {
+			polyfill::memmove(
+				self.buffer as *mut u8,
+				(self.buffer + offset) as *const u8,
+				text_bytes - offset,
+			);
+		}
+
+		let text_height = rows as usize * self.cell_size().1;
+		self.mark_dirty(0, 0, self.width as usize, text_height);
 	}
 }
 
 lazy_static! {
-	pub static ref STDIO_WRITER: Mutex<FramebufferWriter> =
-		Mutex::new(FramebufferWriter::new(
+	pub static ref FRAMEBUFFER: Mutex<Framebuffer> =
+		Mutex::new(Framebuffer::new(
 			STIVALE_STRUCT
 				.inner()
 				.framebuffer()
@@ -173,19 +623,15 @@ lazy_static! {
 		));
 }
 
-/// Render formatted text to the framebuffer
+/// Render formatted text to the kernel console (and serial, if mirroring)
 #[macro_export]
 macro_rules! kprint {
 	($($arg:tt)+) => ({
-		use core::fmt::Write;
-		use crate::stdio::framebuffer::STDIO_WRITER;
-
-		let mut writer = STDIO_WRITER.lock();
-		let _ = writer.write_fmt(format_args!($($arg)+));
+		$crate::stdio::_print(format_args!($($arg)+));
 	});
 }
 
-/// Render formatted text to the framebuffer, with a newline
+/// Render formatted text to the kernel console, with a newline
 #[macro_export]
 macro_rules! kprintln {
 	() => ({
@@ -196,45 +642,3 @@ macro_rules! kprintln {
 		$crate::kprint!("\n");
 	});
 }
-
-/// Render formatted informative text to the framebuffer, with a newline & a
-/// colored "info" label
-#[macro_export]
-macro_rules! kiprintln {
-	($($arg:tt)+) => ({
-		$crate::STDIO_WRITER.lock().fg.set($crate::CommonColors::Cyan);
-		$crate::kprint!("[ info ] => ");
-		$crate::STDIO_WRITER.lock().fg.reset();
-
-		$crate::kprint!($($arg)+);
-		$crate::kprintln!();
-	});
-}
-
-/// Render formatted error text to the framebuffer, with a newline & a colored
-/// "fail" label
-#[macro_export]
-macro_rules! keprintln {
-	($($arg:tt)+) => ({
-		$crate::STDIO_WRITER.lock().fg.set($crate::CommonColors::Red);
-		$crate::kprint!("[ fail ] => ");
-		$crate::STDIO_WRITER.lock().fg.reset();
-
-		$crate::kprint!($($arg)+);
-		$crate::kprintln!();
-	});
-}
-
-/// Render formatted success text to the framebuffer, with a newline & a colored
-/// "scss" label
-#[macro_export]
-macro_rules! ksprintln {
-	($($arg:tt)+) => ({
-		$crate::STDIO_WRITER.lock().fg.set($crate::CommonColors::Green);
-		$crate::kprint!("[ scss ] => ");
-		$crate::STDIO_WRITER.lock().fg.reset();
-
-		$crate::kprint!($($arg)+);
-		$crate::kprintln!();
-	});
-}
@@ -0,0 +1,155 @@
+use crate::ring_buffer::RingBuffer;
+
+const LINE_MAX: usize = 256;
+const INPUT_SIZE: usize = 1024;
+
+const CTRL_C: u8 = 0x03;
+const BACKSPACE: u8 = 0x08;
+const DELETE: u8 = 0x7F;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReadError {
+	/// Ctrl-C was pressed since the last read
+	Interrupted,
+	/// Nothing to read yet (a full line, in canonical mode)
+	WouldBlock,
+}
+
+/// Turns a stream of raw input bytes into something readers can consume.
+/// In canonical mode input is handed out a line at a time, with backspace
+/// editing the pending line and Ctrl-C discarding it; otherwise bytes are
+/// passed through as they arrive.
+pub struct LineDiscipline {
+	line: [u8; LINE_MAX],
+	line_len: usize,
+	input: RingBuffer<u8, INPUT_SIZE>,
+	// complete lines waiting in `input`
+	lines: usize,
+	interrupted: bool,
+	canonical: bool,
+	echo: bool,
+	echo_fn: fn(&[u8]),
+}
+
+impl LineDiscipline {
+	pub const fn new(echo_fn: fn(&[u8])) -> Self {
+		Self {
+			line: [0; LINE_MAX],
+			line_len: 0,
+			input: RingBuffer::new(0),
+			lines: 0,
+			interrupted: false,
+			canonical: true,
+			echo: true,
+			echo_fn,
+		}
+	}
+
+	pub fn set_canonical(&mut self, to: bool) {
+		self.canonical = to;
+
+		// whatever was being edited becomes readable right away
+		if !to {
+			self.commit_line();
+		}
+	}
+
+	pub fn set_echo(&mut self, to: bool) {
+		self.echo = to;
+	}
+
+	fn echo(&self, bytes: &[u8]) {
+		if self.echo {
+			(self.echo_fn)(bytes);
+		}
+	}
+
+	/// Feed one byte of input, usually from an interrupt handler
+	pub fn receive(&mut self, byte: u8) {
+		if !self.canonical {
+			if self.input.push(byte).is_ok() {
+				self.echo(&[byte]);
+			}
+			return;
+		}
+
+		match byte {
+			CTRL_C => {
+				self.line_len = 0;
+				self.interrupted = true;
+				self.echo(b"^C\n");
+			}
+			BACKSPACE | DELETE => {
+				if self.line_len > 0 {
+					self.line_len -= 1;
+					self.echo(b"\x08 \x08");
+				}
+			}
+			b'\r' | b'\n' => {
+				self.echo(b"\n");
+				self.commit_line();
+			}
+			byte if byte == b'\t' || !byte.is_ascii_control() => {
+				// keep room for the newline that terminates the line
+				if self.line_len < LINE_MAX - 1 {
+					self.line[self.line_len] = byte;
+					self.line_len += 1;
+					self.echo(&[byte]);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn commit_line(&mut self) {
+		let needed = self.line_len + self.canonical as usize;
+
+		// a line that doesn't fit is dropped whole, so readers never see half
+		// of one
+		if INPUT_SIZE - self.input.len() >= needed {
+			for &byte in &self.line[..self.line_len] {
+				let _ = self.input.push(byte);
+			}
+
+			if self.canonical {
+				let _ = self.input.push(b'\n');
+				self.lines += 1;
+			}
+		}
+
+		self.line_len = 0;
+	}
+
+	/// Copy pending input into `buf` without waiting. In canonical mode this
+	/// stops after the first newline.
+	pub fn try_read(&mut self, buf: &mut [u8]) -> Result<usize, ReadError> {
+		if core::mem::take(&mut self.interrupted) {
+			return Err(ReadError::Interrupted);
+		}
+
+		if self.input.is_empty() || self.canonical && self.lines == 0 {
+			return Err(ReadError::WouldBlock);
+		}
+
+		let mut read = 0;
+		while read < buf.len() {
+			match self.input.pop() {
+				Some(byte) => {
+					buf[read] = byte;
+					read += 1;
+
+					if byte == b'\n' && self.lines > 0 {
+						self.lines -= 1;
+
+						if self.canonical {
+							break;
+						}
+					}
+				}
+				None => break,
+			}
+		}
+
+		Ok(read)
+	}
+}
@@ -1 +1,26 @@
+pub mod ansi;
+pub mod font;
 pub mod framebuffer;
+pub mod line_discipline;
+pub mod serial;
+pub mod terminal;
+
+use crate::{arch::cpu, log::dmesg};
+use core::fmt::{Arguments, Write};
+use serial::SERIAL_WRITER;
+
+/// Backend for the `kprint!` family of macros: renders to the kernel console,
+/// records the text in dmesg and, if mirroring is enabled, sends it over serial
+#[doc(hidden)]
+pub fn _print(args: Arguments) {
+	terminal::with_console(|console| {
+		let _ = console.write_fmt(args);
+	});
+	dmesg::record_raw(args);
+
+	if serial::mirroring() {
+		cpu::without_interrupts(|| {
+			let _ = SERIAL_WRITER.lock().write_fmt(args);
+		});
+	}
+}
@@ -0,0 +1,304 @@
+use super::line_discipline::{LineDiscipline, ReadError};
+use crate::{
+	arch::{
+		cpu,
+		idt::{self, InterruptStackFrame},
+		pic,
+		port::{inb, outb},
+	},
+	input::{self, DeviceId, DeviceKind, EventKind},
+};
+use bitflags::bitflags;
+use core::{
+	fmt::{self, Write},
+	sync::atomic::{AtomicBool, Ordering},
+};
+use spin::Mutex;
+
+/// I/O base of the first serial port
+pub const COM1: u16 = 0x3F8;
+/// IRQ line COM1 raises when it has received data
+const COM1_IRQ: u8 = 4;
+/// Clock rate of the UART divided by 16, i.e. the highest supported baud rate
+const UART_CLOCK: u32 = 115_200;
+/// Arbitrary byte sent through the UART while in loopback mode
+const LOOPBACK_TEST_BYTE: u8 = 0xAE;
+
+// register offsets from the I/O base
+const DATA: u16 = 0;
+const INTERRUPT_ENABLE: u16 = 1;
+const FIFO_CONTROL: u16 = 2;
+const LINE_CONTROL: u16 = 3;
+const MODEM_CONTROL: u16 = 4;
+const LINE_STATUS: u16 = 5;
+
+// while DLAB is set, the first two registers hold the baud rate divisor
+const DIVISOR_LOW: u16 = 0;
+const DIVISOR_HIGH: u16 = 1;
+
+bitflags! {
+	struct InterruptEnable: u8 {
+		const RECEIVED_DATA = 0b0000_0001;
+	}
+}
+
+bitflags! {
+	struct LineControl: u8 {
+		const DATA_8_BITS = 0b0000_0011;
+		const DLAB = 0b1000_0000;
+	}
+}
+
+bitflags! {
+	struct FifoControl: u8 {
+		const ENABLE = 0b0000_0001;
+		const CLEAR_RECEIVE = 0b0000_0010;
+		const CLEAR_TRANSMIT = 0b0000_0100;
+		const TRIGGER_14_BYTES = 0b1100_0000;
+	}
+}
+
+bitflags! {
+	struct ModemControl: u8 {
+		const DTR = 0b0000_0001;
+		const RTS = 0b0000_0010;
+		const OUT1 = 0b0000_0100;
+		const OUT2 = 0b0000_1000;
+		const LOOPBACK = 0b0001_0000;
+	}
+}
+
+bitflags! {
+	struct LineStatus: u8 {
+		const DATA_READY = 0b0000_0001;
+		const TRANSMIT_EMPTY = 0b0010_0000;
+	}
+}
+
+#[derive(Debug)]
+pub enum SerialError {
+	/// The requested baud rate can't be derived from the UART clock
+	InvalidBaud(u32),
+	/// The byte sent in loopback mode didn't come back, so there's probably no
+	/// UART at this port
+	FaultyLoopback,
+}
+
+pub struct SerialPort {
+	base: u16,
+}
+
+impl SerialPort {
+	pub const fn new(base: u16) -> Self {
+		Self { base }
+	}
+
+	/// Set up the UART for 8N1 at the given baud rate with FIFOs enabled, and
+	/// make sure it's actually there with a loopback self-test
+	pub fn init(&mut self, baud: u32) -> Result<(), SerialError> {
+		if baud == 0 || UART_CLOCK % baud != 0 {
+			return Err(SerialError::InvalidBaud(baud));
+		}
+		let divisor = (UART_CLOCK / baud) as u16;
+
+		unsafe {
+			self.write_reg(INTERRUPT_ENABLE, 0);
+
+			self.write_reg(LINE_CONTROL, LineControl::DLAB.bits());
+			self.write_reg(DIVISOR_LOW, divisor as u8);
+			self.write_reg(DIVISOR_HIGH, (divisor >> 8) as u8);
+			self.write_reg(LINE_CONTROL, LineControl::DATA_8_BITS.bits());
+
+			self.write_reg(FIFO_CONTROL, FifoControl::all().bits());
+
+			self.write_reg(
+				MODEM_CONTROL,
+				(ModemControl::RTS
+					| ModemControl::OUT1
+					| ModemControl::OUT2
+					| ModemControl::LOOPBACK)
+					.bits(),
+			);
+			self.write_reg(DATA, LOOPBACK_TEST_BYTE);
+			if self.read_reg(DATA) != LOOPBACK_TEST_BYTE {
+				return Err(SerialError::FaultyLoopback);
+			}
+
+			self.write_reg(
+				MODEM_CONTROL,
+				(ModemControl::DTR
+					| ModemControl::RTS
+					| ModemControl::OUT1
+					| ModemControl::OUT2)
+					.bits(),
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Raise an IRQ whenever a byte arrives
+	pub fn enable_receive_interrupt(&mut self) {
+		unsafe {
+			self.write_reg(
+				INTERRUPT_ENABLE,
+				InterruptEnable::RECEIVED_DATA.bits(),
+			)
+		}
+	}
+
+	pub fn send(&mut self, byte: u8) {
+		while !self.line_status().contains(LineStatus::TRANSMIT_EMPTY) {
+			core::hint::spin_loop();
+		}
+
+		unsafe { self.write_reg(DATA, byte) }
+	}
+
+	pub fn send_bytes(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			// terminals expect a carriage return before moving down a line
+			if byte == b'\n' {
+				self.send(b'\r');
+			}
+			self.send(byte);
+		}
+	}
+
+	/// Take a byte out of the receive FIFO, if there is one
+	pub fn try_receive(&mut self) -> Option<u8> {
+		if self.line_status().contains(LineStatus::DATA_READY) {
+			Some(unsafe { self.read_reg(DATA) })
+		} else {
+			None
+		}
+	}
+
+	fn line_status(&self) -> LineStatus {
+		LineStatus::from_bits_truncate(unsafe { self.read_reg(LINE_STATUS) })
+	}
+
+	unsafe fn read_reg(&self, offset: u16) -> u8 {
+		inb(self.base + offset)
+	}
+
+	unsafe fn write_reg(&self, offset: u16, value: u8) {
+		outb(self.base + offset, value)
+	}
+}
+
+impl Write for SerialPort {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.send_bytes(s.as_bytes());
+		Ok(())
+	}
+}
+
+/// COM1 transmitter. The receive interrupt echoes through this, so only lock
+/// it with interrupts disabled.
+pub static SERIAL_WRITER: Mutex<SerialPort> = Mutex::new(SerialPort::new(COM1));
+
+static SERIAL_INPUT: Mutex<LineDiscipline> =
+	Mutex::new(LineDiscipline::new(echo));
+
+/// COM1 as an input device, once it's registered
+static INPUT_DEVICE: Mutex<Option<DeviceId>> = Mutex::new(None);
+
+/// Let go of the writer no matter who holds it, for the panic handler only
+pub unsafe fn force_unlock() {
+	SERIAL_WRITER.force_unlock();
+}
+
+fn echo(bytes: &[u8]) {
+	SERIAL_WRITER.lock().send_bytes(bytes);
+}
+
+extern "x86-interrupt" fn receive_interrupt(_frame: InterruptStackFrame) {
+	// drain the whole FIFO, it may have filled up to the trigger level. The
+	// writer has to be unlocked again before the byte is echoed through it.
+	loop {
+		let byte = match SERIAL_WRITER.lock().try_receive() {
+			Some(byte) => byte,
+			None => break,
+		};
+		SERIAL_INPUT.lock().receive(byte);
+		publish(byte);
+	}
+
+	pic::eoi(COM1_IRQ);
+}
+
+/// Hand a received byte to the input subsystem as text, turning what
+/// terminals send for Enter and Backspace into what keyboards would
+fn publish(byte: u8) {
+	let device = match *INPUT_DEVICE.lock() {
+		Some(device) => device,
+		None => return,
+	};
+
+	let c = match byte {
+		b'\r' => '\n',
+		0x7F => '\x08',
+		byte if byte.is_ascii() => byte as char,
+		_ => return,
+	};
+	input::publish(device, EventKind::Char(c));
+}
+
+static MIRROR: AtomicBool = AtomicBool::new(false);
+
+/// Whether text printed to the framebuffer is also sent over COM1
+pub fn mirroring() -> bool {
+	MIRROR.load(Ordering::Relaxed)
+}
+
+pub fn set_mirror(to: bool) {
+	MIRROR.store(to, Ordering::Relaxed);
+}
+
+/// Bring up COM1 at 115200 baud with interrupt-driven input, mirroring console
+/// output to it if the `SERIAL` build option asks for it
+pub fn init() -> Result<(), SerialError> {
+	let mut port = SERIAL_WRITER.lock();
+	port.init(UART_CLOCK)?;
+
+	*INPUT_DEVICE.lock() = input::register("COM1", DeviceKind::Serial);
+	idt::set_handler(pic::vector(COM1_IRQ), receive_interrupt);
+	port.enable_receive_interrupt();
+	pic::unmask(COM1_IRQ);
+
+	set_mirror(cfg!(SERIAL = "MIRROR"));
+	Ok(())
+}
+
+/// Read serial input into `buf` if there is any (a full line, in canonical
+/// mode), returning how many bytes were copied
+pub fn try_read(buf: &mut [u8]) -> Result<usize, ReadError> {
+	cpu::without_interrupts(|| SERIAL_INPUT.lock().try_read(buf))
+}
+
+/// Like `try_read`, but sleeps until there's something to read. Ctrl-C wakes
+/// it up with `ReadError::Interrupted`.
+pub fn read(buf: &mut [u8]) -> Result<usize, ReadError> {
+	loop {
+		cpu::disable_interrupts();
+		let result = SERIAL_INPUT.lock().try_read(buf);
+
+		if result != Err(ReadError::WouldBlock) {
+			cpu::enable_interrupts();
+			return result;
+		}
+
+		cpu::enable_interrupts_and_wait();
+	}
+}
+
+/// Switch between line-buffered input with editing (the default) and raw
+/// byte-at-a-time input
+pub fn set_canonical(to: bool) {
+	cpu::without_interrupts(|| SERIAL_INPUT.lock().set_canonical(to));
+}
+
+pub fn set_echo(to: bool) {
+	cpu::without_interrupts(|| SERIAL_INPUT.lock().set_echo(to));
+}
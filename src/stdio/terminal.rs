@@ -0,0 +1,700 @@
+//! Virtual terminals sharing the framebuffer. Each one keeps its own grid of
+//! character cells (plus the lines that scrolled off the top), colors and
+//! cursor; only the active one is drawn, and switching repaints the screen
+//! from the new one's cells.
+
+use super::{
+	ansi::{Action, Erase, Graphic, Parser},
+	framebuffer::{CommonColors, Framebuffer, Pixel, Rect, FRAMEBUFFER},
+};
+use crate::{
+	arch::{cpu, pit},
+	input::{KeyCode, Modifiers},
+	mm::{pmm, PAGE_SIZE},
+	polyfill,
+};
+use core::{
+	fmt::{self, Write},
+	mem, slice,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+use lazy_static::lazy_static;
+use spin::Mutex;
+use zap_font::Font;
+
+/// Number of virtual terminals
+pub const VT_COUNT: usize = 4;
+/// Terminal the kernel's own output goes to
+pub const KERNEL_VT: usize = 0;
+
+/// Lines of history kept above the grid
+const SCROLLBACK_LINES: usize = 400;
+
+/// Columns between tab stops
+const TAB_WIDTH: u16 = 8;
+/// Milliseconds the cursor stays on (and then off) while blinking
+const BLINK_INTERVAL_MS: u64 = 500;
+
+/// One character on screen, along with how it's drawn
+#[derive(Clone, Copy)]
+pub struct Cell {
+	pub c: char,
+	pub fg: Pixel,
+	pub bg: Pixel,
+	pub bold: bool,
+}
+
+impl Cell {
+	const EMPTY: Self = Self {
+		c: '\0',
+		fg: Pixel::new(0, 0, 0),
+		bg: Pixel::new(0, 0, 0),
+		bold: false,
+	};
+}
+
+static ACTIVE: AtomicUsize = AtomicUsize::new(KERNEL_VT);
+
+/// A text console: a `cols` x `rows` grid of cells, with `col`/`row` being the
+/// cell the next character goes into.
+///
+/// The cells are a ring of `lines` lines. The grid is the `rows` lines
+/// starting at `top`, and the `history` lines before it are scrollback, which
+/// can be viewed by moving the view `view_offset` lines up from the bottom.
+///
+/// Writing only touches the cells. If the terminal is the active one, the
+/// cells changed since the last update (and any scrolling) are then drawn to
+/// the framebuffer.
+pub struct Terminal {
+	index: usize,
+	cells: &'static mut [Cell],
+	lines: usize,
+	top: usize,
+	history: usize,
+	view_offset: usize,
+	cols: u16,
+	rows: u16,
+	col: u16,
+	row: u16,
+	pub fg: Pixel,
+	pub bg: Pixel,
+	bold: bool,
+	saved_cursor: (u16, u16),
+	parser: Parser,
+	cursor_enabled: bool,
+	// blink phase: whether the cursor should be visible right now
+	blink_on: bool,
+	// cells changed since the screen was last updated
+	damage: Option<Rect>,
+	// rows everything has moved up by since the screen was last updated
+	scrolled: u16,
+	// cell the cursor is drawn over on screen, if it's drawn at all
+	drawn_cursor: Option<(u16, u16)>,
+}
+
+impl Terminal {
+	fn new(index: usize, (cols, rows): (u16, u16)) -> Self {
+		let mut terminal = Self {
+			index,
+			cells: &mut [],
+			lines: 0,
+			top: 0,
+			history: 0,
+			view_offset: 0,
+			cols: 0,
+			rows: 0,
+			col: 0,
+			row: 0,
+			fg: CommonColors::White.into(),
+			bg: Default::default(),
+			bold: false,
+			saved_cursor: (0, 0),
+			parser: Parser::new(),
+			cursor_enabled: true,
+			blink_on: true,
+			damage: None,
+			scrolled: 0,
+			drawn_cursor: None,
+		};
+
+		terminal.resize(cols, rows);
+		terminal
+	}
+
+	/// Size of the terminal in character cells, as (columns, rows)
+	pub const fn dimensions(&self) -> (u16, u16) {
+		(self.cols, self.rows)
+	}
+
+	/// Cell the next character will be drawn in, as (column, row)
+	pub const fn cursor(&self) -> (u16, u16) {
+		(self.col, self.row)
+	}
+
+	/// Move the cursor, clamping it to the screen
+	pub fn set_cursor(&mut self, col: u16, row: u16) {
+		self.move_cursor(col, row);
+		self.update();
+	}
+
+	pub fn set_cursor_enabled(&mut self, to: bool) {
+		self.cursor_enabled = to;
+		self.update();
+	}
+
+	/// Blank every cell with the background color and home the cursor
+	pub fn clear(&mut self) {
+		let snapped = self.snap_to_bottom();
+
+		self.clear_rows(0, self.rows);
+		self.col = 0;
+		self.row = 0;
+
+		if snapped {
+			self.refresh();
+		} else {
+			self.update();
+		}
+	}
+
+	/// Lines of scrollback currently kept
+	pub const fn history(&self) -> usize {
+		self.history
+	}
+
+	/// Move the view `lines` lines back into the scrollback, or towards the
+	/// bottom if negative. Any output moves it all the way back down.
+	pub fn scroll_view(&mut self, lines: isize) {
+		let offset = (self.view_offset as isize + lines)
+			.max(0)
+			.min(self.history as isize) as usize;
+
+		if offset != self.view_offset {
+			self.view_offset = offset;
+			self.refresh();
+		}
+	}
+
+	/// Stop looking at the scrollback
+	pub fn scroll_view_to_bottom(&mut self) {
+		if self.snap_to_bottom() {
+			self.refresh();
+		}
+	}
+
+	/// Put the view back on the live grid, saying whether it moved
+	fn snap_to_bottom(&mut self) -> bool {
+		core::mem::take(&mut self.view_offset) > 0
+	}
+
+	fn is_active(&self) -> bool {
+		ACTIVE.load(Ordering::SeqCst) == self.index
+	}
+
+	/// Change the grid size, which throws away everything on it
+	fn resize(&mut self, cols: u16, rows: u16) {
+		let (cols, rows) = (cols.max(1), rows.max(1));
+		if (cols, rows) != (self.cols, self.rows) {
+			free_cells(mem::take(&mut self.cells));
+			self.cells = alloc_cells(cols as usize, rows as usize);
+		}
+
+		self.cols = cols;
+		self.rows = rows;
+		self.lines = self.cells.len() / self.cols as usize;
+		self.top = 0;
+		self.history = 0;
+		self.view_offset = 0;
+		self.col = 0;
+		self.row = 0;
+		self.saved_cursor = (0, 0);
+		self.clear_rows(0, self.rows);
+	}
+
+	const fn blank(&self) -> Cell {
+		Cell {
+			c: ' ',
+			fg: self.fg,
+			bg: self.bg,
+			bold: false,
+		}
+	}
+
+	/// Where the cell at (`col`, `row`) of the grid is stored
+	fn index_of(&self, col: u16, row: u16) -> usize {
+		let line = (self.top + row as usize) % self.lines;
+		line * self.cols as usize + col as usize
+	}
+
+	/// Cell shown at (`col`, `row`) on screen, taking the view into account
+	fn view_cell(&self, col: u16, row: u16) -> Cell {
+		let line = (self.top + self.lines - self.view_offset + row as usize)
+			% self.lines;
+		self.cells[line * self.cols as usize + col as usize]
+	}
+
+	fn damage_cells(&mut self, col: u16, row: u16, cols: u16, rows: u16) {
+		let rect = Rect {
+			x0: col as usize,
+			y0: row as usize,
+			x1: (col + cols) as usize,
+			y1: (row + rows) as usize,
+		};
+
+		self.damage = Some(match self.damage {
+			Some(damage) => damage.union(rect),
+			None => rect,
+		});
+	}
+
+	fn move_cursor(&mut self, col: u16, row: u16) {
+		self.col = col.min(self.cols.saturating_sub(1));
+		self.row = row.min(self.rows.saturating_sub(1));
+	}
+
+	/// Blank `count` cells starting at (`col`, `row`), all on the same row
+	fn clear_cells(&mut self, col: u16, row: u16, count: u16) {
+		let count = count.min(self.cols - col);
+		let start = self.index_of(col, row);
+		let blank = self.blank();
+
+		for cell in &mut self.cells[start..start + count as usize] {
+			*cell = blank;
+		}
+		self.damage_cells(col, row, count, 1);
+	}
+
+	/// Blank whole rows `from..to`
+	fn clear_rows(&mut self, from: u16, to: u16) {
+		for row in from..to {
+			self.clear_cells(0, row, self.cols);
+		}
+	}
+
+	/// Move everything up by one row, pushing the top one into the
+	/// scrollback and blanking the bottom one
+	fn scroll(&mut self) {
+		self.top = (self.top + 1) % self.lines;
+		self.history = (self.history + 1).min(self.lines - self.rows as usize);
+
+		// whatever was damaged moved up along with the text
+		self.damage = self.damage.and_then(|damage| {
+			Some(Rect {
+				y0: damage.y0.saturating_sub(1),
+				y1: damage.y1.checked_sub(1).filter(|&y1| y1 > 0)?,
+				..damage
+			})
+		});
+		self.scrolled = (self.scrolled + 1).min(self.rows);
+
+		self.clear_rows(self.rows - 1, self.rows);
+	}
+
+	fn newline(&mut self) {
+		self.col = 0;
+
+		if self.row + 1 < self.rows {
+			self.row += 1;
+		} else {
+			self.scroll();
+		}
+	}
+
+	fn erase_in_line(&mut self, erase: Erase) {
+		let col = self.col.min(self.cols - 1);
+
+		match erase {
+			Erase::ToEnd => self.clear_cells(col, self.row, self.cols - col),
+			Erase::ToStart => self.clear_cells(0, self.row, col + 1),
+			Erase::All => self.clear_cells(0, self.row, self.cols),
+		}
+	}
+
+	fn erase_in_display(&mut self, erase: Erase) {
+		match erase {
+			Erase::ToEnd => {
+				self.erase_in_line(Erase::ToEnd);
+				self.clear_rows(self.row + 1, self.rows);
+			}
+			Erase::ToStart => {
+				self.clear_rows(0, self.row);
+				self.erase_in_line(Erase::ToStart);
+			}
+			Erase::All => self.clear_rows(0, self.rows),
+		}
+	}
+
+	/// Carry out whatever the ANSI parser asks for
+	fn apply(&mut self, action: Action) {
+		let (col, row) = (self.col, self.row);
+
+		match action {
+			Action::Print(c) => self.draw(c),
+			Action::Sgr(params) => {
+				for graphic in params.graphics() {
+					match graphic {
+						Graphic::Reset => {
+							self.fg.reset();
+							self.bg = Default::default();
+							self.bold = false;
+						}
+						Graphic::Bold(bold) => self.bold = bold,
+						Graphic::Foreground(color) => {
+							self.fg = color.to_pixel(CommonColors::White.into())
+						}
+						Graphic::Background(color) => {
+							self.bg = color.to_pixel(Default::default())
+						}
+					}
+				}
+			}
+			Action::CursorUp(n) => self.move_cursor(col, row.saturating_sub(n)),
+			Action::CursorDown(n) => {
+				self.move_cursor(col, row.saturating_add(n))
+			}
+			Action::CursorForward(n) => {
+				self.move_cursor(col.saturating_add(n), row)
+			}
+			Action::CursorBack(n) => {
+				self.move_cursor(col.saturating_sub(n), row)
+			}
+			Action::CursorPosition { row, col } => self.move_cursor(col, row),
+			Action::EraseInDisplay(erase) => self.erase_in_display(erase),
+			Action::EraseInLine(erase) => self.erase_in_line(erase),
+			Action::SaveCursor => self.saved_cursor = (col, row),
+			Action::RestoreCursor => {
+				let (col, row) = self.saved_cursor;
+				self.move_cursor(col, row);
+			}
+		}
+	}
+
+	pub fn draw(&mut self, c: char) {
+		match c {
+			'\n' => self.newline(),
+			'\r' => self.col = 0,
+			'\x08' => self.col = self.col.saturating_sub(1),
+			'\t' => {
+				let next_stop = (self.col / TAB_WIDTH + 1) * TAB_WIDTH;
+				for _ in self.col..next_stop.min(self.cols) {
+					self.draw(' ');
+				}
+			}
+			// other control characters have nothing to draw
+			c if c.is_control() => {}
+			_ => {
+				// wrapping is deferred until there's something to put on the
+				// next line, so text filling a row exactly doesn't leave a
+				// blank one behind
+				if self.col >= self.cols {
+					self.newline();
+				}
+
+				let index = self.index_of(self.col, self.row);
+				self.cells[index] = Cell {
+					c,
+					fg: self.fg,
+					bg: self.bg,
+					bold: self.bold,
+				};
+				self.damage_cells(self.col, self.row, 1, 1);
+				self.col += 1;
+			}
+		}
+	}
+
+	/// Flip the cursor on or off depending on the time, for blinking. This
+	/// runs from the timer interrupt, so it gives up if the framebuffer is
+	/// busy; the cursor catches up on the next update.
+	pub fn blink(&mut self, uptime_ms: u64) {
+		self.blink_on = uptime_ms / BLINK_INTERVAL_MS % 2 == 0;
+
+		if let Some(mut framebuffer) = FRAMEBUFFER.try_lock() {
+			if self.is_active() {
+				self.render(&mut framebuffer);
+			}
+		}
+	}
+
+	/// Draw whatever changed, if this is the terminal on screen
+	fn update(&mut self) {
+		let mut framebuffer = FRAMEBUFFER.lock();
+
+		if self.is_active() {
+			self.render(&mut framebuffer);
+		}
+	}
+
+	/// Draw everything from scratch, if this is the terminal on screen
+	fn refresh(&mut self) {
+		let mut framebuffer = FRAMEBUFFER.lock();
+
+		if self.is_active() {
+			self.redraw(&mut framebuffer);
+		}
+	}
+
+	/// Draw every cell from scratch
+	fn redraw(&mut self, framebuffer: &mut Framebuffer) {
+		framebuffer.clear(Default::default());
+		self.damage = None;
+		self.scrolled = 0;
+		self.drawn_cursor = None;
+
+		for row in 0..self.rows {
+			for col in 0..self.cols {
+				framebuffer.draw_cell(
+					col,
+					row,
+					self.view_cell(col, row),
+					false,
+				);
+			}
+		}
+
+		self.render(framebuffer);
+	}
+
+	fn render(&mut self, framebuffer: &mut Framebuffer) {
+		// while looking at the scrollback the screen stays put; it's all
+		// redrawn once the view goes back down
+		if self.view_offset > 0 {
+			self.damage = None;
+			self.scrolled = 0;
+			framebuffer.flush();
+			return;
+		}
+
+		let scrolled = core::mem::take(&mut self.scrolled);
+
+		// the old cursor moved up with everything else
+		if let Some((col, row)) = self.drawn_cursor.take() {
+			if row >= scrolled {
+				self.damage_cells(col, row - scrolled, 1, 1);
+			}
+		}
+		framebuffer.scroll_up(scrolled, self.rows);
+
+		if let Some(damage) = self.damage.take() {
+			let rows = damage.y0..damage.y1.min(self.rows as usize);
+			let cols = damage.x0..damage.x1.min(self.cols as usize);
+
+			for row in rows {
+				for col in cols.clone() {
+					let cell =
+						self.cells[self.index_of(col as u16, row as u16)];
+					framebuffer.draw_cell(col as u16, row as u16, cell, false);
+				}
+			}
+		}
+
+		if self.cursor_enabled && self.blink_on {
+			// after filling a row the cursor waits past its end for the wrap
+			let col = self.col.min(self.cols - 1);
+			let cell = self.cells[self.index_of(col, self.row)];
+
+			framebuffer.draw_cell(col, self.row, cell, true);
+			self.drawn_cursor = Some((col, self.row));
+		}
+
+		framebuffer.flush();
+	}
+}
+
+impl Write for Terminal {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let snapped = self.snap_to_bottom();
+
+		for c in s.chars() {
+			if let Some(action) = self.parser.advance(c) {
+				self.apply(action);
+			}
+		}
+
+		if snapped {
+			self.refresh();
+		} else {
+			self.update();
+		}
+		Ok(())
+	}
+}
+
+fn cell_pages(cells: usize) -> usize {
+	polyfill::div_up(cells * mem::size_of::<Cell>(), PAGE_SIZE)
+}
+
+/// Cells for a `cols` x `rows` grid and its scrollback, or just the grid if
+/// there isn't enough memory for both
+fn alloc_cells(cols: usize, rows: usize) -> &'static mut [Cell] {
+	let mut len = cols * (rows + SCROLLBACK_LINES);
+	let ptr = pmm::try_alloc_pages(cell_pages(len)).unwrap_or_else(|| {
+		len = cols * rows;
+		pmm::alloc_pages(cell_pages(len))
+	});
+
+	// SAFETY: the pages are ours alone and big enough for `len` cells
+	let cells = unsafe { slice::from_raw_parts_mut(ptr.cast::<Cell>(), len) };
+	cells.fill(Cell::EMPTY);
+	cells
+}
+
+/// Give cells from `alloc_cells` back
+fn free_cells(cells: &'static mut [Cell]) {
+	if !cells.is_empty() {
+		unsafe {
+			pmm::free_pages(cells.as_mut_ptr().cast(), cell_pages(cells.len()));
+		}
+	}
+}
+
+lazy_static! {
+	pub static ref TERMINALS: [Mutex<Terminal>; VT_COUNT] = {
+		let grid = FRAMEBUFFER.lock().grid_size();
+
+		[
+			Mutex::new(Terminal::new(0, grid)),
+			Mutex::new(Terminal::new(1, grid)),
+			Mutex::new(Terminal::new(2, grid)),
+			Mutex::new(Terminal::new(3, grid)),
+		]
+	};
+}
+
+/// The terminal kernel output goes to
+pub fn console() -> &'static Mutex<Terminal> {
+	&TERMINALS[KERNEL_VT]
+}
+
+/// Run `f` on the console. Interrupt handlers log to it, so the terminals
+/// and the framebuffer are only ever locked with interrupts off.
+pub fn with_console<T>(f: impl FnOnce(&mut Terminal) -> T) -> T {
+	cpu::without_interrupts(|| f(&mut console().lock()))
+}
+
+/// Index of the terminal on screen
+pub fn active() -> Option<usize> {
+	Some(ACTIVE.load(Ordering::SeqCst)).filter(|&index| index < VT_COUNT)
+}
+
+/// Stop drawing terminals, leaving the framebuffer to someone else (the boot
+/// splash) until the next `switch`. Output still lands in the terminals'
+/// cells, so nothing is lost.
+pub fn detach() {
+	cpu::without_interrupts(|| {
+		let _framebuffer = FRAMEBUFFER.lock();
+		ACTIVE.store(VT_COUNT, Ordering::SeqCst);
+	});
+}
+
+/// Let go of the framebuffer and every terminal, for the panic handler only
+pub unsafe fn force_unlock() {
+	FRAMEBUFFER.force_unlock();
+	for terminal in TERMINALS.iter() {
+		terminal.force_unlock();
+	}
+}
+
+/// Put terminal `index` on screen
+pub fn switch(index: usize) {
+	if index >= VT_COUNT {
+		return;
+	}
+
+	cpu::without_interrupts(|| {
+		let mut terminal = TERMINALS[index].lock();
+		let mut framebuffer = FRAMEBUFFER.lock();
+
+		ACTIVE.store(index, Ordering::SeqCst);
+		terminal.redraw(&mut framebuffer);
+	});
+}
+
+/// Act on the console's own key combinations: Alt+F1 to Alt+F4 switch
+/// terminals and Shift+PageUp/PageDown scroll the active one's history.
+/// Returns whether the key press was one of them.
+pub fn handle_hotkey(code: KeyCode, modifiers: Modifiers) -> bool {
+	if modifiers.alt() {
+		match code.function_number() {
+			Some(n) if n <= VT_COUNT => {
+				switch(n - 1);
+				return true;
+			}
+			_ => {}
+		}
+	}
+
+	if modifiers.shift() {
+		let rows = match active() {
+			Some(index) => cpu::without_interrupts(|| {
+				TERMINALS[index].lock().dimensions().1
+			}),
+			None => return false,
+		};
+		let page = (rows / 2) as isize;
+
+		match code {
+			KeyCode::PageUp => scroll_active(page),
+			KeyCode::PageDown => scroll_active(-page),
+			_ => return false,
+		}
+		return true;
+	}
+
+	false
+}
+
+/// `scroll_view` on whichever terminal is on screen
+pub fn scroll_active(lines: isize) {
+	if let Some(index) = active() {
+		cpu::without_interrupts(|| TERMINALS[index].lock().scroll_view(lines));
+	}
+}
+
+/// Fit every terminal to the framebuffer's grid again, which clears them
+fn resize_all() {
+	cpu::without_interrupts(|| {
+		let (cols, rows) = FRAMEBUFFER.lock().grid_size();
+
+		for terminal in TERMINALS.iter() {
+			terminal.lock().resize(cols, rows);
+		}
+	});
+
+	if let Some(index) = active() {
+		switch(index);
+	}
+}
+
+/// Switch every terminal to a different font
+pub fn set_font(font: Font<'static>) {
+	cpu::without_interrupts(|| FRAMEBUFFER.lock().set_font(font));
+	resize_all();
+}
+
+/// Change the glyph scale (`None` picks one from the screen size) for every
+/// terminal
+pub fn set_scale(scale: Option<usize>) {
+	cpu::without_interrupts(|| FRAMEBUFFER.lock().set_scale(scale));
+	resize_all();
+}
+
+/// Set up every terminal. This has to happen after the PMM is up (the cells
+/// come from it), and before the cursor starts blinking, since that runs in
+/// an interrupt handler.
+pub fn init() {
+	lazy_static::initialize(&TERMINALS);
+}
+
+/// Timer callback that blinks the active terminal's cursor
+pub fn blink_cursor(_ticks: u64) {
+	// if someone is drawing, the cursor gets put back when they're done
+	let terminal = match active() {
+		Some(index) => &TERMINALS[index],
+		None => return,
+	};
+
+	if let Some(mut terminal) = terminal.try_lock() {
+		terminal.blink(pit::uptime_ms());
+	}
+}
@@ -50,7 +50,16 @@ def build():
     sprint("Build is complete!")
 
 
+def ramdisk():
+    # blank for now, the kernel exposes it as ram0 for the block layer
+    _("dd if=/dev/zero of=build/ramdisk.img bs=1M count=4")
+    sprint("RAM disk is complete!")
+
+
 def hdd():
+    if not os.path.exists("build/ramdisk.img"):
+        ramdisk()
+
     _("dd if=/dev/zero of=build/bruhos.img bs=1M count=64")
     _("parted -s build/bruhos.img mklabel gpt")
     _("parted -s build/bruhos.img mkpart primary 2048s 100%")
@@ -61,6 +70,7 @@ def hdd():
     _("sudo mount $(cat loopback_dev)p1 isotmp/")
     _("sudo cp -Rf build/kernel.elf isotmp/")
     _("sudo cp -Rf run/limine.cfg isotmp/")
+    _("sudo cp -Rf build/ramdisk.img isotmp/")
     _("sync")
     _("sudo umount isotmp/")
     _("sudo losetup -d $(cat loopback_dev)")
@@ -70,7 +80,7 @@ def hdd():
 
 def run():
     _(
-        "qemu-system-x86_64 -m 2G -net none -smp 4 -drive format=raw,file=build/bruhos.img"
+        "qemu-system-x86_64 -m 2G -net none -smp 4 -drive format=raw,file=build/bruhos.img -serial stdio"
     )
     sprint("Run is complete!")
 
@@ -95,6 +105,7 @@ FLAGS:
 SUBCOMMANDS:
     clean    - clean out build files
     build    - build kernel
+    ramdisk  - create a blank RAM disk image
     hdd      - create and write to hard disk
     run      - emulate with qemu
     all      - clean, build, and run
@@ -109,6 +120,7 @@ def main():
         "build": build,
         "run": run,
         "help": help,
+        "ramdisk": ramdisk,
         "hdd": hdd,
     }
 